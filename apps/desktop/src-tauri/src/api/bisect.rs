@@ -0,0 +1,65 @@
+use gitfrisky_core::bisect;
+use gitfrisky_core::domain::types::{BisectMark, BisectStatus};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+
+// Bisect runs in progress by repository, so they can be cancelled
+#[derive(Default)]
+pub struct BisectRuns {
+    running: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+#[tauri::command]
+pub fn bisect_start(repo_path: String, bad: Option<String>, good: Vec<String>, pathspec: Vec<String>) -> Result<BisectStatus, String> {
+    bisect::bisect_start(&repo_path, bad.as_deref(), &good, &pathspec)
+}
+
+#[tauri::command]
+pub fn bisect_mark(repo_path: String, mark: BisectMark, oid: Option<String>) -> Result<BisectStatus, String> {
+    bisect::bisect_mark(&repo_path, mark, oid.as_deref())
+}
+
+#[tauri::command]
+pub fn bisect_status(repo_path: String) -> Result<BisectStatus, String> {
+    bisect::bisect_status(&repo_path)
+}
+
+// Steps are emitted as `bisect-step` events while the script runs. The
+// script may take a while per commit, so the run happens off the main thread.
+#[tauri::command]
+pub async fn bisect_run(app_handle: AppHandle, runs: tauri::State<'_, BisectRuns>, repo_path: String, command: String) -> Result<BisectStatus, String> {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    {
+        let mut running = runs.running.lock().unwrap();
+        if running.contains_key(&repo_path) {
+            return Err("A bisect run is already in progress".to_string());
+        }
+        running.insert(repo_path.clone(), cancelled.clone());
+    }
+
+    let path = repo_path.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        bisect::bisect_run(&path, &command, &cancelled, |step| {
+            let _ = app_handle.emit("bisect-step", step);
+        })
+    })
+    .await
+    .map_err(|e| e.to_string());
+    runs.running.lock().unwrap().remove(&repo_path);
+    result?
+}
+
+#[tauri::command]
+pub fn cancel_bisect_run(runs: tauri::State<BisectRuns>, repo_path: String) -> Result<(), String> {
+    if let Some(cancelled) = runs.running.lock().unwrap().get(&repo_path) {
+        cancelled.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn bisect_reset(repo_path: String) -> Result<(), String> {
+    bisect::bisect_reset(&repo_path)
+}
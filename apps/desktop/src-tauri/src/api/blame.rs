@@ -0,0 +1,12 @@
+use gitfrisky_core::blame;
+use gitfrisky_core::domain::types::{BlameOptions, BlameResult};
+
+#[tauri::command]
+pub fn blame(repo_path: String, path: String, rev: Option<String>, options: Option<BlameOptions>) -> Result<BlameResult, String> {
+    blame::blame(&repo_path, &path, rev.as_deref(), options)
+}
+
+#[tauri::command]
+pub fn blame_parent(repo_path: String, oid: String, path: String, options: Option<BlameOptions>) -> Result<BlameResult, String> {
+    blame::blame_parent(&repo_path, &oid, &path, options)
+}
@@ -0,0 +1,22 @@
+use gitfrisky_core::conflict;
+use gitfrisky_core::domain::types::{ConflictFile, ConflictResolution, ConflictStyle};
+
+#[tauri::command]
+pub fn list_conflicts(repo_path: String) -> Result<Vec<String>, String> {
+    conflict::list_conflicts(&repo_path)
+}
+
+#[tauri::command]
+pub fn get_conflict(repo_path: String, rel_path: String, style: Option<ConflictStyle>) -> Result<ConflictFile, String> {
+    conflict::get_conflict(&repo_path, &rel_path, style)
+}
+
+#[tauri::command]
+pub fn resolve_conflict(repo_path: String, rel_path: String, resolution: ConflictResolution) -> Result<(), String> {
+    conflict::resolve_conflict(&repo_path, &rel_path, resolution)
+}
+
+#[tauri::command]
+pub fn mark_resolved(repo_path: String, paths: Vec<String>) -> Result<(), String> {
+    conflict::mark_resolved(&repo_path, &paths)
+}
@@ -0,0 +1,7 @@
+use gitfrisky_core::domain::types::FileHistoryEntry;
+use gitfrisky_core::history;
+
+#[tauri::command]
+pub fn file_history(repo_path: String, path: String, rev: Option<String>, limit: Option<usize>) -> Result<Vec<FileHistoryEntry>, String> {
+    history::file_history(&repo_path, &path, rev.as_deref(), limit)
+}
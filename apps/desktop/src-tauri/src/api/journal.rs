@@ -0,0 +1,17 @@
+use gitfrisky_core::domain::types::{JournalEntry, JournalState};
+use gitfrisky_core::journal;
+
+#[tauri::command]
+pub fn journal(repo_path: String) -> Result<JournalState, String> {
+    journal::journal(&repo_path)
+}
+
+#[tauri::command]
+pub fn undo(repo_path: String) -> Result<Option<JournalEntry>, String> {
+    journal::undo(&repo_path)
+}
+
+#[tauri::command]
+pub fn redo(repo_path: String) -> Result<Option<JournalEntry>, String> {
+    journal::redo(&repo_path)
+}
@@ -1,2 +1,15 @@
+// Tauri commands: thin adapters over gitfrisky_core that own the app's state
+// (the file watcher, running searches) and turn callbacks into events
 pub mod repo;
 pub mod watch;
+pub mod conflict;
+pub mod rebase;
+pub mod sequencer;
+pub mod reset;
+pub mod journal;
+pub mod reflog;
+pub mod bisect;
+pub mod blame;
+pub mod history;
+pub mod search;
+pub mod tree;
@@ -0,0 +1,32 @@
+use gitfrisky_core::domain::types::{RebaseStatus, RebaseTodoEntry};
+use gitfrisky_core::rebase;
+
+#[tauri::command]
+pub fn rebase_plan(repo_path: String, upstream: String) -> Result<Vec<RebaseTodoEntry>, String> {
+    rebase::rebase_plan(&repo_path, &upstream)
+}
+
+#[tauri::command]
+pub fn rebase_start(repo_path: String, onto: String, todo: Vec<RebaseTodoEntry>) -> Result<RebaseStatus, String> {
+    rebase::rebase_start(&repo_path, &onto, &todo)
+}
+
+#[tauri::command]
+pub fn rebase_status(repo_path: String) -> Result<RebaseStatus, String> {
+    rebase::rebase_status(&repo_path)
+}
+
+#[tauri::command]
+pub fn rebase_continue(repo_path: String, message: Option<String>) -> Result<RebaseStatus, String> {
+    rebase::rebase_continue(&repo_path, message.as_deref())
+}
+
+#[tauri::command]
+pub fn rebase_skip(repo_path: String) -> Result<RebaseStatus, String> {
+    rebase::rebase_skip(&repo_path)
+}
+
+#[tauri::command]
+pub fn rebase_abort(repo_path: String) -> Result<RebaseStatus, String> {
+    rebase::rebase_abort(&repo_path)
+}
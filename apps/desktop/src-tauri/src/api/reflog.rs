@@ -0,0 +1,17 @@
+use gitfrisky_core::domain::types::{DanglingCommit, ReflogEntry};
+use gitfrisky_core::reflog;
+
+#[tauri::command]
+pub fn reflog(repo_path: String, ref_name: Option<String>) -> Result<Vec<ReflogEntry>, String> {
+    reflog::reflog(&repo_path, ref_name.as_deref())
+}
+
+#[tauri::command]
+pub fn dangling_commits(repo_path: String) -> Result<Vec<DanglingCommit>, String> {
+    reflog::dangling_commits(&repo_path)
+}
+
+#[tauri::command]
+pub fn restore_commit(repo_path: String, oid: String, branch_name: String) -> Result<String, String> {
+    reflog::restore_commit(&repo_path, &oid, &branch_name)
+}
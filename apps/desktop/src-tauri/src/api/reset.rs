@@ -0,0 +1,17 @@
+use gitfrisky_core::domain::types::{ResetMode, ResetResult};
+use gitfrisky_core::reset;
+
+#[tauri::command]
+pub fn reset(repo_path: String, target: String, mode: ResetMode, force: bool) -> Result<ResetResult, String> {
+    reset::reset(&repo_path, &target, mode, force)
+}
+
+#[tauri::command]
+pub fn checkout_commit(repo_path: String, target: String, force: bool) -> Result<String, String> {
+    reset::checkout_commit(&repo_path, &target, force)
+}
+
+#[tauri::command]
+pub fn checkout_paths(repo_path: String, target: String, paths: Vec<String>) -> Result<(), String> {
+    reset::checkout_paths(&repo_path, &target, &paths)
+}
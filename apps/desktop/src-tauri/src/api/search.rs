@@ -0,0 +1,25 @@
+use gitfrisky_core::domain::types::HistorySearch;
+use gitfrisky_core::search::SearchState;
+use tauri::{AppHandle, Emitter};
+
+// Returns the search id right away; matches stream as `history-search-match`
+// events and a final `history-search-done` event reports the outcome
+#[tauri::command]
+pub fn search_history(app_handle: AppHandle, state: tauri::State<SearchState>, repo_path: String, query: HistorySearch) -> Result<u64, String> {
+    let done_handle = app_handle.clone();
+    state.start(
+        &repo_path,
+        query,
+        move |found| {
+            let _ = app_handle.emit("history-search-match", found);
+        },
+        move |done| {
+            let _ = done_handle.emit("history-search-done", done);
+        },
+    )
+}
+
+#[tauri::command]
+pub fn cancel_search(state: tauri::State<SearchState>, search_id: u64) -> Result<(), String> {
+    state.cancel(search_id)
+}
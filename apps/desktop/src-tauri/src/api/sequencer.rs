@@ -0,0 +1,32 @@
+use gitfrisky_core::domain::types::{Commit, GitError, PickOptions, SequenceStatus};
+use gitfrisky_core::sequencer;
+
+#[tauri::command]
+pub fn cherry_pick(repo_path: String, oids: Vec<String>, options: Option<PickOptions>) -> Result<Vec<Commit>, GitError> {
+    sequencer::cherry_pick(&repo_path, &oids, options)
+}
+
+#[tauri::command]
+pub fn revert(repo_path: String, oids: Vec<String>, options: Option<PickOptions>) -> Result<Vec<Commit>, GitError> {
+    sequencer::revert(&repo_path, &oids, options)
+}
+
+#[tauri::command]
+pub fn sequencer_status(repo_path: String) -> Result<SequenceStatus, GitError> {
+    sequencer::sequencer_status(&repo_path)
+}
+
+#[tauri::command]
+pub fn sequencer_continue(repo_path: String) -> Result<Vec<Commit>, GitError> {
+    sequencer::sequencer_continue(&repo_path)
+}
+
+#[tauri::command]
+pub fn sequencer_skip(repo_path: String) -> Result<Vec<Commit>, GitError> {
+    sequencer::sequencer_skip(&repo_path)
+}
+
+#[tauri::command]
+pub fn sequencer_abort(repo_path: String) -> Result<(), GitError> {
+    sequencer::sequencer_abort(&repo_path)
+}
@@ -0,0 +1,12 @@
+use gitfrisky_core::domain::types::{BlobContent, TreeEntry};
+use gitfrisky_core::tree;
+
+#[tauri::command]
+pub fn list_tree(repo_path: String, rev: Option<String>, path: Option<String>) -> Result<Vec<TreeEntry>, String> {
+    tree::list_tree(&repo_path, rev.as_deref(), path.as_deref())
+}
+
+#[tauri::command]
+pub fn read_blob(repo_path: String, rev: Option<String>, path: String, max_bytes: Option<usize>) -> Result<BlobContent, String> {
+    tree::read_blob(&repo_path, rev.as_deref(), &path, max_bytes)
+}
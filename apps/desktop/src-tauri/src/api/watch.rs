@@ -1,132 +1,672 @@
-use notify::{Watcher, RecursiveMode, Event, EventKind};
-use notify_debouncer_full::{new_debouncer, DebounceEventResult};
+use gitfrisky_core::domain::types::{RepoState, StatusDelta, StatusPayload};
+use gitfrisky_core::operations::{finished_operations, is_own_write, RepoLayout};
+use gitfrisky_core::repo::map_repo_state;
+use gitfrisky_core::status_cache::StatusCache;
+use git2::{Index, Repository};
+use notify::event::ModifyKind;
+use notify::{Watcher, RecommendedWatcher, PollWatcher, RecursiveMode, Event, EventKind};
+use notify_debouncer_full::{new_debouncer, new_debouncer_opt, DebounceEventResult, Debouncer, FileIdMap};
+use std::fs;
 use std::path::{Path, PathBuf};
+use std::thread;
 use std::time::Duration;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 use tauri::{AppHandle, Emitter};
 use serde::{Serialize, Deserialize};
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
-#[serde(rename_all = "lowercase")]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
 pub enum WatchEventKind {
-    Status,   // Working tree changes
-    Head,     // HEAD changed (checkout)
-    Refs,     // Refs changed (branch/tag changes)
+    Status,     // Working tree changes
+    Head,       // HEAD changed (checkout)
+    Refs,       // Refs changed (branch/tag changes)
+    State,      // A merge, rebase, cherry-pick, revert or bisect started, stepped or finished
+    Stash,      // Stash pushed, popped or dropped
+    Config,     // Repository config edited
+    RemoteRefs, // Remote-tracking refs moved, e.g. after a fetch from a terminal
+    IndexLock,  // index.lock appeared or went away: another git process holds the index
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
+// Everything that changed in one debounce window
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
 pub struct WatchEvent {
-    pub kind: WatchEventKind,
+    pub kinds: Vec<WatchEventKind>,
+    pub paths: Vec<String>,    // Working tree paths, relative to the repo root
+    pub refs: Vec<String>,     // Refs that moved, e.g. "HEAD" or "refs/heads/main"
+    pub index_changed: bool,   // The index (or other repo metadata) changed, so any file's status may have
+    pub state: Option<RepoState>, // With the State kind: the operation now in progress, or clean
+    pub index_locked: Option<bool>, // With the IndexLock kind
+}
+
+// What a single changed path means for the repository
+enum Change {
+    WorkTree(String),
+    Index,
+    Head,
+    Ref(String),
+    PackedRefs,
+    Operation, // Files marking a merge, rebase, sequence or bisect in progress
+    Config,
+    IndexLock,
+}
+
+impl WatchEvent {
+    fn add(&mut self, change: Change) {
+        let kind = match change {
+            Change::WorkTree(path) => {
+                if !self.paths.contains(&path) {
+                    self.paths.push(path);
+                }
+                WatchEventKind::Status
+            }
+            Change::Index => {
+                self.index_changed = true;
+                WatchEventKind::Status
+            }
+            Change::Head => {
+                if !self.refs.iter().any(|r| r == "HEAD") {
+                    self.refs.push("HEAD".to_string());
+                }
+                WatchEventKind::Head
+            }
+            Change::Ref(name) => {
+                let kind = if name == "refs/stash" {
+                    WatchEventKind::Stash
+                } else if name.starts_with("refs/remotes/") || name == "FETCH_HEAD" {
+                    WatchEventKind::RemoteRefs
+                } else {
+                    WatchEventKind::Refs
+                };
+                if !self.refs.contains(&name) {
+                    self.refs.push(name);
+                }
+                kind
+            }
+            // Which refs moved isn't known without rereading the file
+            Change::PackedRefs => WatchEventKind::Refs,
+            Change::Operation => WatchEventKind::State,
+            Change::Config => WatchEventKind::Config,
+            Change::IndexLock => WatchEventKind::IndexLock,
+        };
+        if !self.kinds.contains(&kind) {
+            self.kinds.push(kind);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+}
+
+// Which notify backend is delivering events
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchBackend {
+    Native, // inotify, FSEvents or ReadDirectoryChangesW
+    Poll,   // Rescans on an interval; works on network filesystems and past OS watch limits
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchOptions {
+    #[serde(default)]
+    pub backend: Option<WatchBackend>, // Native unless the filesystem or OS limits rule it out
+    #[serde(default)]
+    pub poll_interval_ms: Option<u64>,
+}
+
+// Emitted as `watcher-status` when watching starts and whenever it degrades
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WatcherStatus {
+    pub backend: WatchBackend,
+    pub poll_interval_ms: Option<u64>,
+    pub degraded: bool, // Polling when native events were wanted
+    pub reason: Option<String>,
+}
+
+const DEBOUNCE: Duration = Duration::from_millis(300);
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+// Git dir subdirectories holding an interactive rebase's or a sequence's steps
+const POLLED_OPERATION_DIRS: [&str; 2] = ["rebase-merge", "sequencer"];
+
+enum ActiveWatcher {
+    Native(Debouncer<RecommendedWatcher, FileIdMap>),
+    Poll(Debouncer<PollWatcher, FileIdMap>),
+}
+
+impl ActiveWatcher {
+    fn watcher(&mut self) -> &mut dyn Watcher {
+        match self {
+            Self::Native(debouncer) => debouncer.watcher(),
+            Self::Poll(debouncer) => debouncer.watcher(),
+        }
+    }
 }
 
 // Global state to track active watchers
-type WatcherHandle = Arc<Mutex<Option<notify_debouncer_full::Debouncer<notify::RecommendedWatcher, notify_debouncer_full::FileIdMap>>>>;
+type WatcherHandle = Arc<Mutex<Option<ActiveWatcher>>>;
+type StatusHandle = Arc<Mutex<Option<WatcherStatus>>>;
+type CacheHandle = Arc<Mutex<Option<StatusCache>>>;
 
 pub struct WatcherState {
-    pub watcher: WatcherHandle,
+    watcher: WatcherHandle,
+    status: StatusHandle,
+    cache: CacheHandle, // Working tree status of the watched repository
+    generation: Arc<AtomicU64>, // Bumped on every start/stop, so stale restarts can tell
 }
 
 impl WatcherState {
     pub fn new() -> Self {
         Self {
             watcher: Arc::new(Mutex::new(None)),
+            status: Arc::new(Mutex::new(None)),
+            cache: Arc::new(Mutex::new(None)),
+            generation: Arc::new(AtomicU64::new(0)),
         }
     }
+
+    // Status of the repository at `repo_path` from the cache, if it's the one being watched
+    pub fn cached_status(&self, repo_path: &str) -> Result<Option<StatusPayload>, String> {
+        let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+        let mut cache = self.cache.lock().unwrap();
+        let Some(cache) = cache.as_mut().filter(|c| c.git_dir == RepoLayout::resolve(&repo).git_dir) else {
+            return Ok(None);
+        };
+        // Our own operations' writes aren't reported by the watcher
+        let operations = finished_operations();
+        if cache.operations != operations {
+            cache.rescan(&repo, operations)?;
+        }
+        Ok(Some(cache.payload()))
+    }
 }
 
-#[tauri::command]
-pub fn start_watch(app_handle: AppHandle, repo_path: String, state: tauri::State<WatcherState>) -> Result<(), String> {
-    let path = PathBuf::from(&repo_path);
+impl Default for WatcherState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-    if !path.exists() {
-        return Err("Repository path does not exist".to_string());
+// What's needed to (re)start watching one repository
+#[derive(Clone)]
+struct WatchTarget {
+    app_handle: AppHandle,
+    path: PathBuf,
+    poll_interval: Duration,
+    watcher: Weak<Mutex<Option<ActiveWatcher>>>,
+    status: Weak<Mutex<Option<WatcherStatus>>>,
+    cache: Weak<Mutex<Option<StatusCache>>>,
+    generation: Arc<AtomicU64>,
+    started_in: u64,
+}
+
+impl WatchTarget {
+    fn is_current(&self) -> bool {
+        self.generation.load(Ordering::SeqCst) == self.started_in
     }
 
-    // Stop any existing watcher
-    stop_watch(state.clone())?;
+    fn report(&self, status: &WatcherStatus) {
+        if let Some(current) = self.status.upgrade() {
+            *current.lock().unwrap() = Some(status.clone());
+        }
+        let _ = self.app_handle.emit("watcher-status", status);
+    }
+
+    // Bring the status cache up to date, re-checking just `paths` if given,
+    // and push what changed to the UI as `status-changed`
+    fn update_status(&self, repo: &Repository, layout: &RepoLayout, paths: Option<&[String]>) {
+        let Some(handle) = self.cache.upgrade() else { return };
+        let mut cache = handle.lock().unwrap();
+        if !self.is_current() {
+            return;
+        }
+
+        let operations = finished_operations();
+        let result = match cache.as_mut().filter(|c| c.git_dir == layout.git_dir) {
+            // Own operations since the last update went unreported, so re-checking paths isn't enough
+            Some(cached) => match paths {
+                Some(paths) if cached.operations == operations => cached.update(repo, paths, operations),
+                _ => cached.rescan(repo, operations),
+            },
+            // Nothing to compare against yet; the UI asks for the full status itself
+            None => StatusCache::scan(repo, layout.git_dir.clone(), operations).map(|scanned| {
+                *cache = Some(scanned);
+                StatusDelta::default()
+            }),
+        };
+        match result {
+            Ok(delta) if !delta.paths.is_empty() => {
+                let _ = self.app_handle.emit("status-changed", delta);
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("Failed to update status: {}", e),
+        }
+    }
+}
+
+// Whether a change may have altered the status of files nobody touched: the
+// index, HEAD, the commit it points to or ignore rules changed
+fn needs_rescan(event: &WatchEvent, repo: &Repository) -> bool {
+    if event.index_changed || event.kinds.iter().any(|k| matches!(k, WatchEventKind::Head | WatchEventKind::Config)) {
+        return true;
+    }
+    let head = repo.find_reference("HEAD").ok().and_then(|r| r.symbolic_target().map(String::from));
+    head.is_some_and(|head| event.refs.contains(&head))
+}
 
-    let app_handle_clone = app_handle.clone();
-    let repo_path_clone = repo_path.clone();
-
-    // Create debounced watcher with 300ms debounce
-    let mut debouncer = new_debouncer(
-        Duration::from_millis(300),
-        None,
-        move |result: DebounceEventResult| {
-            match result {
-                Ok(events) => {
-                    for event in events {
-                        if let Some(kind) = classify_event(&event.event, &repo_path_clone) {
-                            let watch_event = WatchEvent { kind };
-                            let _ = app_handle_clone.emit("repo-changed", watch_event);
+// Out of inotify watches (ENOSPC) or instances (EMFILE)
+fn is_watch_limit(error: &notify::Error) -> bool {
+    match &error.kind {
+        notify::ErrorKind::MaxFilesWatch => true,
+        notify::ErrorKind::Io(e) => matches!(e.raw_os_error(), Some(24) | Some(28)),
+        _ => false,
+    }
+}
+
+// Filesystem type of `path` if it's a network mount, where native events
+// don't report changes made from other machines. Linux only.
+fn network_filesystem(path: &Path) -> Option<String> {
+    let path = fs::canonicalize(path).ok()?;
+    let mounts = fs::read_to_string("/proc/mounts").ok()?;
+    let mount = mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let _device = fields.next()?;
+            let point = fields.next()?.replace("\\040", " ");
+            let fs_type = fields.next()?;
+            path.starts_with(&point).then(|| (point.len(), fs_type.to_string()))
+        })
+        .max_by_key(|(len, _)| *len)?;
+
+    let fs_type = mount.1;
+    let network = ["nfs", "nfs4", "cifs", "smb3", "smbfs", "9p", "afs", "fuse.sshfs", "ceph", "glusterfs"];
+    network.contains(&fs_type.as_str()).then_some(fs_type)
+}
+
+// Decides which working tree changes git status would never report
+struct IgnoreFilter {
+    repo: Repository,
+}
+
+impl IgnoreFilter {
+    fn open(path: &Path) -> Result<Self, String> {
+        Ok(Self { repo: Repository::open(path).map_err(|e| e.to_string())? })
+    }
+
+    // The index as it is on disk now, to tell tracked files apart
+    fn index(&self) -> Option<Index> {
+        let mut index = self.repo.index().ok()?;
+        let _ = index.read(false);
+        Some(index)
+    }
+
+    // Ignored by .gitignore, info/exclude or core.excludesFile, and not
+    // tracked (ignore rules don't apply to files already in the index)
+    fn is_ignored(&self, rel_path: &str, index: Option<&Index>) -> bool {
+        if !self.repo.is_path_ignored(rel_path).unwrap_or(false) {
+            return false;
+        }
+        let tracked = index.is_some_and(|index| {
+            index.get_path(Path::new(rel_path), 0).is_some() || index.find_prefix(format!("{}/", rel_path)).is_ok()
+        });
+        !tracked
+    }
+}
+
+// Watch `dir` and every directory under it that isn't ignored
+fn watch_tree(watcher: &mut dyn Watcher, filter: &IgnoreFilter, index: Option<&Index>, root: &Path, dir: &Path) -> notify::Result<()> {
+    let rel_path = dir.strip_prefix(root).ok().and_then(|p| p.to_str()).unwrap_or("");
+    if !rel_path.is_empty() && filter.is_ignored(rel_path, index) {
+        return Ok(());
+    }
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        // Symlinks aren't followed, and .git is watched on its own
+        if entry.file_type().is_ok_and(|t| t.is_dir()) && entry.file_name() != ".git" {
+            watch_tree(watcher, filter, index, root, &entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+// Handles one debounce window's events: filters and coalesces them into a
+// `repo-changed` event and keeps watches in step with new directories
+fn event_handler(target: WatchTarget, backend: WatchBackend, filter: Arc<Mutex<IgnoreFilter>>, layout: RepoLayout) -> impl FnMut(DebounceEventResult) + Send + 'static {
+    let mut index_locked = false;
+    let mut downgraded = false;
+
+    move |result: DebounceEventResult| {
+        let mut limit_reached = None;
+
+        match result {
+            Ok(events) => {
+                let filter = filter.lock().unwrap();
+                let index = filter.index();
+
+                // One event per debounce window, however many files were touched
+                let mut watch_event = WatchEvent::default();
+                let mut lock_touched = false;
+                for event in &events {
+                    // Our own operations' writes are refreshed by whoever ran them
+                    let mut event = event.event.clone();
+                    event.paths.retain(|path| !is_own_write(path));
+                    for change in classify_event(&event, &layout) {
+                        match &change {
+                            Change::WorkTree(rel) if filter.is_ignored(rel, index.as_ref()) => continue,
+                            Change::IndexLock => {
+                                lock_touched = true;
+                                continue;
+                            }
+                            _ => {}
+                        }
+                        watch_event.add(change);
+                    }
+                }
+
+                // Every git command takes the lock briefly; only report it
+                // when it's still held once things settle, and when it's released
+                if lock_touched {
+                    let locked = layout.git_dir.join("index.lock").exists();
+                    if locked != index_locked {
+                        watch_event.add(Change::IndexLock);
+                        watch_event.index_locked = Some(locked);
+                        index_locked = locked;
+                    }
+                }
+                if watch_event.kinds.contains(&WatchEventKind::State) {
+                    watch_event.state = Some(map_repo_state(filter.repo.state()));
+                }
+
+                if needs_rescan(&watch_event, &filter.repo) {
+                    target.update_status(&filter.repo, &layout, None);
+                } else if !watch_event.paths.is_empty() {
+                    target.update_status(&filter.repo, &layout, Some(&watch_event.paths));
+                }
+
+                // Directories that appeared need watches of their own, unless
+                // the OS watches whole trees
+                if let (true, Some(workdir)) = (cfg!(target_os = "linux") || backend == WatchBackend::Poll, &layout.workdir) {
+                    let new_dirs = events
+                        .iter()
+                        .filter(|e| matches!(e.event.kind, EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_))))
+                        .flat_map(|e| &e.event.paths)
+                        .filter(|p| p.is_dir() && p.starts_with(workdir) && !p.starts_with(&layout.common_dir));
+                    let handle = target.watcher.upgrade();
+                    let mut active = handle.as_ref().and_then(|h| h.lock().ok());
+                    if let Some(active) = active.as_mut().and_then(|a| a.as_mut()) {
+                        for dir in new_dirs {
+                            // It may be gone again already; its parent's events still cover it
+                            match watch_tree(active.watcher(), &filter, index.as_ref(), workdir, dir) {
+                                Err(e) if is_watch_limit(&e) => limit_reached = Some(e),
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+
+                // Polling only rescans git dirs where it was told to; operation
+                // dirs come and go, so pick them up as they appear
+                if backend == WatchBackend::Poll {
+                    let operation_dirs = events
+                        .iter()
+                        .filter(|e| matches!(e.event.kind, EventKind::Create(_)))
+                        .flat_map(|e| &e.event.paths)
+                        .filter(|p| p.is_dir() && POLLED_OPERATION_DIRS.iter().any(|sub| **p == layout.git_dir.join(sub)));
+                    let handle = target.watcher.upgrade();
+                    let mut active = handle.as_ref().and_then(|h| h.lock().ok());
+                    if let Some(active) = active.as_mut().and_then(|a| a.as_mut()) {
+                        for dir in operation_dirs {
+                            let _ = active.watcher().watch(dir, RecursiveMode::Recursive);
                         }
                     }
                 }
-                Err(errors) => {
-                    eprintln!("Watch errors: {:?}", errors);
+
+                if !watch_event.is_empty() {
+                    let _ = target.app_handle.emit("repo-changed", watch_event);
+                }
+            }
+            Err(errors) => {
+                eprintln!("Watch errors: {:?}", errors);
+                limit_reached = errors.into_iter().find(is_watch_limit);
+            }
+        }
+
+        // Out of OS watches: changes in new directories would go unnoticed,
+        // so switch to polling. Restarting from this thread would drop the
+        // debouncer that's running us, hence the new thread.
+        if let (Some(error), WatchBackend::Native, false) = (limit_reached, backend, downgraded) {
+            downgraded = true;
+            let target = target.clone();
+            thread::spawn(move || {
+                if !target.is_current() {
+                    return;
+                }
+                if let Some(active) = target.watcher.upgrade() {
+                    *active.lock().unwrap() = None;
+                }
+                let reason = format!("File watch limit reached ({}); polling for changes instead", error);
+                if let Err(e) = start(&target, WatchBackend::Poll, Some(reason)) {
+                    eprintln!("Failed to fall back to polling: {}", e);
+                }
+            });
+        }
+    }
+}
+
+// Register watches for the working tree and git dirs
+fn add_watches(watcher: &mut dyn Watcher, filter: &IgnoreFilter, layout: &RepoLayout, backend: WatchBackend) -> notify::Result<()> {
+    if let Some(workdir) = &layout.workdir {
+        // inotify watches are per directory anyway, so add them ourselves and skip
+        // ignored directories like node_modules/ and target/; polling the same
+        // way avoids rescanning them. Elsewhere the OS watches a whole tree with one handle.
+        if cfg!(target_os = "linux") || backend == WatchBackend::Poll {
+            watch_tree(watcher, filter, filter.index().as_ref(), workdir, workdir)?;
+        } else {
+            // Watch the entire repository directory
+            watcher.watch(workdir, RecursiveMode::Recursive)?;
+        }
+    }
+
+    // Watch the git dir, and the common dir it shares refs with if that's elsewhere
+    let mut git_dirs = vec![&layout.common_dir];
+    if layout.git_dir != layout.common_dir && (backend == WatchBackend::Poll || !layout.git_dir.starts_with(&layout.common_dir)) {
+        git_dirs.push(&layout.git_dir);
+    }
+    for dir in git_dirs {
+        match backend {
+            WatchBackend::Native => watcher.watch(dir, RecursiveMode::Recursive)?,
+            // Rescanning objects/ on every poll would be slow; what matters is
+            // at the top level, under refs, or in the state of an operation in
+            // progress (the handler adds those dirs when they appear later)
+            WatchBackend::Poll => {
+                watcher.watch(dir, RecursiveMode::NonRecursive)?;
+                for sub in ["refs", "logs/refs"].iter().chain(&POLLED_OPERATION_DIRS) {
+                    if dir.join(sub).is_dir() {
+                        watcher.watch(&dir.join(sub), RecursiveMode::Recursive)?;
+                    }
                 }
             }
-        },
-    ).map_err(|e| format!("Failed to create watcher: {}", e))?;
+        }
+    }
+    Ok(())
+}
 
-    // Watch the entire repository directory
-    debouncer
-        .watcher()
-        .watch(&path, RecursiveMode::Recursive)
-        .map_err(|e| format!("Failed to watch repository: {}", e))?;
+// Start watching with `backend`, falling back to polling if native watches
+// run out. `reason` explains why this backend was chosen over the native one.
+fn start(target: &WatchTarget, backend: WatchBackend, reason: Option<String>) -> Result<WatcherStatus, String> {
+    let handle = target.watcher.upgrade().ok_or("Watcher was shut down")?;
+    // One repository handle for the handler, the initial watches and the first status
+    let filter = Arc::new(Mutex::new(IgnoreFilter::open(&target.path)?));
+    let layout = RepoLayout::resolve(&filter.lock().unwrap().repo);
+    let handler = event_handler(target.clone(), backend, filter.clone(), layout.clone());
 
-    // Watch .git directory specifically
-    let git_dir = path.join(".git");
-    if git_dir.exists() {
-        debouncer
-            .watcher()
-            .watch(&git_dir, RecursiveMode::Recursive)
-            .map_err(|e| format!("Failed to watch .git directory: {}", e))?;
+    let created = match backend {
+        WatchBackend::Native => new_debouncer(DEBOUNCE, None, handler).map(ActiveWatcher::Native),
+        WatchBackend::Poll => {
+            let config = notify::Config::default().with_poll_interval(target.poll_interval);
+            new_debouncer_opt(DEBOUNCE, None, handler, FileIdMap::new(), config).map(ActiveWatcher::Poll)
+        }
+    };
+    let mut active = match created {
+        Ok(active) => active,
+        Err(e) if backend == WatchBackend::Native && is_watch_limit(&e) => {
+            return start(target, WatchBackend::Poll, Some(format!("No file watches available ({}); polling for changes instead", e)));
+        }
+        Err(e) => return Err(format!("Failed to create watcher: {}", e)),
+    };
+
+    let added = add_watches(active.watcher(), &filter.lock().unwrap(), &layout, backend);
+    if let Err(e) = added {
+        drop(active); // Give back whatever watches it did get
+        if backend == WatchBackend::Native && is_watch_limit(&e) {
+            return start(target, WatchBackend::Poll, Some(format!("File watch limit reached ({}); polling for changes instead", e)));
+        }
+        return Err(format!("Failed to watch repository: {}", e));
     }
 
     // Store the watcher so it stays alive
-    *state.watcher.lock().unwrap() = Some(debouncer);
+    *handle.lock().unwrap() = Some(active);
 
-    Ok(())
+    // Whatever changed while nothing was watching (say, while switching to
+    // polling) would otherwise go unnoticed until the next rescan
+    target.update_status(&filter.lock().unwrap().repo, &layout, None);
+
+    let status = WatcherStatus {
+        backend,
+        poll_interval_ms: (backend == WatchBackend::Poll).then_some(target.poll_interval.as_millis() as u64),
+        degraded: reason.is_some(),
+        reason,
+    };
+    target.report(&status);
+    Ok(status)
+}
+
+#[tauri::command]
+pub fn start_watch(app_handle: AppHandle, repo_path: String, state: tauri::State<WatcherState>, options: Option<WatchOptions>) -> Result<WatcherStatus, String> {
+    let path = PathBuf::from(&repo_path);
+
+    if !path.exists() {
+        return Err("Repository path does not exist".to_string());
+    }
+
+    // Stop any existing watcher
+    stop_watch(state.clone())?;
+
+    let options = options.unwrap_or_default();
+    let target = WatchTarget {
+        app_handle,
+        path: path.clone(),
+        poll_interval: options.poll_interval_ms.map(Duration::from_millis).unwrap_or(DEFAULT_POLL_INTERVAL),
+        watcher: Arc::downgrade(&state.watcher),
+        status: Arc::downgrade(&state.status),
+        cache: Arc::downgrade(&state.cache),
+        generation: state.generation.clone(),
+        started_in: state.generation.fetch_add(1, Ordering::SeqCst) + 1,
+    };
+
+    let (backend, reason) = match (options.backend, network_filesystem(&path)) {
+        (Some(backend), _) => (backend, None),
+        (None, Some(fs_type)) => (WatchBackend::Poll, Some(format!("Repository is on a network filesystem ({}); polling for changes", fs_type))),
+        (None, None) => (WatchBackend::Native, None),
+    };
+    start(&target, backend, reason)
 }
 
 #[tauri::command]
 pub fn stop_watch(state: tauri::State<WatcherState>) -> Result<(), String> {
+    state.generation.fetch_add(1, Ordering::SeqCst);
     let mut watcher = state.watcher.lock().unwrap();
     *watcher = None; // Dropping the watcher stops watching
+    *state.status.lock().unwrap() = None;
+    *state.cache.lock().unwrap() = None; // It would miss changes from here on
     Ok(())
 }
 
-// Classify file system events into watch event types
-fn classify_event(event: &Event, repo_path: &str) -> Option<WatchEventKind> {
-    let repo_path = Path::new(repo_path);
+// How the repository is being watched, or None when it isn't
+#[tauri::command]
+pub fn watcher_status(state: tauri::State<WatcherState>) -> Result<Option<WatcherStatus>, String> {
+    Ok(state.status.lock().unwrap().clone())
+}
 
-    for path in &event.paths {
-        let relative_path = path.strip_prefix(repo_path).ok()?;
-        let path_str = relative_path.to_str()?;
+// What a change to `rel_path` inside a git dir means. `shared` is for the
+// common dir of a linked worktree, where HEAD, the index and in-progress
+// operations belong to the main worktree rather than this one.
+fn classify_git_path(rel_path: &str, shared: bool) -> Option<Change> {
+    if rel_path == "index.lock" && !shared {
+        return Some(Change::IndexLock);
+    }
 
-        // Check for .git/HEAD changes (branch checkout)
-        if path_str == ".git/HEAD" || path_str.contains(".git/HEAD") {
-            return Some(WatchEventKind::Head);
-        }
+    // Git writes HEAD, refs and the index through lock files renamed into place
+    let rel_path = rel_path.strip_suffix(".lock").unwrap_or(rel_path);
+    let in_dir = |dir: &str| rel_path == dir || rel_path.starts_with(&format!("{}/", dir));
 
-        // Check for .git/refs/ changes (branch/tag changes)
-        if path_str.starts_with(".git/refs/") {
-            return Some(WatchEventKind::Refs);
+    match rel_path {
+        // Branch checkout
+        "HEAD" if !shared => Some(Change::Head),
+        // Branch/tag changes; packed-refs is rewritten when refs are packed or deleted
+        "packed-refs" => Some(Change::PackedRefs),
+        _ if rel_path.starts_with("refs/") => Some(Change::Ref(rel_path.to_string())),
+        // Stash push/drop only rewrites the stash reflog when older entries remain
+        "logs/refs/stash" => Some(Change::Ref("refs/stash".to_string())),
+        "FETCH_HEAD" => Some(Change::Ref(rel_path.to_string())),
+        "ORIG_HEAD" if !shared => Some(Change::Ref(rel_path.to_string())),
+        // Merges, rebases, cherry-pick and revert sequences, bisects
+        "MERGE_HEAD" | "CHERRY_PICK_HEAD" | "REVERT_HEAD" | "BISECT_LOG" if !shared => Some(Change::Operation),
+        _ if !shared && (in_dir("rebase-merge") || in_dir("rebase-apply") || in_dir("sequencer")) => Some(Change::Operation),
+        _ if in_dir("objects") || in_dir("logs") || rel_path.contains("/objects/") || rel_path.contains("/logs/") => None,
+        // The operation journal
+        _ if in_dir("gitfrisky") => None,
+        // Other worktrees' private state
+        _ if shared && in_dir("worktrees") => None,
+        "config" | "config.worktree" => Some(Change::Config),
+        "info/exclude" => Some(Change::Index),
+        // Anything else, like the index, can affect status
+        _ if !shared => Some(Change::Index),
+        _ => None,
+    }
+}
+
+// Classify file system events into the repository changes they represent
+fn classify_event(event: &Event, layout: &RepoLayout) -> Vec<Change> {
+    let mut changes = vec![];
+
+    // Reads (including our own) show up as access events; only writes matter
+    if matches!(event.kind, EventKind::Access(_)) {
+        return changes;
+    }
+
+    for path in &event.paths {
+        let relative = |base: &Path| path.strip_prefix(base).ok().and_then(|p| p.to_str()).map(|p| p.replace('\\', "/"));
+
+        // The git dir is checked first: it's usually inside the working tree,
+        // and a linked worktree's is inside the common dir
+        let git_path = relative(&layout.git_dir)
+            .map(|p| (p, false))
+            .or_else(|| relative(&layout.common_dir).map(|p| (p, true)));
+        if let Some((rel_path, shared)) = git_path {
+            // Polling reports directories whose entries changed; for refs the files say which
+            if !(rel_path.starts_with("refs") && path.is_dir()) {
+                changes.extend(classify_git_path(&rel_path, shared));
+            }
+            continue;
         }
+        let Some(path_str) = layout.workdir.as_deref().and_then(relative) else { continue };
 
-        // Check for .git/index changes (staging)
-        if path_str == ".git/index" || path_str.contains(".git/index") {
-            return Some(WatchEventKind::Status);
+        // New ignore rules can change the status of any file
+        if path_str == ".gitignore" || path_str.ends_with("/.gitignore") {
+            changes.push(Change::Index);
         }
 
-        // Working tree file changes (but ignore .git/objects/)
-        if !path_str.starts_with(".git/objects/") &&
-           !path_str.starts_with(".git/logs/") &&
-           matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
-            return Some(WatchEventKind::Status);
+        // Working tree file changes
+        if !path_str.is_empty() && path_str != ".git" && matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+            changes.push(Change::WorkTree(path_str));
         }
     }
 
-    None
+    changes
 }
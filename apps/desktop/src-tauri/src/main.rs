@@ -1,6 +1,5 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod domain;
 mod api;
 
 fn main() {
@@ -8,18 +7,61 @@ fn main() {
     .plugin(tauri_plugin_shell::init())
     .plugin(tauri_plugin_dialog::init())
     .manage(api::watch::WatcherState::new())
+    .manage(gitfrisky_core::search::SearchState::new())
+    .manage(api::bisect::BisectRuns::default())
     .invoke_handler(tauri::generate_handler![
       api::repo::open_repo,
       api::repo::status,
+      api::repo::status_paths,
       api::repo::get_diff,
       api::repo::stage,
       api::repo::unstage,
       api::repo::commit,
+      api::repo::merge_message,
       api::repo::log,
       api::repo::stage_hunk,
       api::repo::unstage_hunk,
+      api::conflict::list_conflicts,
+      api::conflict::get_conflict,
+      api::conflict::resolve_conflict,
+      api::conflict::mark_resolved,
+      api::rebase::rebase_plan,
+      api::rebase::rebase_start,
+      api::rebase::rebase_status,
+      api::rebase::rebase_continue,
+      api::rebase::rebase_skip,
+      api::rebase::rebase_abort,
+      api::sequencer::cherry_pick,
+      api::sequencer::revert,
+      api::sequencer::sequencer_status,
+      api::sequencer::sequencer_continue,
+      api::sequencer::sequencer_skip,
+      api::sequencer::sequencer_abort,
+      api::reset::reset,
+      api::reset::checkout_commit,
+      api::reset::checkout_paths,
+      api::journal::journal,
+      api::journal::undo,
+      api::journal::redo,
+      api::reflog::reflog,
+      api::reflog::dangling_commits,
+      api::reflog::restore_commit,
+      api::bisect::bisect_start,
+      api::bisect::bisect_mark,
+      api::bisect::bisect_status,
+      api::bisect::bisect_run,
+      api::bisect::cancel_bisect_run,
+      api::bisect::bisect_reset,
+      api::blame::blame,
+      api::blame::blame_parent,
+      api::history::file_history,
+      api::search::search_history,
+      api::search::cancel_search,
+      api::tree::list_tree,
+      api::tree::read_blob,
       api::watch::start_watch,
       api::watch::stop_watch,
+      api::watch::watcher_status,
     ])
     .run(tauri::generate_context!())
     .expect("error while running GitFrisky");
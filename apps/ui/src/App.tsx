@@ -11,6 +11,10 @@ import { CommitGraph } from './components/graph/CommitGraph'
 import { CommitDetails } from './components/graph/CommitDetails'
 import { useRepoStore } from './stores/repo'
 import { ipc } from './lib/ipc'
+import type { DiffViewOptions, FileDiff } from '@gitfrisky/shared-types'
+
+// Diff lines requested at first; "Load more" asks for more
+const DEFAULT_DIFF_LINES = 5000
 
 function App() {
   const [commandPaletteOpen, setCommandPaletteOpen] = useState(false)
@@ -31,7 +35,9 @@ function App() {
   } = useRepoStore()
 
   // Diff state for selected file
-  const [diffHunks, setDiffHunks] = useState<any[]>([])
+  const [fileDiff, setFileDiff] = useState<FileDiff | null>(null)
+  const [diffLineLimit, setDiffLineLimit] = useState(DEFAULT_DIFF_LINES)
+  const [diffOptions, setDiffOptions] = useState<DiffViewOptions>({})
   const [loadingDiff, setLoadingDiff] = useState(false)
 
   // View mode: 'working-tree' (FileTree) or 'commit-details' (CommitDetails)
@@ -105,7 +111,7 @@ function App() {
   // Load diff when file selected
   useEffect(() => {
     if (!selectedFile || !repoPath) {
-      setDiffHunks([])
+      setFileDiff(null)
       return
     }
 
@@ -115,8 +121,9 @@ function App() {
     const loadDiff = async () => {
       setLoadingDiff(true)
       try {
-        const hunks = await ipc.getDiff(repoPath, selectedFile.path, selectedFileStaged)
-        setDiffHunks(hunks)
+        const diff = await ipc.getDiff(repoPath, selectedFile.path, selectedFileStaged, DEFAULT_DIFF_LINES, 'word', diffOptions)
+        setDiffLineLimit(DEFAULT_DIFF_LINES)
+        setFileDiff(diff)
       } catch (error) {
         console.error('Failed to load diff:', error)
         toast.error('Failed to load diff')
@@ -126,7 +133,7 @@ function App() {
     }
 
     loadDiff()
-  }, [selectedFile, selectedFileStaged, repoPath])
+  }, [selectedFile, selectedFileStaged, repoPath, diffOptions])
 
   // Handle file selection with toggle behavior
   const handleSelectFile = (file: any, isStaged: boolean) => {
@@ -202,7 +209,7 @@ function App() {
       setSelectedCommitOid(null)
       setSelectedCommit(null)
       setInspectorVisible(false)
-      setDiffHunks([])
+      setFileDiff(null)
 
       toast.success('Closed repository')
     } catch (error) {
@@ -281,12 +288,27 @@ function App() {
     }
   }
 
+  // Fetch more lines of a diff that was cut off
+  const handleLoadMoreDiff = async () => {
+    if (!repoPath || !selectedFile) return
+
+    try {
+      const limit = diffLineLimit * 4
+      const diff = await ipc.getDiff(repoPath, selectedFile.path, selectedFileStaged, limit, 'word', diffOptions)
+      setDiffLineLimit(limit)
+      setFileDiff(diff)
+    } catch (error) {
+      const msg = error instanceof Error ? error.message : String(error)
+      toast.error(`Failed to load diff: ${msg}`)
+    }
+  }
+
   // Handle hunk staging
   const handleStageHunk = async (hunk: any) => {
     if (!repoPath || !selectedFile) return
 
     try {
-      await ipc.stageHunk(repoPath, selectedFile.path, hunk)
+      await ipc.stageHunk(repoPath, selectedFile.path, hunk, diffOptions)
       toast.success('Hunk staged')
 
       // Refresh status and diff
@@ -297,8 +319,8 @@ function App() {
       })
 
       // Reload diff to show updated hunks
-      const hunks = await ipc.getDiff(repoPath, selectedFile.path, selectedFileStaged)
-      setDiffHunks(hunks)
+      const diff = await ipc.getDiff(repoPath, selectedFile.path, selectedFileStaged, diffLineLimit, 'word', diffOptions)
+      setFileDiff(diff)
     } catch (error) {
       const msg = error instanceof Error ? error.message : String(error)
       toast.error(`Failed to stage hunk: ${msg}`)
@@ -310,7 +332,7 @@ function App() {
     if (!repoPath || !selectedFile) return
 
     try {
-      await ipc.unstageHunk(repoPath, selectedFile.path, hunk)
+      await ipc.unstageHunk(repoPath, selectedFile.path, hunk, diffOptions)
       toast.success('Hunk unstaged')
 
       // Refresh status and diff
@@ -321,8 +343,8 @@ function App() {
       })
 
       // Reload diff to show updated hunks
-      const hunks = await ipc.getDiff(repoPath, selectedFile.path, selectedFileStaged)
-      setDiffHunks(hunks)
+      const diff = await ipc.getDiff(repoPath, selectedFile.path, selectedFileStaged, diffLineLimit, 'word', diffOptions)
+      setFileDiff(diff)
     } catch (error) {
       const msg = error instanceof Error ? error.message : String(error)
       toast.error(`Failed to unstage hunk: ${msg}`)
@@ -338,13 +360,17 @@ function App() {
       </div>
     ) : (
       <DiffViewer
-        hunks={diffHunks}
+        hunks={fileDiff?.hunks ?? []}
+        fileDiff={fileDiff ?? undefined}
+        onLoadMore={handleLoadMoreDiff}
         onStageHunk={selectedFileStaged ? undefined : handleStageHunk}
         onUnstageHunk={selectedFileStaged ? handleUnstageHunk : undefined}
         isStaged={selectedFileStaged}
         isNewFile={selectedFile.status === 'U'}
         filePath={selectedFile.path}
-        fileSize={0} // TODO: Add file size tracking
+        fileSize={fileDiff?.newSize ?? fileDiff?.oldSize ?? 0}
+        options={diffOptions}
+        onOptionsChange={setDiffOptions}
       />
     )
   ) : (
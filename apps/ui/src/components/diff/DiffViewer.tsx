@@ -1,15 +1,37 @@
 import React from 'react'
 import { cn } from '../../lib/utils'
-import type { DiffHunk } from '@gitfrisky/shared-types'
+import type { ChangeRange, DiffHunk, DiffViewOptions, FileDiff } from '@gitfrisky/shared-types'
 
 type DiffViewerProps = {
   hunks: DiffHunk[]
+  fileDiff?: FileDiff
+  onLoadMore?: () => void
   onStageHunk?: (hunk: DiffHunk) => void
   onUnstageHunk?: (hunk: DiffHunk) => void
   isStaged?: boolean
   isNewFile?: boolean
   filePath?: string
   fileSize?: number
+  options?: DiffViewOptions
+  onOptionsChange?: (options: DiffViewOptions) => void
+}
+
+// Split a line into plain and changed segments; range offsets are UTF-16,
+// which is what JS string indices count
+function highlightChanges(content: string, changes: ChangeRange[], className: string) {
+  const segments: React.ReactNode[] = []
+  let pos = 0
+  changes.forEach((range, i) => {
+    if (range.start > pos) segments.push(content.slice(pos, range.start))
+    segments.push(
+      <span key={i} className={className}>
+        {content.slice(range.start, range.end)}
+      </span>
+    )
+    pos = range.end
+  })
+  if (pos < content.length) segments.push(content.slice(pos))
+  return segments
 }
 
 function DiffLineComponent({
@@ -17,11 +39,13 @@ function DiffLineComponent({
   lineType,
   oldLineno,
   newLineno,
+  changes,
 }: {
   content: string
   lineType: 'context' | 'addition' | 'deletion'
   oldLineno?: number | null
   newLineno?: number | null
+  changes?: ChangeRange[]
 }) {
   const colors = {
     context: 'bg-base text-foreground',
@@ -54,7 +78,9 @@ function DiffLineComponent({
       {/* Line content with prefix */}
       <div className="flex-1 pl-2 whitespace-pre overflow-x-auto">
         <span className="text-foreground-dim select-none mr-1">{prefix[lineType]}</span>
-        {content}
+        {changes && changes.length > 0
+          ? highlightChanges(content, changes, lineType === 'addition' ? 'bg-added/30 rounded-sm' : 'bg-removed/30 rounded-sm')
+          : content}
       </div>
     </div>
   )
@@ -93,14 +119,97 @@ function HunkHeader({
   )
 }
 
+function formatSize(bytes?: number) {
+  if (bytes === undefined) return '—'
+  if (bytes < 1024) return `${bytes} B`
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`
+  return `${(bytes / (1024 * 1024)).toFixed(1)} MB`
+}
+
+// Binary and oversized files: show what changed instead of lines
+function FileSummary({ fileDiff }: { fileDiff: FileDiff }) {
+  return (
+    <div className="flex flex-col items-center justify-center h-full p-6 text-center">
+      <p className="text-sm text-foreground mb-2">
+        {fileDiff.tooLarge ? 'File too large to diff' : 'Binary file'}
+      </p>
+      <p className="text-xs font-mono text-foreground-dim">
+        {fileDiff.oldOid?.slice(0, 7) ?? 'none'} ({formatSize(fileDiff.oldSize)}) → {fileDiff.newOid?.slice(0, 7) ?? 'none'} ({formatSize(fileDiff.newSize)})
+      </p>
+    </div>
+  )
+}
+
+function DiffToolbar({
+  options,
+  onChange,
+}: {
+  options: DiffViewOptions
+  onChange: (options: DiffViewOptions) => void
+}) {
+  const context = options.contextLines ?? 3
+
+  return (
+    <div className="flex items-center gap-3 px-2 py-1 text-xs text-foreground-dim border-b border-border bg-subtle">
+      <label className="flex items-center gap-1 cursor-pointer">
+        <input
+          type="checkbox"
+          checked={!!options.ignoreAllSpace}
+          onChange={(e) => onChange({ ...options, ignoreAllSpace: e.target.checked })}
+        />
+        Hide whitespace
+      </label>
+      <label className="flex items-center gap-1 cursor-pointer">
+        <input
+          type="checkbox"
+          checked={!!options.ignoreBlankLines}
+          onChange={(e) => onChange({ ...options, ignoreBlankLines: e.target.checked })}
+        />
+        Hide blank lines
+      </label>
+      <div className="flex items-center gap-1">
+        <span>Context</span>
+        <button
+          onClick={() => onChange({ ...options, contextLines: Math.max(0, context - 1) })}
+          className="px-1 rounded hover:bg-hover"
+        >
+          −
+        </button>
+        <span className="w-4 text-center">{context}</span>
+        <button
+          onClick={() => onChange({ ...options, contextLines: context + 1 })}
+          className="px-1 rounded hover:bg-hover"
+        >
+          +
+        </button>
+      </div>
+      <select
+        value={options.algorithm ?? ''}
+        onChange={(e) => onChange({ ...options, algorithm: (e.target.value || undefined) as DiffViewOptions['algorithm'] })}
+        className="bg-transparent"
+      >
+        <option value="">Default algorithm</option>
+        <option value="myers">Myers</option>
+        <option value="minimal">Minimal</option>
+        <option value="patience">Patience</option>
+        <option value="histogram">Histogram</option>
+      </select>
+    </div>
+  )
+}
+
 export function DiffViewer({
   hunks,
+  fileDiff,
+  onLoadMore,
   onStageHunk,
   onUnstageHunk,
   isStaged = false,
   isNewFile = false,
   filePath,
-  fileSize = 0
+  fileSize = 0,
+  options,
+  onOptionsChange,
 }: DiffViewerProps) {
   const [forceShow, setForceShow] = React.useState(false)
 
@@ -108,10 +217,19 @@ export function DiffViewer({
   const totalLines = hunks.reduce((sum, hunk) => sum + hunk.lines.length, 0)
   const isLargeFile = fileSize > 10 * 1024 || totalLines > 500
 
+  if (fileDiff && (fileDiff.isBinary || fileDiff.tooLarge)) {
+    return <FileSummary fileDiff={fileDiff} />
+  }
+
+  const toolbar = options && onOptionsChange ? <DiffToolbar options={options} onChange={onOptionsChange} /> : null
+
   if (hunks.length === 0) {
     return (
-      <div className="flex items-center justify-center h-full">
-        <p className="text-sm text-foreground-dim">No diff to display</p>
+      <div className="flex flex-col h-full">
+        {toolbar}
+        <div className="flex flex-1 items-center justify-center">
+          <p className="text-sm text-foreground-dim">No diff to display</p>
+        </div>
       </div>
     )
   }
@@ -137,12 +255,13 @@ export function DiffViewer({
 
   return (
     <div className="h-full overflow-y-auto bg-base">
+      {toolbar}
       {hunks.map((hunk, hunkIndex) => (
         <div key={hunkIndex} className="border-b border-border last:border-b-0">
           <HunkHeader
             header={hunk.header}
-            onStage={isNewFile ? undefined : (onStageHunk ? () => onStageHunk(hunk) : undefined)}
-            onUnstage={isNewFile ? undefined : (onUnstageHunk ? () => onUnstageHunk(hunk) : undefined)}
+            onStage={isNewFile || hunk.truncated ? undefined : (onStageHunk ? () => onStageHunk(hunk) : undefined)}
+            onUnstage={isNewFile || hunk.truncated ? undefined : (onUnstageHunk ? () => onUnstageHunk(hunk) : undefined)}
             isStaged={isStaged}
           />
           <div>
@@ -153,11 +272,23 @@ export function DiffViewer({
                 lineType={line.lineType}
                 oldLineno={line.oldLineno}
                 newLineno={line.newLineno}
+                changes={line.changes}
               />
             ))}
           </div>
         </div>
       ))}
+      {fileDiff?.truncated && onLoadMore && (
+        <div className="flex items-center justify-center gap-3 p-3 text-xs text-foreground-dim">
+          <span>Showing {totalLines} of {fileDiff.totalLines} lines</span>
+          <button
+            onClick={onLoadMore}
+            className="px-2 py-1 rounded bg-hover hover:bg-active transition-colors"
+          >
+            Load more
+          </button>
+        </div>
+      )}
     </div>
   )
 }
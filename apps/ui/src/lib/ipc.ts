@@ -1,5 +1,36 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { RepoSummary, FileChange, DiffHunk, Commit } from '@gitfrisky/shared-types';
+import type {
+  RepoSummary,
+  FileChange,
+  DiffHunk,
+  FileDiff,
+  Commit,
+  ConflictFile,
+  ConflictResolution,
+  ConflictStyle,
+  RebaseStatus,
+  RebaseTodoEntry,
+  PickOptions,
+  SequenceStatus,
+  ResetMode,
+  ResetResult,
+  JournalEntry,
+  JournalState,
+  ReflogEntry,
+  DanglingCommit,
+  BisectMark,
+  BisectStatus,
+  BlameOptions,
+  BlameResult,
+  FileHistoryEntry,
+  HistorySearch,
+  TreeEntry,
+  BlobContent,
+  IntralineMode,
+  DiffViewOptions,
+  WatchOptions,
+  WatcherStatus,
+} from '@gitfrisky/shared-types';
 
 async function invokeWithError<T>(cmd: string, args?: Record<string, unknown>): Promise<T> {
   try {
@@ -20,9 +51,19 @@ export const ipc = {
   status: (repoPath: string) =>
     invokeWithError<{ unstaged: FileChange[]; staged: FileChange[] }>('status', { repoPath }),
 
+  // Status of just these files or directories
+  statusPaths: (repoPath: string, paths: string[]) =>
+    invokeWithError<{ unstaged: FileChange[]; staged: FileChange[] }>('status_paths', { repoPath, paths }),
+
   // Diff
-  getDiff: (repoPath: string, relPath: string, staged: boolean) =>
-    invokeWithError<DiffHunk[]>('get_diff', { repoPath, relPath, staged }),
+  getDiff: (
+    repoPath: string,
+    relPath: string,
+    staged: boolean,
+    maxLines?: number,
+    intraline?: IntralineMode,
+    options?: DiffViewOptions
+  ) => invokeWithError<FileDiff>('get_diff', { repoPath, relPath, staged, maxLines, intraline, options }),
 
   // Staging
   stage: (repoPath: string, paths: string[]) =>
@@ -31,24 +72,157 @@ export const ipc = {
   unstage: (repoPath: string, paths: string[]) =>
     invokeWithError<void>('unstage', { repoPath, paths }),
 
-  stageHunk: (repoPath: string, filePath: string, hunk: DiffHunk) =>
-    invokeWithError<void>('stage_hunk', { repoPath, filePath, hunk }),
+  // Pass the options the hunk was shown with so whitespace-reduced hunks stage correctly
+  stageHunk: (repoPath: string, filePath: string, hunk: DiffHunk, options?: DiffViewOptions) =>
+    invokeWithError<void>('stage_hunk', { repoPath, filePath, hunk, options }),
 
-  unstageHunk: (repoPath: string, filePath: string, hunk: DiffHunk) =>
-    invokeWithError<void>('unstage_hunk', { repoPath, filePath, hunk }),
+  unstageHunk: (repoPath: string, filePath: string, hunk: DiffHunk, options?: DiffViewOptions) =>
+    invokeWithError<void>('unstage_hunk', { repoPath, filePath, hunk, options }),
 
   // Commits
   commit: (repoPath: string, message: string) =>
     invokeWithError<Commit>('commit', { repoPath, message }),
 
+  // Prepared message of an in-progress merge/revert/cherry-pick
+  mergeMessage: (repoPath: string) =>
+    invokeWithError<string | null>('merge_message', { repoPath }),
+
   // History
   log: (repoPath: string, limit?: number) =>
     invokeWithError<Commit[]>('log', { repoPath, limit }),
 
+  // Conflicts
+  listConflicts: (repoPath: string) =>
+    invokeWithError<string[]>('list_conflicts', { repoPath }),
+
+  getConflict: (repoPath: string, relPath: string, style?: ConflictStyle) =>
+    invokeWithError<ConflictFile>('get_conflict', { repoPath, relPath, style }),
+
+  resolveConflict: (repoPath: string, relPath: string, resolution: ConflictResolution) =>
+    invokeWithError<void>('resolve_conflict', { repoPath, relPath, resolution }),
+
+  markResolved: (repoPath: string, paths: string[]) =>
+    invokeWithError<void>('mark_resolved', { repoPath, paths }),
+
+  // Interactive rebase
+  rebasePlan: (repoPath: string, upstream: string) =>
+    invokeWithError<RebaseTodoEntry[]>('rebase_plan', { repoPath, upstream }),
+
+  rebaseStart: (repoPath: string, onto: string, todo: RebaseTodoEntry[]) =>
+    invokeWithError<RebaseStatus>('rebase_start', { repoPath, onto, todo }),
+
+  rebaseStatus: (repoPath: string) =>
+    invokeWithError<RebaseStatus>('rebase_status', { repoPath }),
+
+  rebaseContinue: (repoPath: string, message?: string) =>
+    invokeWithError<RebaseStatus>('rebase_continue', { repoPath, message }),
+
+  rebaseSkip: (repoPath: string) =>
+    invokeWithError<RebaseStatus>('rebase_skip', { repoPath }),
+
+  rebaseAbort: (repoPath: string) =>
+    invokeWithError<RebaseStatus>('rebase_abort', { repoPath }),
+
+  // Cherry-pick / revert
+  cherryPick: (repoPath: string, oids: string[], options?: PickOptions) =>
+    invokeWithError<Commit[]>('cherry_pick', { repoPath, oids, options }),
+
+  revert: (repoPath: string, oids: string[], options?: PickOptions) =>
+    invokeWithError<Commit[]>('revert', { repoPath, oids, options }),
+
+  sequencerStatus: (repoPath: string) =>
+    invokeWithError<SequenceStatus>('sequencer_status', { repoPath }),
+
+  sequencerContinue: (repoPath: string) =>
+    invokeWithError<Commit[]>('sequencer_continue', { repoPath }),
+
+  sequencerSkip: (repoPath: string) =>
+    invokeWithError<Commit[]>('sequencer_skip', { repoPath }),
+
+  sequencerAbort: (repoPath: string) =>
+    invokeWithError<void>('sequencer_abort', { repoPath }),
+
+  // Moving HEAD
+  reset: (repoPath: string, target: string, mode: ResetMode, force = false) =>
+    invokeWithError<ResetResult>('reset', { repoPath, target, mode, force }),
+
+  checkoutCommit: (repoPath: string, target: string, force = false) =>
+    invokeWithError<string>('checkout_commit', { repoPath, target, force }),
+
+  checkoutPaths: (repoPath: string, target: string, paths: string[]) =>
+    invokeWithError<void>('checkout_paths', { repoPath, target, paths }),
+
+  // Undo / redo
+  journal: (repoPath: string) =>
+    invokeWithError<JournalState>('journal', { repoPath }),
+
+  undo: (repoPath: string) =>
+    invokeWithError<JournalEntry | null>('undo', { repoPath }),
+
+  redo: (repoPath: string) =>
+    invokeWithError<JournalEntry | null>('redo', { repoPath }),
+
+  // Reflog and recovery
+  reflog: (repoPath: string, refName?: string) =>
+    invokeWithError<ReflogEntry[]>('reflog', { repoPath, refName }),
+
+  danglingCommits: (repoPath: string) =>
+    invokeWithError<DanglingCommit[]>('dangling_commits', { repoPath }),
+
+  restoreCommit: (repoPath: string, oid: string, branchName: string) =>
+    invokeWithError<string>('restore_commit', { repoPath, oid, branchName }),
+
+  // Bisect
+  bisectStart: (repoPath: string, good: string[], bad?: string, pathspec: string[] = []) =>
+    invokeWithError<BisectStatus>('bisect_start', { repoPath, bad, good, pathspec }),
+
+  bisectMark: (repoPath: string, mark: BisectMark, oid?: string) =>
+    invokeWithError<BisectStatus>('bisect_mark', { repoPath, mark, oid }),
+
+  bisectStatus: (repoPath: string) =>
+    invokeWithError<BisectStatus>('bisect_status', { repoPath }),
+
+  bisectRun: (repoPath: string, command: string) =>
+    invokeWithError<BisectStatus>('bisect_run', { repoPath, command }),
+
+  cancelBisectRun: (repoPath: string) =>
+    invokeWithError<void>('cancel_bisect_run', { repoPath }),
+
+  bisectReset: (repoPath: string) =>
+    invokeWithError<void>('bisect_reset', { repoPath }),
+
+  // Blame
+  blame: (repoPath: string, path: string, rev?: string, options?: BlameOptions) =>
+    invokeWithError<BlameResult>('blame', { repoPath, path, rev, options }),
+
+  blameParent: (repoPath: string, oid: string, path: string, options?: BlameOptions) =>
+    invokeWithError<BlameResult>('blame_parent', { repoPath, oid, path, options }),
+
+  // File history
+  fileHistory: (repoPath: string, path: string, rev?: string, limit?: number) =>
+    invokeWithError<FileHistoryEntry[]>('file_history', { repoPath, path, rev, limit }),
+
+  // History search; results arrive as 'history-search-match' events
+  searchHistory: (repoPath: string, query: HistorySearch) =>
+    invokeWithError<number>('search_history', { repoPath, query }),
+
+  cancelSearch: (searchId: number) =>
+    invokeWithError<void>('cancel_search', { searchId }),
+
+  // Browsing revisions
+  listTree: (repoPath: string, rev?: string, path?: string) =>
+    invokeWithError<TreeEntry[]>('list_tree', { repoPath, rev, path }),
+
+  readBlob: (repoPath: string, path: string, rev?: string, maxBytes?: number) =>
+    invokeWithError<BlobContent>('read_blob', { repoPath, rev, path, maxBytes }),
+
   // File watching
-  startWatch: (repoPath: string) =>
-    invokeWithError<void>('start_watch', { repoPath }),
+  startWatch: (repoPath: string, options?: WatchOptions) =>
+    invokeWithError<WatcherStatus>('start_watch', { repoPath, options }),
 
   stopWatch: () =>
     invokeWithError<void>('stop_watch'),
+
+  watcherStatus: () =>
+    invokeWithError<WatcherStatus | null>('watcher_status'),
 };
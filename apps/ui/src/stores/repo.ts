@@ -1,14 +1,24 @@
 import { create } from 'zustand'
 import { listen, UnlistenFn } from '@tauri-apps/api/event'
 import { ipc } from '../lib/ipc'
-import type { RepoSummary, FileChange } from '@gitfrisky/shared-types'
+import type { RepoSummary, FileChange, RepoState as RepoOperationState, WatcherStatus, StatusDelta } from '@gitfrisky/shared-types'
 
+// One debounce window's worth of changes
 type WatchEvent = {
-  kind: 'status' | 'head' | 'refs'
+  kinds: ('status' | 'head' | 'refs' | 'state' | 'stash' | 'config' | 'remoteRefs' | 'indexLock')[]
+  paths: string[] // Working tree paths, relative to the repo root
+  refs: string[] // e.g. 'HEAD' or 'refs/heads/main'
+  indexChanged: boolean
+  state?: RepoOperationState // With 'state': the operation now in progress, or clean
+  indexLocked?: boolean // With 'indexLock'
 }
 
-// Global unlisten function for watch events
+const byPath = (a: FileChange, b: FileChange) => a.path.localeCompare(b.path)
+
+// Global unlisten functions for watch events
 let watchUnlisten: UnlistenFn | null = null
+let watcherStatusUnlisten: UnlistenFn | null = null
+let statusUnlisten: UnlistenFn | null = null
 
 type RepoState = {
   // Current repo
@@ -23,6 +33,12 @@ type RepoState = {
   selectedFile: FileChange | null
   selectedFileStaged: boolean
 
+  // Another git process holds .git/index.lock
+  indexLocked: boolean
+
+  // How changes are being picked up; degraded when polling had to stand in
+  watcherStatus: WatcherStatus | null
+
   // Loading states
   isLoading: boolean
   error: string | null
@@ -31,6 +47,7 @@ type RepoState = {
   openRepo: (path: string) => Promise<void>
   closeRepo: () => void
   refreshStatus: () => Promise<void>
+  applyStatusDelta: (delta: StatusDelta) => void
   selectFile: (file: FileChange, isStaged: boolean) => void
   clearSelection: () => void
   stageFile: (path: string) => Promise<void>
@@ -45,6 +62,8 @@ export const useRepoStore = create<RepoState>((set, get) => ({
   staged: [],
   selectedFile: null,
   selectedFileStaged: false,
+  indexLocked: false,
+  watcherStatus: null,
   isLoading: false,
   error: null,
 
@@ -55,28 +74,43 @@ export const useRepoStore = create<RepoState>((set, get) => ({
       const summary = await ipc.openRepo(path)
       set({ repoPath: path, summary })
 
-      // Load initial status
-      await get().refreshStatus()
+      // Hear about fallbacks to polling, including ones during startup
+      if (watcherStatusUnlisten) {
+        watcherStatusUnlisten()
+      }
+      watcherStatusUnlisten = await listen<WatcherStatus>('watcher-status', (event) => {
+        set({ watcherStatus: event.payload })
+      })
+
+      // The watcher pushes status changes from here on
+      if (statusUnlisten) {
+        statusUnlisten()
+      }
+      statusUnlisten = await listen<StatusDelta>('status-changed', (event) => {
+        get().applyStatusDelta(event.payload)
+      })
 
       // Start file watcher
-      await ipc.startWatch(path)
+      const watcherStatus = await ipc.startWatch(path)
+      set({ watcherStatus })
+
+      // Load initial status, which the watcher has just scanned
+      await get().refreshStatus()
 
       // Set up event listener for file changes
       if (watchUnlisten) {
         watchUnlisten()
       }
       watchUnlisten = await listen<WatchEvent>('repo-changed', (event) => {
-        const { kind } = event.payload
-        switch (kind) {
-          case 'status':
-            // Working tree or index changed - refresh status
-            get().refreshStatus()
-            break
-          case 'head':
-          case 'refs':
-            // Branches changed - could refresh branches here in the future
-            get().refreshStatus()
-            break
+        const { state, indexLocked } = event.payload
+
+        // A merge/rebase started or finished outside the app
+        const { summary } = get()
+        if (state && summary) {
+          set({ summary: { ...summary, state } })
+        }
+        if (indexLocked !== undefined && indexLocked !== null) {
+          set({ indexLocked })
         }
       })
     } catch (error) {
@@ -102,6 +136,14 @@ export const useRepoStore = create<RepoState>((set, get) => ({
       watchUnlisten()
       watchUnlisten = null
     }
+    if (watcherStatusUnlisten) {
+      watcherStatusUnlisten()
+      watcherStatusUnlisten = null
+    }
+    if (statusUnlisten) {
+      statusUnlisten()
+      statusUnlisten = null
+    }
 
     set({
       repoPath: null,
@@ -110,6 +152,8 @@ export const useRepoStore = create<RepoState>((set, get) => ({
       staged: [],
       selectedFile: null,
       selectedFileStaged: false,
+      indexLocked: false,
+      watcherStatus: null,
       error: null,
     })
   },
@@ -133,6 +177,17 @@ export const useRepoStore = create<RepoState>((set, get) => ({
     }
   },
 
+  // Replace the entries of the files the watcher says changed
+  applyStatusDelta: (delta: StatusDelta) => {
+    const changed = new Set(delta.paths)
+    const keep = (f: FileChange) => !changed.has(f.path)
+    const { unstaged, staged } = get()
+    set({
+      unstaged: [...unstaged.filter(keep), ...delta.unstaged].sort(byPath),
+      staged: [...staged.filter(keep), ...delta.staged].sort(byPath),
+    })
+  },
+
   // Select a file to view diff
   selectFile: (file: FileChange, isStaged: boolean) => {
     set({
@@ -0,0 +1,139 @@
+// Status on a generated 100k-file working tree: libgit2, which GitFrisky
+// uses by default, against the git CLI with each of the features that let it
+// avoid stat-ing every file, which GitFrisky switches to when a repository
+// enables them (see src/cli_status.rs).
+//
+//     cargo bench --bench status
+//
+// GITFRISKY_BENCH_FILES changes the fixture size. The fixture is generated
+// once under target/ and reused.
+
+use git2::{Repository, StatusOptions, StatusShow};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+const RUNS: usize = 5;
+const FILES_PER_DIR: usize = 100;
+
+fn git(repo: &Path, args: &[&str]) -> Vec<u8> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["-c", "user.name=bench", "-c", "user.email=bench@example.com"])
+        .args(args)
+        .output()
+        .expect("git must be on PATH");
+    assert!(output.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    output.stdout
+}
+
+// A committed tree of `files` files, 100 per directory, with a few modified
+// and a few untracked ones
+fn fixture(files: usize) -> PathBuf {
+    let root = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("status-fixture-{}", files));
+    if root.join(".git").join("fixture-done").exists() {
+        return root;
+    }
+    let _ = fs::remove_dir_all(&root);
+
+    eprintln!("Generating {} files in {}...", files, root.display());
+    for i in 0..files {
+        let dir = root.join(format!("src/m{}/d{}", i / (FILES_PER_DIR * 100), i / FILES_PER_DIR));
+        if i % FILES_PER_DIR == 0 {
+            fs::create_dir_all(&dir).unwrap();
+        }
+        fs::write(dir.join(format!("f{}.txt", i % FILES_PER_DIR)), format!("{}\n", i)).unwrap();
+    }
+    git(&root, &["init", "-q", "-b", "main"]);
+    git(&root, &["add", "-A"]);
+    git(&root, &["commit", "-q", "-m", "fixture"]);
+
+    for i in 0..10 {
+        fs::write(root.join(format!("src/m0/d{}/f0.txt", i)), "modified\n").unwrap();
+        fs::write(root.join(format!("src/m0/d{}/untracked.txt", i)), "new\n").unwrap();
+    }
+    fs::write(root.join(".git").join("fixture-done"), "").unwrap();
+    root
+}
+
+fn median(mut times: Vec<Duration>) -> Duration {
+    times.sort();
+    times[times.len() / 2]
+}
+
+// Median of RUNS timed runs after one untimed one, which lets git write
+// whatever cache the scenario turns on into the index
+fn measure(mut run: impl FnMut() -> usize) -> (Duration, usize) {
+    let entries = run();
+    let times = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .collect();
+    (median(times), entries)
+}
+
+fn libgit2_status(repo: &Repository) -> usize {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true).recurse_untracked_dirs(true).show(StatusShow::IndexAndWorkdir);
+    repo.statuses(Some(&mut opts)).unwrap().len()
+}
+
+fn cli_status(root: &Path, config: &[&str]) -> usize {
+    let mut args = vec![];
+    for setting in config {
+        args.extend(["-c", setting]);
+    }
+    args.extend(["status", "--porcelain=v2", "-z", "--untracked-files=all", "--no-renames"]);
+    git(root, &args).split(|b| *b == 0).filter(|r| !r.is_empty()).count()
+}
+
+fn main() {
+    let files = std::env::var("GITFRISKY_BENCH_FILES").ok().and_then(|n| n.parse().ok()).unwrap_or(100_000);
+    let root = fixture(files);
+
+    // Stands in for Watchman on a tree nothing touched since the last query:
+    // protocol v2 answers with a token and no changed paths
+    let hook = root.join(".git").join("bench-fsmonitor");
+    fs::write(&hook, "#!/bin/sh\nprintf 'bench\\0'\n").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let fsmonitor = format!("core.fsmonitor={}", hook.display());
+
+    // Start from a plain index every time
+    git(&root, &["update-index", "--no-untracked-cache", "--no-split-index", "--no-fsmonitor"]);
+
+    let mut results = vec![];
+    let repo = Repository::open(&root).unwrap();
+    results.push(("libgit2", measure(|| libgit2_status(&repo))));
+    results.push(("git", measure(|| cli_status(&root, &[]))));
+    results.push(("git, untracked cache", measure(|| cli_status(&root, &["core.untrackedCache=true"]))));
+    results.push((
+        "git, untracked cache + fsmonitor",
+        measure(|| cli_status(&root, &["core.untrackedCache=true", &fsmonitor])),
+    ));
+    results.push((
+        "git, untracked cache + fsmonitor + split index",
+        measure(|| cli_status(&root, &["core.untrackedCache=true", &fsmonitor, "core.splitIndex=true"])),
+    ));
+    git(&root, &["update-index", "--no-untracked-cache", "--no-split-index", "--no-fsmonitor"]);
+
+    println!("status of {} files, median of {} runs", files, RUNS);
+    let baseline = results[0].1 .0;
+    for (name, (time, entries)) in &results {
+        println!(
+            "{:<48} {:>8.1} ms  {:>5.2}x  ({} entries)",
+            name,
+            time.as_secs_f64() * 1000.0,
+            baseline.as_secs_f64() / time.as_secs_f64(),
+            entries
+        );
+    }
+}
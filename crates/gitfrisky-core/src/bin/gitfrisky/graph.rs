@@ -0,0 +1,154 @@
+use gitfrisky_core::domain::types::Commit;
+use std::collections::HashSet;
+
+// Text commit graph in the style of `git log --graph`. Each lane is a column
+// two characters wide holding the commit it leads to; lanes move at most one
+// column per row, so edges can always be drawn with `|`, `/` and `\`.
+
+pub struct Row {
+    pub graph: String,
+    pub commit: Option<usize>, // Index of the commit shown on this row
+}
+
+// Rows for `commits` (newest first), setting each commit's lane
+pub fn layout(commits: &mut [Commit]) -> Vec<Row> {
+    let mut lanes: Vec<Option<String>> = vec![];
+    let mut rows = vec![];
+    let mut shown = HashSet::new();
+
+    for (i, commit) in commits.iter_mut().enumerate() {
+        // A branch tip nothing led to yet starts a new lane
+        let column = match lanes.iter().position(|l| l.as_deref() == Some(commit.oid.as_str())) {
+            Some(column) => column,
+            None => {
+                let free = lanes.iter().position(Option::is_none).unwrap_or(lanes.len());
+                if free == lanes.len() {
+                    lanes.push(None);
+                }
+                lanes[free] = Some(commit.oid.clone());
+                free
+            }
+        };
+        commit.lane = Some(column as u32);
+        shown.insert(commit.oid.clone());
+
+        let mut line: Vec<char> = lanes.iter().flat_map(|l| [if l.is_some() { '|' } else { ' ' }, ' ']).collect();
+        line[column * 2] = '*';
+        rows.push(Row { graph: render(line), commit: Some(i) });
+
+        // The first parent continues this lane, later ones open lanes to its
+        // right, pushing the rest over. Other lanes that led here end. The log
+        // is in date order, so with clock skew a parent may already be shown.
+        let parents: Vec<&String> = commit.parents.iter().filter(|p| !shown.contains(*p)).collect();
+        let mut next = vec![];
+        let mut moves = vec![];
+        for (j, lane) in lanes.iter().enumerate() {
+            if j == column {
+                for parent in &parents {
+                    moves.push((next.len(), j));
+                    next.push(Some(parent.to_string()));
+                }
+                if parents.is_empty() {
+                    next.push(None);
+                }
+            } else if lane.is_some() && lane.as_deref() != Some(commit.oid.as_str()) {
+                moves.push((next.len(), j));
+                next.push(lane.clone());
+            } else {
+                next.push(None);
+            }
+        }
+        if moves.iter().any(|(to, from)| to != from) {
+            rows.push(Row { graph: edges(&moves), commit: None });
+        }
+        lanes = next;
+
+        // Then close the gaps and join lanes leading to the same commit
+        loop {
+            if let Some((next, moves)) = converge(&lanes) {
+                rows.push(Row { graph: edges(&moves), commit: None });
+                lanes = next;
+            } else if let Some((next, steps)) = cross(&lanes) {
+                rows.extend(steps.iter().map(|moves| Row { graph: edges(moves), commit: None }));
+                lanes = next;
+            } else {
+                break;
+            }
+        }
+        while lanes.last().is_some_and(Option::is_none) {
+            lanes.pop();
+        }
+    }
+    rows
+}
+
+// (to, from) columns of every lane that continues into the next row
+type Moves = Vec<(usize, usize)>;
+
+// Every lane with an empty lane, a lane moving left, or a lane headed to the
+// same commit to its left moves one column left; None when nothing moves
+fn converge(lanes: &[Option<String>]) -> Option<(Vec<Option<String>>, Moves)> {
+    let mut moving = vec![false; lanes.len()];
+    for j in 1..lanes.len() {
+        moving[j] = lanes[j].is_some() && (lanes[j - 1].is_none() || lanes[j - 1] == lanes[j] || moving[j - 1]);
+    }
+    if !moving.contains(&true) {
+        return None;
+    }
+
+    let mut next: Vec<Option<String>> = vec![];
+    let mut moves = vec![];
+    for (j, lane) in lanes.iter().enumerate() {
+        let to = if moving[j] { j - 1 } else { j };
+        if lane.is_some() {
+            moves.push((to, j));
+        }
+        if next.len() <= to {
+            next.push(lane.clone());
+        } else if next[to].is_none() {
+            // Filling the gap a lane moving left just left
+            next[to] = lane.clone();
+        }
+    }
+    Some((next, moves))
+}
+
+// A lane headed to the same commit as one further left, with other lanes in
+// between, crosses over them to join it, one row per column
+fn cross(lanes: &[Option<String>]) -> Option<(Vec<Option<String>>, Vec<Moves>)> {
+    let (from, to) = lanes.iter().enumerate().find_map(|(j, lane)| {
+        let i = lanes[..j].iter().position(|l| l.is_some() && l == lane)?;
+        Some((j, i))
+    })?;
+    let others: Moves = (0..lanes.len()).filter(|k| *k != from && lanes[*k].is_some()).map(|k| (k, k)).collect();
+    let steps = (to + 1..=from)
+        .rev()
+        .map(|k| {
+            let mut moves = others.clone();
+            moves.push((k - 1, k));
+            moves
+        })
+        .collect();
+
+    let mut next = lanes.to_vec();
+    next[from] = None;
+    Some((next, steps))
+}
+
+// Connectors from each lane's previous column to its new one
+fn edges(moves: &Moves) -> String {
+    let width = moves.iter().map(|(to, from)| to.max(from) + 1).max().unwrap_or(0);
+    let mut line = vec![' '; width * 2];
+    for (to, from) in moves {
+        match from.cmp(to) {
+            std::cmp::Ordering::Equal => line[to * 2] = '|',
+            std::cmp::Ordering::Less => line[to * 2 - 1] = '\\',
+            std::cmp::Ordering::Greater => line[to * 2 + 1] = '/',
+        }
+    }
+    render(line)
+}
+
+fn render(line: Vec<char>) -> String {
+    line.into_iter().collect::<String>().trim_end().to_string()
+}
@@ -0,0 +1,402 @@
+// Headless front end to the same core functions the desktop app invokes,
+// for scripting, reproducing reports from the UI, and integration tests.
+//
+//     gitfrisky [-C <path>] [--json] <command> [<args>]
+//
+// Paths given are relative to the current directory, as with git; paths
+// printed are relative to the repository root. --json prints exactly what the
+// app would receive.
+
+mod graph;
+
+use gitfrisky_core::domain::types::{FileChange, FileDiff, FileStatus, JournalEntry, LineType, RepoState, StatusPayload};
+use gitfrisky_core::{blame, conflict, journal, reflog, repo};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: gitfrisky [-C <path>] [--json] <command> [<args>]
+
+commands:
+  summary                        Current branch, branches and operation in progress
+  status [<path>...]             Staged and unstaged changes
+  diff [--staged] <path>         Changes to one file
+  stage <path>...                Stage files
+  unstage <path>...              Unstage files
+  commit -m <message>            Commit what's staged
+  log [-n <count>] [--graph]     Commits on all branches, newest first
+  blame [--rev <rev>] <path>     Commit that last changed each line
+  reflog [<ref>]                 Where a ref has pointed, newest first
+  conflicts                      Files with unresolved conflicts
+  journal                        Operations that can be undone and redone
+  undo                           Undo the last operation
+  redo                           Redo the last undone operation";
+
+// A command line that couldn't be understood; exits with 2 and the usage
+struct UsageError(String);
+
+enum Failure {
+    Usage(UsageError),
+    Operation(String),
+}
+
+impl From<UsageError> for Failure {
+    fn from(e: UsageError) -> Self {
+        Failure::Usage(e)
+    }
+}
+
+impl From<String> for Failure {
+    fn from(e: String) -> Self {
+        Failure::Operation(e)
+    }
+}
+
+// Arguments after the command name, consumed as options are looked up
+struct Args(Vec<String>);
+
+impl Args {
+    fn flag(&mut self, names: &[&str]) -> bool {
+        let before = self.0.len();
+        self.0.retain(|a| !names.contains(&a.as_str()));
+        self.0.len() != before
+    }
+
+    fn value(&mut self, names: &[&str]) -> Result<Option<String>, UsageError> {
+        let Some(i) = self.0.iter().position(|a| names.contains(&a.as_str())) else {
+            return Ok(None);
+        };
+        if i + 1 >= self.0.len() {
+            return Err(UsageError(format!("{} needs a value", self.0[i])));
+        }
+        let value = self.0.remove(i + 1);
+        self.0.remove(i);
+        Ok(Some(value))
+    }
+
+    // Whatever is left, once all options have been taken
+    fn positional(self) -> Result<Vec<String>, UsageError> {
+        match self.0.iter().find(|a| a.starts_with('-') && a.len() > 1) {
+            Some(unknown) => Err(UsageError(format!("unknown option {}", unknown))),
+            None => Ok(self.0),
+        }
+    }
+}
+
+// The repository the command runs in, and where relative paths start from
+struct Context {
+    root: String,
+    base: PathBuf,
+    json: bool,
+}
+
+impl Context {
+    fn open(dir: Option<String>, json: bool) -> Result<Self, String> {
+        let base = std::env::current_dir().map_err(|e| e.to_string())?.join(dir.unwrap_or_default());
+        let repository = git2::Repository::discover(&base).map_err(|e| e.message().to_string())?;
+        let root = repository.workdir().unwrap_or(repository.path());
+        let root = root.to_string_lossy().trim_end_matches('/').to_string();
+        let base = base.canonicalize().map_err(|e| format!("{}: {}", base.display(), e))?;
+        Ok(Self { root, base, json })
+    }
+
+    // `arg` as a path relative to the repository root, which is what every
+    // backend function takes
+    fn repo_path(&self, arg: &str) -> Result<String, String> {
+        let root = Path::new(&self.root).canonicalize().map_err(|e| e.to_string())?;
+        let mut path = if Path::new(arg).is_absolute() { PathBuf::new() } else { self.base.clone() };
+        for component in Path::new(arg).components() {
+            match component {
+                Component::ParentDir => {
+                    path.pop();
+                }
+                Component::CurDir => {}
+                other => path.push(other),
+            }
+        }
+        let relative = path.strip_prefix(&root).map_err(|_| format!("{} is outside the repository", arg))?;
+        Ok(relative.to_string_lossy().replace('\\', "/"))
+    }
+
+    fn repo_paths(&self, args: &[String]) -> Result<Vec<String>, String> {
+        args.iter().map(|a| self.repo_path(a)).collect()
+    }
+
+    // JSON when asked for, otherwise the human-readable form. A closed pipe
+    // (`| head`) isn't an error.
+    fn print<T: Serialize>(&self, value: &T, text: impl FnOnce(&T) -> String) {
+        let text = match self.json {
+            true => serde_json::to_string_pretty(value).unwrap_or_default(),
+            false => text(value),
+        };
+        if !text.is_empty() {
+            let _ = writeln!(std::io::stdout(), "{}", text);
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut dir = None;
+    let mut json = false;
+    // Global options come before the command
+    while let Some(first) = args.first() {
+        match first.as_str() {
+            "--json" => json = true,
+            "-C" if args.len() > 1 => dir = Some(args.remove(1)),
+            "-h" | "--help" | "help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => break,
+        }
+        args.remove(0);
+    }
+    if args.is_empty() {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    }
+    let command = args.remove(0);
+    let mut args = Args(args);
+    // --json may also follow the command
+    json |= args.flag(&["--json"]);
+
+    let result = Context::open(dir, json).map_err(Failure::from).and_then(|cx| run(&cx, &command, args));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Usage(UsageError(message))) => {
+            eprintln!("gitfrisky: {}\nRun 'gitfrisky --help' for usage.", message);
+            ExitCode::from(2)
+        }
+        Err(Failure::Operation(message)) => {
+            eprintln!("gitfrisky: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cx: &Context, command: &str, mut args: Args) -> Result<(), Failure> {
+    let root = cx.root.as_str();
+    match command {
+        "summary" => {
+            args.positional()?;
+            cx.print(&repo::open_repo(root)?, |summary| {
+                let mut lines = vec![match &summary.head {
+                    Some(_) if summary.is_detached => "HEAD detached".to_string(),
+                    Some(branch) => format!("On branch {}", branch),
+                    None => "No commits yet".to_string(),
+                }];
+                if let Some(state) = state_name(&summary.state) {
+                    lines.push(format!("{} in progress", state));
+                }
+                for branch in summary.branches.iter().filter(|b| !b.is_remote) {
+                    let mut line = format!("{} {}", if branch.is_head { "*" } else { " " }, branch.name);
+                    if let Some(upstream) = &branch.upstream {
+                        line.push_str(&format!(" [{}", upstream));
+                        match (branch.ahead, branch.behind) {
+                            (0, 0) => {}
+                            (ahead, 0) => line.push_str(&format!(": ahead {}", ahead)),
+                            (0, behind) => line.push_str(&format!(": behind {}", behind)),
+                            (ahead, behind) => line.push_str(&format!(": ahead {}, behind {}", ahead, behind)),
+                        }
+                        line.push(']');
+                    }
+                    lines.push(line);
+                }
+                lines.join("\n")
+            });
+        }
+        "status" => {
+            let mut paths = cx.repo_paths(&args.positional()?)?;
+            // The root itself means everything
+            paths.retain(|p| !p.is_empty());
+            let status = if paths.is_empty() { repo::status(root)? } else { repo::status_paths(root, &paths)? };
+            cx.print(&status, short_status);
+        }
+        "diff" => {
+            let staged = args.flag(&["--staged", "--cached"]);
+            let [path] = <[String; 1]>::try_from(args.positional()?).map_err(|_| UsageError("diff takes one path".into()))?;
+            let diff = repo::get_diff(root, &cx.repo_path(&path)?, staged, None, None, None)?;
+            cx.print(&diff, unified_diff);
+        }
+        "stage" | "unstage" => {
+            let paths = cx.repo_paths(&args.positional()?)?;
+            if paths.is_empty() {
+                return Err(UsageError(format!("{} needs at least one path", command)).into());
+            }
+            if command == "stage" { repo::stage(root, &paths)? } else { repo::unstage(root, &paths)? }
+        }
+        "commit" => {
+            let message = args.value(&["-m", "--message"])?.ok_or_else(|| UsageError("commit needs -m <message>".into()))?;
+            args.positional()?;
+            cx.print(&repo::commit(root, &message)?, |commit| format!("{} {}", short(&commit.oid), commit.summary));
+        }
+        "log" => {
+            let limit = args.value(&["-n", "--max-count"])?;
+            let limit = limit.map(|n| n.parse().map_err(|_| UsageError(format!("not a count: {}", n)))).transpose()?;
+            let graph = args.flag(&["--graph"]);
+            args.positional()?;
+
+            let mut commits = repo::log(root, limit)?;
+            let rows = if graph { graph::layout(&mut commits) } else { vec![] };
+            cx.print(&commits, |commits| {
+                let line = |i: usize| {
+                    let commit = &commits[i];
+                    match commit.refs.is_empty() {
+                        true => format!("{} {}", short(&commit.oid), commit.summary),
+                        false => format!("{} ({}) {}", short(&commit.oid), commit.refs.join(", "), commit.summary),
+                    }
+                };
+                if !graph {
+                    return (0..commits.len()).map(line).collect::<Vec<_>>().join("\n");
+                }
+                rows.iter()
+                    .map(|row| match row.commit {
+                        Some(i) => format!("{} {}", row.graph, line(i)),
+                        None => row.graph.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            });
+        }
+        "blame" => {
+            let rev = args.value(&["--rev"])?;
+            let [path] = <[String; 1]>::try_from(args.positional()?).map_err(|_| UsageError("blame takes one path".into()))?;
+            cx.print(&blame::blame(root, &cx.repo_path(&path)?, rev.as_deref(), None)?, |result| {
+                let width = result.lines.len().to_string().len();
+                let mut lines = vec![];
+                for range in &result.ranges {
+                    for n in range.start_line..range.start_line + range.line_count {
+                        let content = result.lines.get(n as usize - 1).map(String::as_str).unwrap_or_default();
+                        lines.push(format!("{} ({} {:>width$}) {}", short(&range.oid), range.author, n, content));
+                    }
+                }
+                lines.join("\n")
+            });
+        }
+        "reflog" => {
+            let positional = args.positional()?;
+            if positional.len() > 1 {
+                return Err(UsageError("reflog takes at most one ref".into()).into());
+            }
+            let name = positional.into_iter().next();
+            let shown = name.clone().unwrap_or_else(|| "HEAD".into());
+            cx.print(&reflog::reflog(root, name.as_deref())?, |entries| {
+                entries
+                    .iter()
+                    .enumerate()
+                    .map(|(i, e)| format!("{} {}@{{{}}}: {}", short(&e.new_oid), shown, i, e.message.as_deref().unwrap_or_default()))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            });
+        }
+        "conflicts" => {
+            args.positional()?;
+            cx.print(&conflict::list_conflicts(root)?, |paths| paths.join("\n"));
+        }
+        "journal" => {
+            args.positional()?;
+            cx.print(&journal::journal(root)?, |state| {
+                let mut lines = vec![];
+                for entry in state.undo.iter().rev() {
+                    lines.push(format!("undo  {}", entry.label));
+                }
+                for entry in state.redo.iter().rev() {
+                    lines.push(format!("redo  {}", entry.label));
+                }
+                lines.join("\n")
+            });
+        }
+        "undo" | "redo" => {
+            args.positional()?;
+            let entry = if command == "undo" { journal::undo(root)? } else { journal::redo(root)? };
+            cx.print(&entry, |entry: &Option<JournalEntry>| match entry {
+                Some(entry) => format!("{} {}", if command == "undo" { "Undid:" } else { "Redid:" }, entry.label),
+                None => format!("Nothing to {}", command),
+            });
+        }
+        _ => return Err(UsageError(format!("unknown command {}", command)).into()),
+    }
+    Ok(())
+}
+
+fn short(oid: &str) -> &str {
+    oid.get(..7).unwrap_or(oid)
+}
+
+fn state_name(state: &RepoState) -> Option<&'static str> {
+    match state {
+        RepoState::Clean => None,
+        RepoState::Merge => Some("Merge"),
+        RepoState::Rebase | RepoState::RebaseInteractive | RepoState::RebaseMerge => Some("Rebase"),
+        RepoState::Revert => Some("Revert"),
+        RepoState::CherryPick => Some("Cherry-pick"),
+        RepoState::Bisect => Some("Bisect"),
+    }
+}
+
+// `git status --short`: index column, working tree column, path; untracked
+// files last
+fn short_status(status: &StatusPayload) -> String {
+    let letter = |change: &FileChange| match change.status {
+        FileStatus::A => 'A',
+        FileStatus::M => 'M',
+        FileStatus::D => 'D',
+        FileStatus::R => 'R',
+        FileStatus::U => '?',
+        FileStatus::C => 'U',
+    };
+    let mut columns: BTreeMap<&str, [char; 2]> = BTreeMap::new();
+    for change in &status.staged {
+        columns.entry(&change.path).or_insert([' ', ' '])[0] = letter(change);
+    }
+    let mut untracked = vec![];
+    for change in &status.unstaged {
+        match change.status {
+            // A file deleted from the index can be untracked at the same time
+            FileStatus::U => untracked.push(format!("?? {}", change.path)),
+            // Conflicts fill both columns
+            FileStatus::C => {
+                columns.insert(&change.path, ['U', 'U']);
+            }
+            _ => columns.entry(&change.path).or_insert([' ', ' '])[1] = letter(change),
+        }
+    }
+    columns
+        .iter()
+        .map(|(path, [x, y])| format!("{}{} {}", x, y, path))
+        .chain(untracked)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn unified_diff(diff: &FileDiff) -> String {
+    let old = diff.old_path.as_deref().unwrap_or(&diff.path);
+    let mut lines = vec![format!("--- a/{}", old), format!("+++ b/{}", diff.path)];
+    if diff.too_large {
+        lines.push("File too large to diff".into());
+    } else if diff.is_binary {
+        lines.push(format!(
+            "Binary file: {} -> {} bytes",
+            diff.old_size.map_or("none".into(), |s| s.to_string()),
+            diff.new_size.map_or("none".into(), |s| s.to_string())
+        ));
+    }
+    for hunk in &diff.hunks {
+        lines.push(hunk.header.trim_end().to_string());
+        for line in &hunk.lines {
+            let prefix = match line.line_type {
+                LineType::Context => ' ',
+                LineType::Addition => '+',
+                LineType::Deletion => '-',
+            };
+            lines.push(format!("{}{}", prefix, line.content));
+        }
+    }
+    if diff.truncated {
+        lines.push(format!("... {} lines in total", diff.total_lines));
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,522 @@
+use crate::domain::types::{BisectMark, BisectStatus, BisectStep};
+use super::repo::{ensure_clean_worktree, toe, to_commit};
+use git2::{build::CheckoutBuilder, DiffOptions, Oid, Repository, RepositoryState, Sort};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+// Bisect state uses the same files and refs as `git bisect`, so a session can
+// be started here and finished on the command line or vice versa:
+//   BISECT_START        branch (or detached oid) to return to on reset
+//   BISECT_TERMS        names for bad/good, one per line
+//   BISECT_NAMES        sq-quoted pathspec
+//   BISECT_LOG          replayable log of marks
+//   BISECT_EXPECTED_REV commit checked out for testing
+//   refs/bisect/<bad>, refs/bisect/<good>-<oid>, refs/bisect/skip-<oid>
+const STATE_FILES: &[&str] = &[
+    "BISECT_START",
+    "BISECT_TERMS",
+    "BISECT_NAMES",
+    "BISECT_LOG",
+    "BISECT_EXPECTED_REV",
+    "BISECT_ANCESTORS_OK",
+    "BISECT_RUN",
+    "BISECT_HEAD",
+    "BISECT_FIRST_PARENT",
+];
+
+struct Terms {
+    bad: String,
+    good: String,
+}
+
+struct Marks {
+    bad: Option<Oid>,
+    good: Vec<Oid>,
+    skip: Vec<Oid>,
+}
+
+fn state_path(repo: &Repository, name: &str) -> PathBuf {
+    repo.path().join(name)
+}
+
+fn in_progress(repo: &Repository) -> bool {
+    state_path(repo, "BISECT_START").exists()
+}
+
+fn write_state(repo: &Repository, name: &str, content: &str) -> Result<(), String> {
+    fs::write(state_path(repo, name), content).map_err(|e| format!("Failed to write {}: {}", name, e))
+}
+
+fn append_log(repo: &Repository, text: &str) -> Result<(), String> {
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(state_path(repo, "BISECT_LOG"))
+        .and_then(|mut f| f.write_all(text.as_bytes()))
+        .map_err(|e| format!("Failed to write BISECT_LOG: {}", e))
+}
+
+// "# bad: [<oid>] <summary>", the comment line git writes for each mark
+fn log_comment(repo: &Repository, label: &str, oid: Oid) -> String {
+    let summary = repo
+        .find_commit(oid)
+        .ok()
+        .and_then(|c| c.summary().map(|s| s.to_string()))
+        .unwrap_or_default();
+    format!("# {}: [{}] {}\n", label, oid, summary)
+}
+
+fn read_terms(repo: &Repository) -> Terms {
+    let content = fs::read_to_string(state_path(repo, "BISECT_TERMS")).unwrap_or_default();
+    let mut lines = content.lines();
+    match (lines.next(), lines.next()) {
+        (Some(bad), Some(good)) => Terms { bad: bad.to_string(), good: good.to_string() },
+        _ => Terms { bad: "bad".to_string(), good: "good".to_string() },
+    }
+}
+
+fn read_marks(repo: &Repository, terms: &Terms) -> Result<Marks, String> {
+    let bad_ref = format!("refs/bisect/{}", terms.bad);
+    let good_prefix = format!("refs/bisect/{}-", terms.good);
+    let mut marks = Marks { bad: None, good: vec![], skip: vec![] };
+
+    for reference in repo.references_glob("refs/bisect/*").map_err(toe)?.flatten() {
+        let (Some(name), Some(oid)) = (reference.name(), reference.target()) else { continue };
+        if name == bad_ref {
+            marks.bad = Some(oid);
+        } else if name.starts_with(&good_prefix) {
+            marks.good.push(oid);
+        } else if name.starts_with("refs/bisect/skip-") {
+            marks.skip.push(oid);
+        }
+    }
+    Ok(marks)
+}
+
+// Shell single-quoting, as git uses for BISECT_NAMES and BISECT_LOG
+fn sq_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+fn sq_split(line: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quoted = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            '\\' if !quoted => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+fn read_pathspec(repo: &Repository) -> Vec<String> {
+    fs::read_to_string(state_path(repo, "BISECT_NAMES"))
+        .map(|s| sq_split(s.trim()))
+        .unwrap_or_default()
+}
+
+// Whether `commit` changes anything under `pathspec`. Like git's history
+// simplification, a merge that matches any parent there does not count.
+fn touches(repo: &Repository, commit: &git2::Commit, pathspec: &[String]) -> Result<bool, String> {
+    let tree = commit.tree().map_err(toe)?;
+    let mut opts = DiffOptions::new();
+    for spec in pathspec {
+        opts.pathspec(spec);
+    }
+
+    if commit.parent_count() == 0 {
+        let diff = repo.diff_tree_to_tree(None, Some(&tree), Some(&mut opts)).map_err(toe)?;
+        return Ok(diff.deltas().len() > 0);
+    }
+    for parent in commit.parents() {
+        let parent_tree = parent.tree().map_err(toe)?;
+        let diff = repo.diff_tree_to_tree(Some(&parent_tree), Some(&tree), Some(&mut opts)).map_err(toe)?;
+        if diff.deltas().len() == 0 {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+struct Range {
+    candidates: Vec<Oid>, // Newest first; includes the bad commit
+    next: Option<Oid>,
+    first_bad: Option<Oid>,
+    untested: usize, // Candidates other than bad that are not skipped
+}
+
+fn compute_range(repo: &Repository, marks: &Marks, pathspec: &[String]) -> Result<Range, String> {
+    let Some(bad) = marks.bad else {
+        return Ok(Range { candidates: vec![], next: None, first_bad: None, untested: 0 });
+    };
+
+    let mut walk = repo.revwalk().map_err(toe)?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME).map_err(toe)?;
+    walk.push(bad).map_err(toe)?;
+    for good in &marks.good {
+        walk.hide(*good).map_err(toe)?;
+    }
+
+    // Parent links over the whole range, so ancestry still works when the
+    // pathspec filters intermediate commits out
+    let mut parents: HashMap<Oid, Vec<Oid>> = HashMap::new();
+    let mut candidates = vec![];
+    for oid in walk {
+        let oid = oid.map_err(toe)?;
+        let commit = repo.find_commit(oid).map_err(toe)?;
+        parents.insert(oid, commit.parent_ids().collect());
+        if oid == bad || pathspec.is_empty() || touches(repo, &commit, pathspec)? {
+            candidates.push(oid);
+        }
+    }
+
+    if marks.good.is_empty() {
+        return Ok(Range { candidates, next: None, first_bad: None, untested: 0 });
+    }
+
+    let in_range: HashSet<Oid> = candidates.iter().copied().collect();
+    let skipped: HashSet<Oid> = marks.skip.iter().copied().collect();
+    let testable: Vec<Oid> = candidates
+        .iter()
+        .copied()
+        .filter(|oid| *oid != bad && !skipped.contains(oid))
+        .collect();
+
+    if testable.is_empty() {
+        // With skipped commits still in range the culprit can't be pinned down
+        let only_bad_left = candidates.iter().all(|oid| *oid == bad);
+        let first_bad = if only_bad_left { Some(bad) } else { None };
+        return Ok(Range { candidates, next: None, first_bad, untested: 0 });
+    }
+
+    // Pick the commit that splits the range most evenly: the number of
+    // candidates it reaches should be as close to half as possible
+    let total = candidates.len();
+    let mut best: Option<(usize, Oid)> = None;
+    for oid in &testable {
+        let mut seen = HashSet::from([*oid]);
+        let mut queue = VecDeque::from([*oid]);
+        let mut reached = 0;
+        while let Some(current) = queue.pop_front() {
+            if in_range.contains(&current) {
+                reached += 1;
+            }
+            for parent in parents.get(&current).into_iter().flatten() {
+                if parents.contains_key(parent) && seen.insert(*parent) {
+                    queue.push_back(*parent);
+                }
+            }
+        }
+        let score = reached.min(total - reached);
+        if best.is_none_or(|(s, _)| score > s) {
+            best = Some((score, *oid));
+        }
+    }
+
+    Ok(Range { candidates, next: best.map(|(_, oid)| oid), first_bad: None, untested: testable.len() })
+}
+
+fn checkout_detached(repo: &Repository, oid: Oid) -> Result<(), String> {
+    let commit = repo.find_commit(oid).map_err(toe)?;
+    repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe())).map_err(|e| {
+        if e.code() == git2::ErrorCode::Conflict {
+            "Your local changes would be overwritten by checkout; commit or stash them first".to_string()
+        } else {
+            toe(e)
+        }
+    })?;
+    repo.set_head_detached(oid).map_err(toe)
+}
+
+fn build_status(repo: &Repository, range: &Range, marks: &Marks) -> Result<BisectStatus, String> {
+    let remaining = range
+        .candidates
+        .iter()
+        .map(|oid| repo.find_commit(*oid).map(|c| to_commit(&c)).map_err(toe))
+        .collect::<Result<Vec<_>, _>>()?;
+    let first_bad = match range.first_bad {
+        Some(oid) => Some(to_commit(&repo.find_commit(oid).map_err(toe)?)),
+        None => None,
+    };
+
+    Ok(BisectStatus {
+        in_progress: true,
+        bad: marks.bad.map(|o| o.to_string()),
+        good: marks.good.iter().map(|o| o.to_string()).collect(),
+        skipped: marks.skip.iter().map(|o| o.to_string()).collect(),
+        pathspec: read_pathspec(repo),
+        current: range.next.map(|o| o.to_string()),
+        remaining,
+        steps_left: usize::BITS - range.untested.leading_zeros(),
+        first_bad,
+    })
+}
+
+// Recompute the range, check out the next commit to test and report where
+// the bisect stands
+fn advance(repo: &Repository) -> Result<BisectStatus, String> {
+    let terms = read_terms(repo);
+    let marks = read_marks(repo, &terms)?;
+    let range = compute_range(repo, &marks, &read_pathspec(repo))?;
+
+    if let Some(first_bad) = range.first_bad {
+        let line = log_comment(repo, &format!("first {} commit", terms.bad), first_bad);
+        let log = fs::read_to_string(state_path(repo, "BISECT_LOG")).unwrap_or_default();
+        if !log.ends_with(&line) {
+            append_log(repo, &line)?;
+        }
+    } else if let Some(next) = range.next {
+        if repo.head().ok().and_then(|h| h.target()) != Some(next) {
+            checkout_detached(repo, next)?;
+        }
+        write_state(repo, "BISECT_EXPECTED_REV", &format!("{}\n", next))?;
+    }
+
+    build_status(repo, &range, &marks)
+}
+
+fn mark_commit(repo: &Repository, mark: BisectMark, oid: Oid) -> Result<BisectStatus, String> {
+    let terms = read_terms(repo);
+    let (term, ref_name) = match mark {
+        BisectMark::Bad => (terms.bad.clone(), format!("refs/bisect/{}", terms.bad)),
+        BisectMark::Good => (terms.good.clone(), format!("refs/bisect/{}-{}", terms.good, oid)),
+        BisectMark::Skip => ("skip".to_string(), format!("refs/bisect/skip-{}", oid)),
+    };
+
+    repo.reference(&ref_name, oid, true, "bisect").map_err(toe)?;
+    append_log(repo, &format!("{}git bisect {} {}\n", log_comment(repo, &term, oid), term, oid))?;
+    advance(repo)
+}
+
+fn resolve(repo: &Repository, spec: &str) -> Result<Oid, String> {
+    Ok(repo.revparse_single(spec).map_err(toe)?.peel_to_commit().map_err(toe)?.id())
+}
+
+// How often a running script is checked for having exited or been cancelled
+const SCRIPT_POLL: Duration = Duration::from_millis(50);
+
+// Run `command` in the working tree; cancelling kills it
+fn run_script(repo: &Repository, command: &str, cancelled: &AtomicBool) -> Result<Option<i32>, String> {
+    let workdir = repo.workdir().ok_or("Repository has no working directory")?;
+
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut c = Command::new("cmd");
+        c.args(["/C", command]);
+        c
+    };
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut c = Command::new("sh");
+        c.args(["-c", command]);
+        c
+    };
+
+    let mut child = cmd.current_dir(workdir).spawn().map_err(|e| format!("Failed to run '{}': {}", command, e))?;
+    loop {
+        if let Some(status) = child.try_wait().map_err(toe)? {
+            return Ok(status.code());
+        }
+        if cancelled.load(Ordering::Relaxed) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err("Bisect run cancelled".to_string());
+        }
+        thread::sleep(SCRIPT_POLL);
+    }
+}
+
+// `git bisect run`: test HEAD with the script, mark it by exit code, repeat
+// until the first bad commit is found. Exit code 0 is good, 125 is skip,
+// 1-127 is bad; anything else (including signals) stops the run. A cancelled
+// run stops where it is, leaving the bisect in progress like an interrupted
+// `git bisect run`.
+fn run_bisect(repo: &Repository, command: &str, cancelled: &AtomicBool, mut on_step: impl FnMut(&BisectStep)) -> Result<BisectStatus, String> {
+    let mut status = advance(repo)?;
+    if status.bad.is_none() || status.good.is_empty() {
+        return Err("Bisect run needs both a good and a bad commit".to_string());
+    }
+
+    while status.first_bad.is_none() && status.current.is_some() {
+        let head = repo.head().map_err(toe)?.peel_to_commit().map_err(toe)?;
+        let exit_code = run_script(repo, command, cancelled)?;
+        let mark = match exit_code {
+            Some(0) => BisectMark::Good,
+            Some(125) => BisectMark::Skip,
+            Some(code) if (1..128).contains(&code) => BisectMark::Bad,
+            _ => {
+                return Err(format!("Bisect run stopped: '{}' exited with {:?} on {}", command, exit_code, head.id()));
+            }
+        };
+
+        on_step(&BisectStep {
+            oid: head.id().to_string(),
+            summary: head.summary().unwrap_or("").to_string(),
+            mark,
+            exit_code,
+        });
+        status = mark_commit(repo, mark, head.id())?;
+    }
+
+    Ok(status)
+}
+
+pub fn bisect_start(repo_path: &str, bad: Option<&str>, good: &[String], pathspec: &[String]) -> Result<BisectStatus, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    if in_progress(&repo) {
+        return Err("A bisect is already in progress".to_string());
+    }
+    if repo.state() != RepositoryState::Clean {
+        return Err(format!("Cannot bisect during {:?}. Please complete or abort the current operation.", repo.state()));
+    }
+    ensure_clean_worktree(&repo)?;
+
+    let bad_spec = bad.unwrap_or("HEAD");
+    let bad_oid = resolve(&repo, bad_spec)?;
+    let good_oids = good.iter().map(|g| resolve(&repo, g)).collect::<Result<Vec<_>, _>>()?;
+
+    // Where `bisect reset` returns to: the branch name, or the commit if detached
+    let head = repo.find_reference("HEAD").map_err(toe)?;
+    let start = match head.symbolic_target() {
+        Some(target) => target.strip_prefix("refs/heads/").unwrap_or(target).to_string(),
+        None => head.target().map(|o| o.to_string()).ok_or("HEAD has no commit")?,
+    };
+
+    let names: Vec<String> = pathspec.iter().map(|p| format!(" {}", sq_quote(p))).collect();
+    write_state(&repo, "BISECT_START", &format!("{}\n", start))?;
+    write_state(&repo, "BISECT_TERMS", "bad\ngood\n")?;
+    write_state(&repo, "BISECT_NAMES", &format!("{}\n", names.concat()))?;
+
+    repo.reference("refs/bisect/bad", bad_oid, true, "bisect").map_err(toe)?;
+    let mut log = log_comment(&repo, "bad", bad_oid);
+    for oid in &good_oids {
+        repo.reference(&format!("refs/bisect/good-{}", oid), *oid, true, "bisect").map_err(toe)?;
+        log.push_str(&log_comment(&repo, "good", *oid));
+    }
+
+    let mut args = vec![sq_quote(bad_spec)];
+    args.extend(good.iter().map(|g| sq_quote(g)));
+    if !pathspec.is_empty() {
+        args.push(sq_quote("--"));
+        args.extend(pathspec.iter().map(|p| sq_quote(p)));
+    }
+    log.push_str(&format!("git bisect start {}\n", args.join(" ")));
+    append_log(&repo, &log)?;
+
+    // Don't leave a half-started bisect behind if the first checkout fails
+    advance(&repo).inspect_err(|_| {
+        let _ = clear_state(&repo);
+    })
+}
+
+pub fn bisect_mark(repo_path: &str, mark: BisectMark, oid: Option<&str>) -> Result<BisectStatus, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    if !in_progress(&repo) {
+        return Err("No bisect in progress".to_string());
+    }
+
+    let target = resolve(&repo, oid.unwrap_or("HEAD"))?;
+    mark_commit(&repo, mark, target)
+}
+
+pub fn bisect_status(repo_path: &str) -> Result<BisectStatus, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    if !in_progress(&repo) {
+        return Ok(BisectStatus {
+            in_progress: false,
+            bad: None,
+            good: vec![],
+            skipped: vec![],
+            pathspec: vec![],
+            current: None,
+            remaining: vec![],
+            steps_left: 0,
+            first_bad: None,
+        });
+    }
+
+    let terms = read_terms(&repo);
+    let marks = read_marks(&repo, &terms)?;
+    let range = compute_range(&repo, &marks, &read_pathspec(&repo))?;
+    build_status(&repo, &range, &marks)
+}
+
+// `on_step` hears about each commit the script judged as it goes. Setting
+// `cancelled` kills the script and stops the run with an error.
+pub fn bisect_run(repo_path: &str, command: &str, cancelled: &AtomicBool, on_step: impl FnMut(&BisectStep)) -> Result<BisectStatus, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    if !in_progress(&repo) {
+        return Err("No bisect in progress".to_string());
+    }
+
+    run_bisect(&repo, command, cancelled, on_step)
+}
+
+pub fn bisect_reset(repo_path: &str) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    if !in_progress(&repo) {
+        return Err("No bisect in progress".to_string());
+    }
+
+    let start = fs::read_to_string(state_path(&repo, "BISECT_START")).map_err(toe)?;
+    let start = start.trim();
+    match Oid::from_str(start).ok().filter(|_| start.len() == 40) {
+        Some(oid) => checkout_detached(&repo, oid)?,
+        None => {
+            let branch = format!("refs/heads/{}", start);
+            let commit = repo.find_reference(&branch).map_err(toe)?.peel_to_commit().map_err(toe)?;
+            repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe())).map_err(toe)?;
+            repo.set_head(&branch).map_err(toe)?;
+        }
+    }
+
+    clear_state(&repo)
+}
+
+// Remove the refs and files that make up bisect state
+fn clear_state(repo: &Repository) -> Result<(), String> {
+    let refs: Vec<String> = repo
+        .references_glob("refs/bisect/*")
+        .map_err(toe)?
+        .flatten()
+        .filter_map(|r| r.name().map(|n| n.to_string()))
+        .collect();
+    for name in refs {
+        repo.find_reference(&name).and_then(|mut r| r.delete()).map_err(toe)?;
+    }
+    for name in STATE_FILES {
+        let _ = fs::remove_file(state_path(repo, name));
+    }
+    Ok(())
+}
@@ -0,0 +1,266 @@
+use crate::domain::types::{BlameOptions, BlameRange, BlameResult};
+use super::repo::toe;
+use git2::{Blame, BlameHunk, DiffFindOptions, DiffOptions, Oid, Patch, Repository, Tree};
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+// Lines of an ignored commit are re-blamed in its parent; this bounds how many
+// ignored commits deep a line is followed
+const MAX_IGNORE_DEPTH: usize = 32;
+
+// Where a line of the blamed file came from
+#[derive(Clone)]
+struct Origin {
+    commit: Oid,
+    path: String,
+    line: usize, // 1-based, in `path` at `commit`
+    unblamable: bool,
+}
+
+fn blame_at<'r>(repo: &'r Repository, path: &str, commit: Oid, options: &BlameOptions) -> Result<Blame<'r>, String> {
+    let mut opts = git2::BlameOptions::new();
+    opts.newest_commit(commit).ignore_whitespace(options.ignore_whitespace);
+    repo.blame_file(Path::new(path), Some(&mut opts)).map_err(toe)
+}
+
+fn hunk_path(hunk: &BlameHunk, default: &str) -> String {
+    hunk.path().map(|p| p.to_string_lossy().to_string()).unwrap_or_else(|| default.to_string())
+}
+
+// Commits listed in blame.ignoreRevsFile plus any passed explicitly
+fn ignored_revs(repo: &Repository, options: &BlameOptions) -> Result<HashSet<Oid>, String> {
+    let mut revs = HashSet::new();
+
+    if options.ignore_revs_file {
+        let file = repo.config().and_then(|c| c.get_path("blame.ignoreRevsFile")).ok();
+        if let (Some(file), Some(workdir)) = (file, repo.workdir()) {
+            let content = fs::read_to_string(workdir.join(file)).unwrap_or_default();
+            for line in content.lines() {
+                let line = line.split('#').next().unwrap_or("").trim();
+                if let Ok(obj) = repo.revparse_single(line) {
+                    revs.insert(obj.peel_to_commit().map_err(toe)?.id());
+                }
+            }
+        }
+    }
+
+    for rev in &options.ignore_revs {
+        revs.insert(repo.revparse_single(rev).map_err(toe)?.peel_to_commit().map_err(toe)?.id());
+    }
+    Ok(revs)
+}
+
+// Name of `path` (in `tree`) before the change from `parent_tree`, following renames
+fn path_in_parent(repo: &Repository, parent_tree: &Tree, tree: &Tree, path: &str) -> Result<Option<String>, String> {
+    if parent_tree.get_path(Path::new(path)).is_ok() {
+        return Ok(Some(path.to_string()));
+    }
+
+    let mut diff = repo.diff_tree_to_tree(Some(parent_tree), Some(tree), None).map_err(toe)?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true))).map_err(toe)?;
+    for delta in diff.deltas() {
+        if delta.new_file().path().and_then(|p| p.to_str()) == Some(path) && delta.status() == git2::Delta::Renamed {
+            return Ok(delta.old_file().path().and_then(|p| p.to_str()).map(|p| p.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+struct ParentLines {
+    parent: Oid,
+    path: String,
+    lines: Vec<Option<usize>>, // Indexed by line - 1 in the child
+}
+
+// For each line of `path` at `commit`, the matching line in its first parent.
+// Unchanged lines map directly; changed lines map to the line they replaced
+// within the same block, and lines that were purely added map to nothing.
+fn map_to_parent(repo: &Repository, commit: &git2::Commit, path: &str, options: &BlameOptions) -> Result<Option<ParentLines>, String> {
+    let Ok(parent) = commit.parent(0) else { return Ok(None) };
+    let tree = commit.tree().map_err(toe)?;
+    let parent_tree = parent.tree().map_err(toe)?;
+    let Some(old_path) = path_in_parent(repo, &parent_tree, &tree, path)? else { return Ok(None) };
+
+    let new_blob = repo.find_blob(tree.get_path(Path::new(path)).map_err(toe)?.id()).map_err(toe)?;
+    let old_blob = repo.find_blob(parent_tree.get_path(Path::new(&old_path)).map_err(toe)?.id()).map_err(toe)?;
+
+    let mut opts = DiffOptions::new();
+    opts.context_lines(u32::MAX / 2).ignore_whitespace(options.ignore_whitespace);
+    let patch = Patch::from_blobs(&old_blob, Some(Path::new(&old_path)), &new_blob, Some(Path::new(path)), Some(&mut opts))
+        .map_err(toe)?;
+
+    let line_count = new_blob.content().split(|b| *b == b'\n').count();
+    let mut map = vec![None; line_count];
+    let mut replaced: Vec<usize> = vec![]; // Deleted lines of the current change block
+    let mut added_in_block = 0;
+
+    for h in 0..patch.num_hunks() {
+        for l in 0..patch.num_lines_in_hunk(h).map_err(toe)? {
+            let line = patch.line_in_hunk(h, l).map_err(toe)?;
+            match line.origin() {
+                '-' => {
+                    if added_in_block > 0 {
+                        replaced.clear();
+                        added_in_block = 0;
+                    }
+                    replaced.extend(line.old_lineno().map(|n| n as usize));
+                }
+                '+' => {
+                    if let Some(new) = line.new_lineno() {
+                        if let (Some(old), Some(slot)) = (replaced.get(added_in_block), map.get_mut(new as usize - 1)) {
+                            *slot = Some(*old);
+                        }
+                    }
+                    added_in_block += 1;
+                }
+                ' ' => {
+                    replaced.clear();
+                    added_in_block = 0;
+                    if let (Some(old), Some(new)) = (line.old_lineno(), line.new_lineno()) {
+                        if let Some(slot) = map.get_mut(new as usize - 1) {
+                            *slot = Some(old as usize);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(Some(ParentLines { parent: parent.id(), path: old_path, lines: map }))
+}
+
+// Move lines attributed to ignored commits onto whichever commit last touched
+// the corresponding line before them
+fn skip_ignored(repo: &Repository, origins: &mut [Origin], ignored: &HashSet<Oid>, options: &BlameOptions) -> Result<(), String> {
+    for _ in 0..MAX_IGNORE_DEPTH {
+        let mut groups: HashMap<(Oid, String), Vec<usize>> = HashMap::new();
+        for (i, origin) in origins.iter().enumerate() {
+            if ignored.contains(&origin.commit) && !origin.unblamable {
+                groups.entry((origin.commit, origin.path.clone())).or_default().push(i);
+            }
+        }
+        if groups.is_empty() {
+            return Ok(());
+        }
+
+        for ((oid, path), indices) in groups {
+            let commit = repo.find_commit(oid).map_err(toe)?;
+            let Some(ParentLines { parent, path: parent_path, lines: map }) = map_to_parent(repo, &commit, &path, options)? else {
+                for i in indices {
+                    origins[i].unblamable = true;
+                }
+                continue;
+            };
+
+            let blame = blame_at(repo, &parent_path, parent, options)?;
+            for i in indices {
+                let line = map.get(origins[i].line - 1).copied().flatten();
+                match line.and_then(|line| blame.get_line(line).map(|hunk| (line, hunk))) {
+                    Some((line, hunk)) => {
+                        origins[i] = Origin {
+                            commit: hunk.orig_commit_id(),
+                            path: hunk_path(&hunk, &parent_path),
+                            line: hunk.orig_start_line() + line - hunk.final_start_line(),
+                            unblamable: false,
+                        };
+                    }
+                    None => origins[i].unblamable = true,
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn blame_commit(repo: &Repository, commit: &git2::Commit, path: &str, options: &BlameOptions) -> Result<BlameResult, String> {
+    let tree = commit.tree().map_err(toe)?;
+    let entry = tree
+        .get_path(Path::new(path))
+        .map_err(|_| format!("{} does not exist in {}", path, commit.id()))?;
+    let blob = repo.find_blob(entry.id()).map_err(toe)?;
+    if blob.is_binary() {
+        return Err(format!("Cannot blame binary file {}", path));
+    }
+    let lines: Vec<String> = String::from_utf8_lossy(blob.content()).lines().map(|l| l.to_string()).collect();
+
+    let blame = blame_at(repo, path, commit.id(), options)?;
+    let mut origins = vec![];
+    for hunk in blame.iter() {
+        let origin_path = hunk_path(&hunk, path);
+        for offset in 0..hunk.lines_in_hunk() {
+            origins.push(Origin {
+                commit: hunk.orig_commit_id(),
+                path: origin_path.clone(),
+                line: hunk.orig_start_line() + offset,
+                unblamable: false,
+            });
+        }
+    }
+
+    let ignored = ignored_revs(repo, options)?;
+    if !ignored.is_empty() {
+        skip_ignored(repo, &mut origins, &ignored, options)?;
+    }
+
+    // Coalesce consecutive lines from the same place into ranges
+    let mut ranges: Vec<BlameRange> = vec![];
+    let mut commits: HashMap<Oid, git2::Commit> = HashMap::new();
+    for (i, origin) in origins.iter().enumerate() {
+        if let Some(last) = ranges.last_mut() {
+            let continues = last.oid == origin.commit.to_string()
+                && last.orig_path == origin.path
+                && last.unblamable == origin.unblamable
+                && (last.orig_start_line + last.line_count) as usize == origin.line;
+            if continues {
+                last.line_count += 1;
+                continue;
+            }
+        }
+
+        let source = match commits.entry(origin.commit) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(repo.find_commit(origin.commit).map_err(toe)?),
+        };
+        let author = source.author();
+        ranges.push(BlameRange {
+            start_line: i as u32 + 1,
+            line_count: 1,
+            oid: origin.commit.to_string(),
+            author: author.name().unwrap_or("Unknown").to_string(),
+            email: author.email().unwrap_or("").to_string(),
+            timestamp: author.when().seconds(),
+            summary: source.summary().unwrap_or("").to_string(),
+            orig_path: origin.path.clone(),
+            orig_start_line: origin.line as u32,
+            unblamable: origin.unblamable,
+        });
+    }
+
+    Ok(BlameResult { path: path.to_string(), oid: commit.id().to_string(), lines, ranges })
+}
+
+pub fn blame(repo_path: &str, path: &str, rev: Option<&str>, options: Option<BlameOptions>) -> Result<BlameResult, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let commit = repo
+        .revparse_single(rev.unwrap_or("HEAD"))
+        .map_err(toe)?
+        .peel_to_commit()
+        .map_err(toe)?;
+
+    blame_commit(&repo, &commit, path, &options.unwrap_or_default())
+}
+
+// Blame the file as it was just before `oid` changed it, following a rename
+// made by that commit, to dig past a line's most recent change
+pub fn blame_parent(repo_path: &str, oid: &str, path: &str, options: Option<BlameOptions>) -> Result<BlameResult, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let commit = repo.find_commit(Oid::from_str(oid).map_err(toe)?).map_err(toe)?;
+    let parent = commit.parent(0).map_err(|_| format!("{} has no parent", oid))?;
+
+    let parent_path = path_in_parent(&repo, &parent.tree().map_err(toe)?, &commit.tree().map_err(toe)?, path)?
+        .ok_or_else(|| format!("{} did not exist before {}", path, oid))?;
+
+    blame_commit(&repo, &parent, &parent_path, &options.unwrap_or_default())
+}
@@ -0,0 +1,108 @@
+use super::operations;
+use git2::{Repository, Status};
+use std::fs;
+use std::process::Command;
+
+// Status through the git CLI, for repositories set up to make it fast in ways
+// libgit2 can't use: the untracked cache, a split index and core.fsmonitor
+// (Watchman, or git's own daemon) all let git skip stat-ing most of the tree.
+// libgit2 can't read a split index at all.
+
+// Whether `repo` opted into any of that
+pub(crate) fn preferred(repo: &Repository) -> bool {
+    if repo.is_bare() {
+        return false;
+    }
+    // A split index can be turned on without touching config; its shared
+    // part lives next to the index
+    let split_index = fs::read_dir(repo.path())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .any(|entry| entry.file_name().to_string_lossy().starts_with("sharedindex."));
+    if split_index {
+        return true;
+    }
+
+    let Ok(config) = repo.config() else { return false };
+    let enabled = |key: &str| config.get_bool(key).unwrap_or(false);
+    // core.fsmonitor is either a bool (git's own daemon) or a hook path
+    let fsmonitor = match config.get_bool("core.fsmonitor") {
+        Ok(on) => on,
+        Err(_) => config.get_string("core.fsmonitor").is_ok_and(|hook| !hook.is_empty()),
+    };
+    let untracked_cache = enabled("core.untrackedCache") || config.get_string("core.untrackedCache").is_ok_and(|v| v == "keep");
+
+    fsmonitor || untracked_cache || enabled("core.splitIndex") || enabled("feature.manyFiles")
+}
+
+// Same entries as a libgit2 scan with untracked files and no rename detection
+pub(crate) fn status(repo: &Repository, paths: &[String]) -> Result<Vec<(String, Status)>, String> {
+    let workdir = repo.workdir().ok_or("Repository has no working directory")?;
+
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(workdir)
+        // No --ignore-submodules: like libgit2, git then honors
+        // diff.ignoreSubmodules and each submodule's own `ignore`
+        .args(["--literal-pathspecs", "status", "--porcelain=v2", "-z", "--untracked-files=all", "--no-renames", "--"])
+        .args(paths);
+
+    // git writes back the refreshed index (and with it the untracked cache and
+    // fsmonitor token) when it can; that's not a change anyone needs to hear about
+    let _refresh = operations::begin_index_refresh(repo);
+    let output = command.output().map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("git status failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(parse(&output.stdout))
+}
+
+// Entries of `git status --porcelain=v2 -z`
+fn parse(output: &[u8]) -> Vec<(String, Status)> {
+    let mut entries = vec![];
+    for record in output.split(|b| *b == 0).filter(|r| !r.is_empty()) {
+        let record = String::from_utf8_lossy(record);
+        let entry = match record.as_bytes()[0] {
+            // 1 XY sub mH mI mW hH hI path
+            b'1' => {
+                let mut fields = record.splitn(9, ' ');
+                fields.nth(1).zip(fields.nth(6)).map(|(xy, path)| (path, changed(xy)))
+            }
+            // u XY sub m1 m2 m3 mW h1 h2 h3 path
+            b'u' => record.splitn(11, ' ').nth(10).map(|path| (path, Status::CONFLICTED)),
+            // ? path
+            b'?' => record.get(2..).map(|path| (path, Status::WT_NEW)),
+            _ => None, // Ignored files, or headers we didn't ask for
+        };
+        if let Some((path, flags)) = entry {
+            entries.push((path.to_string(), flags));
+        }
+    }
+    // git lists untracked files last; libgit2 goes by path
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
+
+// The index (X) and working tree (Y) columns of a changed entry
+fn changed(xy: &str) -> Status {
+    let mut chars = xy.chars();
+    let index = match chars.next() {
+        Some('A') => Status::INDEX_NEW,
+        Some('M') => Status::INDEX_MODIFIED,
+        Some('D') => Status::INDEX_DELETED,
+        Some('T') => Status::INDEX_TYPECHANGE,
+        Some('R') => Status::INDEX_RENAMED,
+        _ => Status::empty(),
+    };
+    let workdir = match chars.next() {
+        Some('M') => Status::WT_MODIFIED,
+        Some('D') => Status::WT_DELETED,
+        Some('T') => Status::WT_TYPECHANGE,
+        // Intent to add: libgit2 sees an empty blob added, then modified
+        Some('A') => Status::INDEX_NEW | Status::WT_MODIFIED,
+        _ => Status::empty(),
+    };
+    index | workdir
+}
@@ -0,0 +1,433 @@
+use crate::domain::types::{ConflictChoice, ConflictFile, ConflictRegion, ConflictResolution, ConflictSide, ConflictStyle};
+use super::journal;
+use super::repo::toe;
+use git2::{build::CheckoutBuilder, Index, IndexEntry, Repository};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Conflict marker labels used when regenerating a conflicted file
+const OUR_LABEL: &str = "ours";
+const BASE_LABEL: &str = "base";
+const THEIR_LABEL: &str = "theirs";
+
+// Index stages of a conflicted path (1 = base, 2 = ours, 3 = theirs)
+struct Stages {
+    base: Option<IndexEntry>,
+    ours: Option<IndexEntry>,
+    theirs: Option<IndexEntry>,
+}
+
+fn read_stages(index: &Index, rel_path: &str) -> Result<Stages, String> {
+    let path = Path::new(rel_path);
+    let stages = Stages {
+        base: index.get_path(path, 1),
+        ours: index.get_path(path, 2),
+        theirs: index.get_path(path, 3),
+    };
+
+    if stages.base.is_none() && stages.ours.is_none() && stages.theirs.is_none() {
+        return Err(format!("{} is not conflicted", rel_path));
+    }
+
+    Ok(stages)
+}
+
+fn workdir(repo: &Repository) -> Result<&Path, String> {
+    repo.workdir().ok_or_else(|| "Repository has no working directory".to_string())
+}
+
+fn to_side(repo: &Repository, entry: &IndexEntry) -> Result<ConflictSide, String> {
+    let blob = repo.find_blob(entry.id).map_err(toe)?;
+    let is_binary = blob.is_binary();
+
+    Ok(ConflictSide {
+        oid: entry.id.to_string(),
+        mode: entry.mode,
+        size: blob.size(),
+        is_binary,
+        content: if is_binary { None } else { Some(String::from_utf8_lossy(blob.content()).into_owned()) },
+    })
+}
+
+// Style from merge.conflictStyle, falling back to plain merge markers
+fn configured_style(repo: &Repository) -> ConflictStyle {
+    let style = repo.config()
+        .and_then(|c| c.get_string("merge.conflictStyle"))
+        .unwrap_or_default();
+
+    match style.as_str() {
+        "diff3" => ConflictStyle::Diff3,
+        "zdiff3" => ConflictStyle::Zdiff3,
+        _ => ConflictStyle::Merge,
+    }
+}
+
+// Let libgit2 redo the three-way merge of a single path, writing the result with
+// diff3 markers into a scratch directory so the working copy is left untouched.
+fn merged_with_markers(repo: &Repository, index: &mut Index, rel_path: &str) -> Result<String, String> {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let scratch: PathBuf = std::env::temp_dir().join(format!("gitfrisky-conflict-{}-{}", std::process::id(), nanos));
+    fs::create_dir_all(&scratch).map_err(|e| format!("Failed to create temp dir: {}", e))?;
+
+    let mut checkout = CheckoutBuilder::new();
+    checkout
+        .target_dir(&scratch)
+        .path(rel_path)
+        .force()
+        .allow_conflicts(true)
+        .conflict_style_diff3(true)
+        .our_label(OUR_LABEL)
+        .ancestor_label(BASE_LABEL)
+        .their_label(THEIR_LABEL);
+
+    let result = repo.checkout_index(Some(index), Some(&mut checkout))
+        .map_err(toe)
+        .and_then(|_| {
+            let bytes = fs::read(scratch.join(rel_path)).map_err(|e| format!("Failed to read merged file: {}", e))?;
+            Ok(String::from_utf8_lossy(&bytes).into_owned())
+        });
+
+    let _ = fs::remove_dir_all(&scratch);
+    result
+}
+
+// A marker line is 7 marker chars followed by end of line or a label
+fn is_marker(line: &str, ch: char) -> bool {
+    let body = line.trim_end_matches(['\n', '\r']);
+    let count = body.chars().take_while(|c| *c == ch).count();
+    if count != 7 {
+        return false;
+    }
+    let rest = &body[7..];
+    if ch == '=' {
+        rest.trim().is_empty()
+    } else {
+        rest.is_empty() || rest.starts_with(' ')
+    }
+}
+
+enum ParseState {
+    Outside,
+    Ours,
+    Base,
+    Theirs,
+}
+
+// Split a file containing conflict markers (merge or diff3 style) into regions
+fn parse_conflict_markers(text: &str) -> Vec<ConflictRegion> {
+    let mut regions = vec![];
+    let mut state = ParseState::Outside;
+    let mut resolved = String::new();
+    let mut raw = String::new(); // Marker block as written, used if it is never closed
+    let mut ours = String::new();
+    let mut base: Option<String> = None;
+    let mut theirs = String::new();
+
+    for line in text.split_inclusive('\n') {
+        match state {
+            ParseState::Outside => {
+                if is_marker(line, '<') {
+                    state = ParseState::Ours;
+                    raw.push_str(line);
+                } else {
+                    resolved.push_str(line);
+                }
+                continue;
+            }
+            ParseState::Ours => {
+                if is_marker(line, '|') {
+                    state = ParseState::Base;
+                    base = Some(String::new());
+                } else if is_marker(line, '=') {
+                    state = ParseState::Theirs;
+                } else {
+                    ours.push_str(line);
+                }
+            }
+            ParseState::Base => {
+                if is_marker(line, '=') {
+                    state = ParseState::Theirs;
+                } else if let Some(ref mut b) = base {
+                    b.push_str(line);
+                }
+            }
+            ParseState::Theirs => {
+                if is_marker(line, '>') {
+                    if !resolved.is_empty() {
+                        regions.push(ConflictRegion::Resolved { text: std::mem::take(&mut resolved) });
+                    }
+                    regions.push(ConflictRegion::Conflict {
+                        index: 0,
+                        ours: std::mem::take(&mut ours),
+                        base: base.take(),
+                        theirs: std::mem::take(&mut theirs),
+                    });
+                    raw.clear();
+                    state = ParseState::Outside;
+                    continue;
+                }
+                theirs.push_str(line);
+            }
+        }
+        raw.push_str(line);
+    }
+
+    // Unterminated marker block: keep it as plain text
+    resolved.push_str(&raw);
+    if !resolved.is_empty() {
+        regions.push(ConflictRegion::Resolved { text: resolved });
+    }
+
+    renumber(regions)
+}
+
+// Merge adjacent resolved regions and assign conflict indices in order
+fn renumber(regions: Vec<ConflictRegion>) -> Vec<ConflictRegion> {
+    let mut out: Vec<ConflictRegion> = vec![];
+    let mut next = 0;
+
+    for region in regions {
+        match region {
+            ConflictRegion::Resolved { text } => {
+                if text.is_empty() {
+                    continue;
+                }
+                if let Some(ConflictRegion::Resolved { text: prev }) = out.last_mut() {
+                    prev.push_str(&text);
+                } else {
+                    out.push(ConflictRegion::Resolved { text });
+                }
+            }
+            ConflictRegion::Conflict { ours, base, theirs, .. } => {
+                out.push(ConflictRegion::Conflict { index: next, ours, base, theirs });
+                next += 1;
+            }
+        }
+    }
+
+    out
+}
+
+// zdiff3: move lines shared by both sides at the start/end of a conflict out of it
+fn hoist_common_lines(regions: Vec<ConflictRegion>) -> Vec<ConflictRegion> {
+    let mut out = vec![];
+
+    for region in regions {
+        let ConflictRegion::Conflict { ours, base, theirs, .. } = region else {
+            out.push(region);
+            continue;
+        };
+
+        let our_lines: Vec<&str> = ours.split_inclusive('\n').collect();
+        let their_lines: Vec<&str> = theirs.split_inclusive('\n').collect();
+        let shortest = our_lines.len().min(their_lines.len());
+
+        let prefix = our_lines.iter().zip(&their_lines).take_while(|(a, b)| a == b).count();
+        let suffix = our_lines.iter().rev().zip(their_lines.iter().rev())
+            .take(shortest - prefix)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        out.push(ConflictRegion::Resolved { text: our_lines[..prefix].concat() });
+        out.push(ConflictRegion::Conflict {
+            index: 0,
+            ours: our_lines[prefix..our_lines.len() - suffix].concat(),
+            base,
+            theirs: their_lines[prefix..their_lines.len() - suffix].concat(),
+        });
+        out.push(ConflictRegion::Resolved { text: our_lines[our_lines.len() - suffix..].concat() });
+    }
+
+    renumber(out)
+}
+
+fn apply_style(regions: Vec<ConflictRegion>, style: ConflictStyle) -> Vec<ConflictRegion> {
+    match style {
+        ConflictStyle::Diff3 => regions,
+        ConflictStyle::Zdiff3 => hoist_common_lines(regions),
+        ConflictStyle::Merge => regions
+            .into_iter()
+            .map(|r| match r {
+                ConflictRegion::Conflict { index, ours, theirs, .. } => ConflictRegion::Conflict { index, ours, base: None, theirs },
+                other => other,
+            })
+            .collect(),
+    }
+}
+
+fn choose(choice: ConflictChoice, ours: &str, base: Option<&str>, theirs: &str) -> Result<String, String> {
+    match choice {
+        ConflictChoice::Ours => Ok(ours.to_string()),
+        ConflictChoice::Theirs => Ok(theirs.to_string()),
+        ConflictChoice::Base => base.map(|b| b.to_string()).ok_or_else(|| "Base version is not available".to_string()),
+        ConflictChoice::Union => Ok(format!("{}{}", ours, theirs)),
+    }
+}
+
+// Rebuild file content from regions, taking one choice per conflict
+fn assemble(regions: &[ConflictRegion], choices: &[ConflictChoice]) -> Result<String, String> {
+    let conflicts = regions.iter().filter(|r| matches!(r, ConflictRegion::Conflict { .. })).count();
+    if conflicts != choices.len() {
+        return Err(format!("Expected {} conflict choices, got {}", conflicts, choices.len()));
+    }
+
+    let mut content = String::new();
+    for region in regions {
+        match region {
+            ConflictRegion::Resolved { text } => content.push_str(text),
+            ConflictRegion::Conflict { index, ours, base, theirs } => {
+                content.push_str(&choose(choices[*index], ours, base.as_deref(), theirs)?);
+            }
+        }
+    }
+
+    Ok(content)
+}
+
+fn write_workdir_file(repo: &Repository, rel_path: &str, content: &[u8], mode: Option<u32>) -> Result<(), String> {
+    let file_path = workdir(repo)?.join(rel_path);
+    if let Some(parent) = file_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    fs::write(&file_path, content).map_err(|e| format!("Failed to write {}: {}", rel_path, e))?;
+
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::PermissionsExt;
+        let perms = if mode == 0o100755 { 0o755 } else { 0o644 };
+        fs::set_permissions(&file_path, fs::Permissions::from_mode(perms))
+            .map_err(|e| format!("Failed to set permissions on {}: {}", rel_path, e))?;
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+
+    Ok(())
+}
+
+// Stage whatever is in the working tree for these paths; adding or removing
+// a path drops its conflict entries from the index
+fn stage_resolution(repo: &Repository, index: &mut Index, rel_paths: &[String]) -> Result<(), String> {
+    let root = workdir(repo)?;
+    for rel_path in rel_paths {
+        let path = Path::new(rel_path);
+        if root.join(path).exists() {
+            index.add_path(path).map_err(toe)?;
+        } else {
+            index.remove_path(path).map_err(toe)?;
+        }
+    }
+    index.write().map_err(toe)
+}
+
+pub fn list_conflicts(repo_path: &str) -> Result<Vec<String>, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let index = repo.index().map_err(toe)?;
+
+    let mut paths = vec![];
+    for conflict in index.conflicts().map_err(toe)? {
+        let conflict = conflict.map_err(toe)?;
+        let entry = conflict.our.or(conflict.their).or(conflict.ancestor);
+        if let Some(entry) = entry {
+            paths.push(String::from_utf8_lossy(&entry.path).into_owned());
+        }
+    }
+
+    Ok(paths)
+}
+
+pub fn get_conflict(repo_path: &str, rel_path: &str, style: Option<ConflictStyle>) -> Result<ConflictFile, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let mut index = repo.index().map_err(toe)?;
+    let stages = read_stages(&index, rel_path)?;
+    let style = style.unwrap_or_else(|| configured_style(&repo));
+
+    let base = stages.base.as_ref().map(|e| to_side(&repo, e)).transpose()?;
+    let ours = stages.ours.as_ref().map(|e| to_side(&repo, e)).transpose()?;
+    let theirs = stages.theirs.as_ref().map(|e| to_side(&repo, e)).transpose()?;
+
+    // Regions only make sense when both sides have text content
+    let mergeable = matches!((&ours, &theirs), (Some(o), Some(t)) if !o.is_binary && !t.is_binary);
+    let regions = if mergeable {
+        let text = merged_with_markers(&repo, &mut index, rel_path)?;
+        apply_style(parse_conflict_markers(&text), style)
+    } else {
+        vec![]
+    };
+
+    Ok(ConflictFile {
+        path: rel_path.to_string(),
+        base,
+        ours,
+        theirs,
+        style,
+        regions,
+    })
+}
+
+pub fn resolve_conflict(repo_path: &str, rel_path: &str, resolution: ConflictResolution) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let recorder = journal::begin(&repo, &format!("Resolve {}", rel_path), vec![rel_path.to_string()])?;
+    let mut index = repo.index().map_err(toe)?;
+    let stages = read_stages(&index, rel_path)?;
+
+    match resolution {
+        ConflictResolution::File { choice: ConflictChoice::Union } => {
+            let text = merged_with_markers(&repo, &mut index, rel_path)?;
+            // zdiff3 keeps the lines both sides share from being repeated
+            let regions = apply_style(parse_conflict_markers(&text), configured_style(&repo));
+            let count = regions.iter().filter(|r| matches!(r, ConflictRegion::Conflict { .. })).count();
+            let content = assemble(&regions, &vec![ConflictChoice::Union; count])?;
+            let mode = stages.ours.as_ref().map(|e| e.mode);
+            write_workdir_file(&repo, rel_path, content.as_bytes(), mode)?;
+        }
+        ConflictResolution::File { choice } => {
+            let entry = match choice {
+                ConflictChoice::Ours => stages.ours,
+                ConflictChoice::Theirs => stages.theirs,
+                _ => stages.base,
+            };
+
+            match entry {
+                Some(entry) => {
+                    let blob = repo.find_blob(entry.id).map_err(toe)?;
+                    write_workdir_file(&repo, rel_path, blob.content(), Some(entry.mode))?;
+                }
+                None => {
+                    // The chosen side deleted the file
+                    let file_path = workdir(&repo)?.join(rel_path);
+                    if file_path.exists() {
+                        fs::remove_file(&file_path).map_err(|e| format!("Failed to remove {}: {}", rel_path, e))?;
+                    }
+                }
+            }
+        }
+        ConflictResolution::Regions { choices, style } => {
+            if stages.ours.is_none() || stages.theirs.is_none() {
+                return Err(format!("{} was deleted on one side; resolve the whole file instead", rel_path));
+            }
+            // The same regions get_conflict showed: with zdiff3 their contents
+            // leave out the lines both sides share
+            let text = merged_with_markers(&repo, &mut index, rel_path)?;
+            let style = style.unwrap_or_else(|| configured_style(&repo));
+            let content = assemble(&apply_style(parse_conflict_markers(&text), style), &choices)?;
+            let mode = stages.ours.as_ref().map(|e| e.mode);
+            write_workdir_file(&repo, rel_path, content.as_bytes(), mode)?;
+        }
+    }
+
+    stage_resolution(&repo, &mut index, &[rel_path.to_string()])?;
+    recorder.finish(&repo);
+    Ok(())
+}
+
+pub fn mark_resolved(repo_path: &str, paths: &[String]) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let recorder = journal::begin(&repo, &journal::paths_label("Mark resolved", paths), paths.to_vec())?;
+    let mut index = repo.index().map_err(toe)?;
+    stage_resolution(&repo, &mut index, paths)?;
+    recorder.finish(&repo);
+    Ok(())
+}
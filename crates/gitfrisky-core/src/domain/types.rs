@@ -0,0 +1,626 @@
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+// Re-export RepoState for use in GitError
+pub use RepoState as RepoStateEnum;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Branch {
+    pub name: String,
+    pub full_name: String,
+    pub is_head: bool,
+    pub is_remote: bool,
+    pub upstream: Option<String>,
+    pub ahead: i32,
+    pub behind: i32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Commit {
+    pub oid: String,
+    pub author: String,
+    pub email: String,
+    pub timestamp: i64,
+    pub summary: String,
+    pub message: Option<String>, // Full message (lazy-loaded)
+    pub parents: Vec<String>,
+    pub refs: Vec<String>, // Branch/tag names pointing to this commit
+    pub lane: Option<u32>, // Computed lane for graph rendering
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FileChange {
+    pub path: String,
+    pub status: FileStatus,
+    pub old_path: Option<String>, // For renames
+    pub additions: u32,
+    pub deletions: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum FileStatus {
+    A, // Added
+    M, // Modified
+    D, // Deleted
+    R, // Renamed
+    U, // Untracked
+    C, // Conflicted
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DiffHunk {
+    pub header: String, // @@ -15,6 +15,9 @@
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+    #[serde(default)]
+    pub truncated: bool, // Lines cut off to fit the diff's line limit; can't be staged as shown
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DiffLine {
+    pub content: String,
+    #[serde(rename = "lineType")]
+    pub line_type: LineType,
+    #[serde(rename = "oldLineno")]
+    pub old_lineno: Option<u32>,
+    #[serde(rename = "newLineno")]
+    pub new_lineno: Option<u32>,
+    // Changed spans within the line, when intra-line highlighting was asked for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<ChangeRange>,
+}
+
+// Offsets are in UTF-16 code units so the UI can slice the string directly
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChangeRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IntralineMode {
+    Word,
+    Char,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum LineType {
+    Context,
+    Addition,
+    Deletion,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffAlgorithm {
+    Myers,
+    Minimal,
+    Patience,
+    Histogram, // libgit2 has no histogram diff; patience is used and reported instead
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RenameDetection {
+    Off,
+    Renames,
+    Copies,
+}
+
+// How a diff is computed for display. Unset fields follow the repository's
+// diff.algorithm and diff.renames config.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffViewOptions {
+    #[serde(default)]
+    pub ignore_all_space: bool, // -w
+    #[serde(default)]
+    pub ignore_space_at_eol: bool, // --ignore-space-at-eol
+    #[serde(default)]
+    pub ignore_space_change: bool, // -b
+    #[serde(default)]
+    pub ignore_blank_lines: bool, // --ignore-blank-lines
+    #[serde(default)]
+    pub context_lines: Option<u32>,
+    #[serde(default)]
+    pub algorithm: Option<DiffAlgorithm>,
+    #[serde(default)]
+    pub renames: Option<RenameDetection>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDiff {
+    pub path: String,
+    pub old_path: Option<String>, // Staged renames and copies: where the file came from
+    pub is_binary: bool,          // No lines; compare sizes and hashes instead
+    pub too_large: bool,          // Over the size limit, so not diffed at all
+    pub old_oid: Option<String>,  // None when the file is absent on that side
+    pub new_oid: Option<String>,
+    pub old_size: Option<u64>,
+    pub new_size: Option<u64>,
+    pub encoding: String,         // Encoding the lines were decoded from
+    pub algorithm: DiffAlgorithm, // Algorithm that produced the hunks, which may differ from the one asked for
+    pub hunks: Vec<DiffHunk>,
+    pub total_lines: usize,       // Lines in the full diff
+    pub truncated: bool,          // More than max_lines; request again with a higher limit
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RepoSummary {
+    pub path: String,
+    pub branches: Vec<Branch>,
+    pub head: Option<String>, // Current branch name or "detached HEAD"
+    pub is_bare: bool,
+    pub is_detached: bool,
+    pub state: RepoState,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StatusPayload {
+    pub unstaged: Vec<FileChange>,
+    pub staged: Vec<FileChange>,
+}
+
+// Emitted as `status-changed` when the watcher sees files change status.
+// `paths` lists every file whose entries changed; their old entries are
+// replaced by the ones in `unstaged` and `staged` (none if it's now clean).
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct StatusDelta {
+    pub paths: Vec<String>,
+    pub unstaged: Vec<FileChange>,
+    pub staged: Vec<FileChange>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStyle {
+    Merge,  // ours / theirs only
+    Diff3,  // ours / base / theirs
+    Zdiff3, // diff3 with common lines hoisted out of the conflict
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictSide {
+    pub oid: String,
+    pub mode: u32,
+    pub size: usize,
+    pub is_binary: bool,
+    pub content: Option<String>, // None for binary blobs
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ConflictRegion {
+    // Text both sides agree on (or that merged cleanly)
+    Resolved {
+        text: String,
+    },
+    // A conflict hunk; `index` is the position used when resolving per region
+    Conflict {
+        index: usize,
+        ours: String,
+        base: Option<String>, // Only present for diff3/zdiff3
+        theirs: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictFile {
+    pub path: String,
+    pub base: Option<ConflictSide>,
+    pub ours: Option<ConflictSide>,   // None if deleted on our side
+    pub theirs: Option<ConflictSide>, // None if deleted on their side
+    pub style: ConflictStyle,
+    pub regions: Vec<ConflictRegion>, // Empty for binary or delete/modify conflicts
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictChoice {
+    Ours,
+    Theirs,
+    Base,
+    Union, // Ours followed by theirs
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ConflictResolution {
+    // Take one side for the whole file
+    File { choice: ConflictChoice },
+    // One choice per conflict region, in order, as get_conflict returned them
+    // in `style` (merge.conflictStyle if not given)
+    Regions {
+        choices: Vec<ConflictChoice>,
+        #[serde(default)]
+        style: Option<ConflictStyle>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RebaseAction {
+    Pick,
+    Reword,
+    Edit,
+    Squash,
+    Fixup,
+    Drop,
+    Exec,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RebaseTodoEntry {
+    pub action: RebaseAction,
+    pub oid: Option<String>,     // None for exec
+    pub summary: Option<String>, // Commit summary, for display
+    pub message: Option<String>, // Replacement message for reword/squash/fixup
+    pub command: Option<String>, // Shell command for exec
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum RebaseStopReason {
+    Conflict,   // Resolve, stage, then continue
+    Edit,       // Commit applied; amend or continue
+    Reword,     // Changes applied; continue with the new message
+    ExecFailed, // An exec step exited non-zero
+    Empty,      // Nothing left to commit; stage changes and continue, or skip
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RebaseStatus {
+    pub in_progress: bool,
+    pub head_name: Option<String>, // Branch being rebased, None if detached
+    pub onto: Option<String>,
+    pub orig_head: Option<String>,
+    pub done: Vec<RebaseTodoEntry>, // Last entry is the current step
+    pub remaining: Vec<RebaseTodoEntry>,
+    pub stopped: Option<RebaseStopReason>,
+    pub message: Option<String>, // Message for the stopped commit
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PickOptions {
+    #[serde(default)]
+    pub no_commit: bool, // -n: leave the changes staged
+    #[serde(default)]
+    pub record_origin: bool, // -x: append "(cherry picked from commit ...)"
+    pub mainline: Option<u32>, // Parent number (1-based) to diff merge commits against
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SequenceKind {
+    CherryPick,
+    Revert,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SequenceStatus {
+    pub in_progress: bool,
+    pub kind: Option<SequenceKind>,
+    pub remaining: Vec<String>, // Oids still to apply, current step first
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResetMode {
+    Soft,  // Move HEAD only
+    Mixed, // Move HEAD and reset the index
+    Hard,  // Move HEAD, reset the index and the working tree
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetResult {
+    pub applied: bool, // false if a hard reset was refused because of dirty files
+    pub previous_head: String, // Also saved as ORIG_HEAD; reset to it to undo
+    pub new_head: String,
+    pub dirty_files: Vec<String>, // Changes a hard reset discards (or would discard)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub id: u64,
+    pub label: String, // e.g. "Stage 3 files"
+    pub timestamp: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalState {
+    pub undo: Vec<JournalEntry>, // Most recent last
+    pub redo: Vec<JournalEntry>, // Next to redo last
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ReflogEntry {
+    pub old_oid: String, // All zeros when the ref was created
+    pub new_oid: String,
+    pub message: Option<String>, // e.g. "reset: moving to HEAD~1"
+    pub committer: String,
+    pub timestamp: i64,
+    pub commit: Option<Commit>, // None if new_oid has been garbage collected
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DanglingCommit {
+    pub commit: Commit,
+    pub reflog: Option<String>, // Reflog still recording it, e.g. "HEAD"; None if only the object database has it
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BisectMark {
+    Good,
+    Bad,
+    Skip,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BisectStatus {
+    pub in_progress: bool,
+    pub bad: Option<String>,
+    pub good: Vec<String>,
+    pub skipped: Vec<String>,
+    pub pathspec: Vec<String>,
+    pub current: Option<String>, // Commit checked out for testing; None when done or waiting for marks
+    pub remaining: Vec<Commit>,  // Candidate range, newest first, including the bad commit
+    pub steps_left: u32,         // Rough number of tests still needed
+    pub first_bad: Option<Commit>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BisectStep {
+    pub oid: String,
+    pub summary: String,
+    pub mark: BisectMark,
+    pub exit_code: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BlameOptions {
+    #[serde(default)]
+    pub ignore_whitespace: bool,
+    #[serde(default)]
+    pub ignore_revs_file: bool, // Honour blame.ignoreRevsFile
+    #[serde(default)]
+    pub ignore_revs: Vec<String>, // Additional commits to look past
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BlameRange {
+    pub start_line: u32, // 1-based line in the blamed file
+    pub line_count: u32,
+    pub oid: String,
+    pub author: String,
+    pub email: String,
+    pub timestamp: i64,
+    pub summary: String,
+    pub orig_path: String,      // Path in `oid`, which differs after renames
+    pub orig_start_line: u32,   // Line number in `oid`
+    pub unblamable: bool,       // Changed by an ignored commit with no earlier origin
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BlameResult {
+    pub path: String,
+    pub oid: String, // Commit that was blamed
+    pub lines: Vec<String>,
+    pub ranges: Vec<BlameRange>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistoryEntry {
+    pub commit: Commit,
+    pub path: String,             // Name of the file at this commit
+    pub old_path: Option<String>, // Previous name when this commit renamed it
+    pub status: FileStatus,
+    pub hunks: Vec<DiffHunk>,     // Changes to this file only
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    Pickaxe, // -S: commits that change the number of occurrences
+    Regex,   // -G: commits with an added or removed line matching
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HistorySearch {
+    pub mode: SearchMode,
+    pub pattern: String,
+    #[serde(default)]
+    pub pickaxe_regex: bool, // Treat a pickaxe pattern as a regex
+    #[serde(default)]
+    pub ignore_case: bool,
+    pub range: Option<String>, // "a..b" or a single revision; defaults to HEAD
+    #[serde(default)]
+    pub paths: Vec<String>,
+    pub limit: Option<usize>, // Stop after this many matching commits
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchFileMatch {
+    pub path: String,
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+    pub search_id: u64,
+    pub commit: Commit,
+    pub files: Vec<SearchFileMatch>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchDone {
+    pub search_id: u64,
+    pub scanned: usize, // Commits checked
+    pub matched: usize,
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TreeEntryKind {
+    File,
+    Executable,
+    Symlink,
+    Directory,
+    Submodule, // Gitlink; `oid` is the submodule commit
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeEntry {
+    pub name: String,
+    pub path: String, // Relative to the repository root
+    pub oid: String,
+    pub mode: u32,
+    pub kind: TreeEntryKind,
+    pub size: Option<u64>, // Blobs only
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobContent {
+    pub path: String,
+    pub oid: String,
+    pub size: u64,
+    pub is_binary: bool,
+    pub encoding: String,        // "utf-8", "utf-16le", "iso-8859-1", ... or "binary"
+    pub content: Option<String>, // None for binary files
+    pub truncated: bool,         // Content was cut off at the size limit
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum RepoState {
+    Clean,
+    Merge,
+    Rebase,
+    RebaseInteractive,
+    RebaseMerge,
+    Revert,
+    CherryPick,
+    Bisect,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Stash {
+    pub index: usize,
+    pub message: String,
+    pub oid: String,
+    pub timestamp: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Tag {
+    pub name: String,
+    pub full_name: String,
+    pub target_oid: String,
+    pub message: Option<String>, // Annotated tag message
+    pub tagger: Option<String>,
+    pub timestamp: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Remote {
+    pub name: String,
+    pub url: String,
+    pub fetch_url: Option<String>,
+    pub push_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProgressEvent {
+    pub phase: ProgressPhase,
+    pub current: usize,
+    pub total: usize,
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ProgressPhase {
+    Counting,
+    Compressing,
+    Receiving,
+    Resolving,
+    Indexing,
+    Checkout,
+}
+
+#[derive(Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "message")]
+pub enum GitError {
+    #[error("Repository not found: {0}")]
+    RepoNotFound(String),
+
+    #[error("Not a git repository: {0}")]
+    NotARepo(String),
+
+    #[error("Invalid path: {0}")]
+    InvalidPath(String),
+
+    #[error("Operation failed: {0}")]
+    OperationFailed(String),
+
+    #[error("Authentication required")]
+    AuthRequired,
+
+    #[error("Merge conflict in {0}")]
+    MergeConflict(String),
+
+    #[error("Repository in unsafe state: {0:?}")]
+    UnsafeState(RepoState),
+
+    #[error("Nothing to commit")]
+    NothingToCommit,
+
+    #[error("Detached HEAD")]
+    DetachedHead,
+}
+
+// Convert git2::Error to GitError with user-friendly messages
+impl From<git2::Error> for GitError {
+    fn from(e: git2::Error) -> Self {
+        match e.code() {
+            git2::ErrorCode::NotFound => GitError::RepoNotFound(e.message().to_string()),
+            git2::ErrorCode::Auth => GitError::AuthRequired,
+            git2::ErrorCode::Conflict => GitError::MergeConflict(e.message().to_string()),
+            _ => GitError::OperationFailed(e.message().to_string()),
+        }
+    }
+}
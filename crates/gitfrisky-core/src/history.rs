@@ -0,0 +1,101 @@
+use crate::domain::types::{FileHistoryEntry, FileStatus};
+use super::repo::{collect_hunks, toe, to_commit};
+use git2::{DiffFindOptions, DiffOptions, Oid, Repository, Sort, Tree};
+use std::path::Path;
+
+fn blob_at(tree: &Tree, path: &str) -> Option<Oid> {
+    tree.get_path(Path::new(path)).ok().map(|entry| entry.id())
+}
+
+// Where `path` (which `tree` has but `parent` doesn't) was renamed or copied from
+fn rename_source(repo: &Repository, parent: &Tree, tree: &Tree, path: &str) -> Result<Option<String>, String> {
+    let mut diff = repo.diff_tree_to_tree(Some(parent), Some(tree), None).map_err(toe)?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true).copies(true))).map_err(toe)?;
+
+    for delta in diff.deltas() {
+        let renamed = matches!(delta.status(), git2::Delta::Renamed | git2::Delta::Copied);
+        if renamed && delta.new_file().path().and_then(|p| p.to_str()) == Some(path) {
+            return Ok(delta.old_file().path().and_then(|p| p.to_str()).map(|p| p.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+// Commits that changed `path`, newest first, following renames like
+// `git log --follow`. Each entry has the file's name at that commit and the
+// diff of just that file against the first parent.
+pub fn file_history(repo_path: &str, path: &str, rev: Option<&str>, limit: Option<usize>) -> Result<Vec<FileHistoryEntry>, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let limit = limit.unwrap_or(500);
+
+    let start = repo
+        .revparse_single(rev.unwrap_or("HEAD"))
+        .map_err(toe)?
+        .peel_to_commit()
+        .map_err(toe)?;
+
+    let mut revwalk = repo.revwalk().map_err(toe)?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME).map_err(toe)?;
+    revwalk.push(start.id()).map_err(toe)?;
+
+    let mut current = path.to_string();
+    let mut entries = vec![];
+
+    for oid in revwalk {
+        if entries.len() >= limit {
+            break;
+        }
+        let commit = repo.find_commit(oid.map_err(toe)?).map_err(toe)?;
+        let tree = commit.tree().map_err(toe)?;
+        let blob = blob_at(&tree, &current);
+
+        // A merge that kept the file from one of its parents didn't change it
+        let parent_trees = commit.parents().map(|p| p.tree()).collect::<Result<Vec<_>, _>>().map_err(toe)?;
+        if parent_trees.iter().any(|t| blob_at(t, &current) == blob) {
+            continue;
+        }
+        let parent_tree = parent_trees.into_iter().next();
+        let parent_blob = parent_tree.as_ref().and_then(|t| blob_at(t, &current));
+
+        let (status, old_path) = match (parent_blob, blob) {
+            (Some(_), Some(_)) => (FileStatus::M, None),
+            (Some(_), None) => (FileStatus::D, None),
+            (None, Some(_)) => {
+                let source = match &parent_tree {
+                    Some(parent) => rename_source(&repo, parent, &tree, &current)?,
+                    None => None,
+                };
+                match source {
+                    Some(source) => (FileStatus::R, Some(source)),
+                    None => (FileStatus::A, None),
+                }
+            }
+            (None, None) => continue,
+        };
+
+        let mut opts = DiffOptions::new();
+        opts.pathspec(&current).context_lines(3);
+        if let Some(old) = &old_path {
+            opts.pathspec(old);
+        }
+        let mut diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut opts)).map_err(toe)?;
+        if old_path.is_some() {
+            diff.find_similar(Some(DiffFindOptions::new().renames(true).copies(true))).map_err(toe)?;
+        }
+
+        entries.push(FileHistoryEntry {
+            commit: to_commit(&commit),
+            path: current.clone(),
+            old_path: old_path.clone(),
+            status,
+            hunks: collect_hunks(&diff)?,
+        });
+
+        // Keep following the file under its previous name
+        if let Some(old) = old_path {
+            current = old;
+        }
+    }
+
+    Ok(entries)
+}
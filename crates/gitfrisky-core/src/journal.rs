@@ -0,0 +1,571 @@
+use crate::domain::types::{JournalEntry, JournalState};
+use super::repo::toe;
+use super::operations::{self, OperationToken};
+use git2::{DiffOptions, FileMode, ObjectType, Oid, Repository, Tree};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Operation journal: every mutating command snapshots what it is about to
+// change (HEAD, the refs it touches, the index tree, and the content of any
+// working tree files it overwrites) before and after running. Undo restores
+// the "before" snapshot and redo the "after" one. File contents and index
+// trees are written to the object database, so the journal file only stores
+// oids and survives restarts; JOURNAL_REF keeps them reachable so gc doesn't
+// prune them.
+//
+// Cherry-picks, reverts and rebases are journaled when they run to the end in
+// one go. One that stops (conflict, edit, exec) is not: its abort is the way
+// back, and the commits made while finishing it are journaled one by one.
+// Bisect isn't journaled either, since bisect_reset returns to where it started.
+const JOURNAL_DIR: &str = "gitfrisky";
+const JOURNAL_FILE: &str = "journal.json";
+const JOURNAL_REF: &str = "refs/gitfrisky/journal";
+const MAX_ENTRIES: usize = 100;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", content = "target", rename_all = "lowercase")]
+enum HeadState {
+    Symbolic(String), // refs/heads/<branch>, possibly unborn
+    Detached(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct IndexStage {
+    stage: u16, // 0 = merged, 1-3 = base/ours/theirs of a conflict
+    mode: u32,
+    oid: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Snapshot {
+    head: HeadState,
+    index_tree: Option<String>,               // None if the index could not be written (conflicts)
+    refs: BTreeMap<String, Option<String>>,   // None = ref did not exist
+    files: BTreeMap<String, Option<String>>,  // Blob of the working tree file; None = absent
+    #[serde(default)]
+    modes: BTreeMap<String, u32>,             // Git filemode of each file present in `files`
+    #[serde(default)]
+    index_entries: BTreeMap<String, Vec<IndexStage>>, // Index entries of `files`, when index_tree is None
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Record {
+    id: u64,
+    label: String,
+    timestamp: i64,
+    before: Snapshot,
+    after: Snapshot,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct Journal {
+    next_id: u64,
+    undo: Vec<Record>,
+    redo: Vec<Record>,
+}
+
+fn journal_path(repo: &Repository) -> PathBuf {
+    repo.path().join(JOURNAL_DIR).join(JOURNAL_FILE)
+}
+
+fn load(repo: &Repository) -> Journal {
+    fs::read_to_string(journal_path(repo))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save(repo: &Repository, journal: &Journal) -> Result<(), String> {
+    let path = journal_path(repo);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create journal directory: {}", e))?;
+    }
+    let json = serde_json::to_string(journal).map_err(toe)?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write journal: {}", e))?;
+    anchor(repo, journal)
+}
+
+// Point JOURNAL_REF at a tree holding every blob and index tree the
+// journal refers to, so they stay reachable
+fn anchor(repo: &Repository, journal: &Journal) -> Result<(), String> {
+    let mut blobs = BTreeSet::new();
+    let mut trees = BTreeSet::new();
+    for record in journal.undo.iter().chain(&journal.redo) {
+        for snapshot in [&record.before, &record.after] {
+            trees.extend(snapshot.index_tree.iter().cloned());
+            blobs.extend(snapshot.files.values().flatten().cloned());
+            blobs.extend(snapshot.index_entries.values().flatten().map(|e| e.oid.clone()));
+        }
+    }
+
+    if blobs.is_empty() && trees.is_empty() {
+        if let Ok(mut reference) = repo.find_reference(JOURNAL_REF) {
+            reference.delete().map_err(toe)?;
+        }
+        return Ok(());
+    }
+
+    let mut builder = repo.treebuilder(None).map_err(toe)?;
+    for (oids, mode) in [(&blobs, FileMode::Blob), (&trees, FileMode::Tree)] {
+        for oid in oids {
+            // Objects pruned before anchoring existed can't be kept any more
+            if repo.find_object(Oid::from_str(oid).map_err(toe)?, None).is_ok() {
+                builder.insert(oid, Oid::from_str(oid).map_err(toe)?, mode.into()).map_err(toe)?;
+            }
+        }
+    }
+    // A tree rather than a commit, so replaced anchors don't pile up as dangling commits
+    let tree = builder.write().map_err(toe)?;
+    repo.reference(JOURNAL_REF, tree, true, "gitfrisky: journal").map_err(toe)?;
+    Ok(())
+}
+
+// Content and git filemode of a working tree file; symlinks are read as their target
+fn read_workdir_file(full: &Path) -> Result<Option<(Vec<u8>, u32)>, String> {
+    let Ok(metadata) = fs::symlink_metadata(full) else { return Ok(None) };
+    let read_error = |e: std::io::Error| format!("Failed to read {}: {}", full.display(), e);
+
+    if metadata.file_type().is_symlink() {
+        let target = fs::read_link(full).map_err(read_error)?;
+        return Ok(Some((target.to_string_lossy().as_bytes().to_vec(), FileMode::Link.into())));
+    }
+    if !metadata.is_file() {
+        return Ok(None);
+    }
+
+    #[cfg(unix)]
+    let executable = {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.permissions().mode() & 0o111 != 0
+    };
+    #[cfg(not(unix))]
+    let executable = false;
+
+    let mode = if executable { FileMode::BlobExecutable } else { FileMode::Blob };
+    Ok(Some((fs::read(full).map_err(read_error)?, mode.into())))
+}
+
+fn write_workdir_file(full: &Path, content: &[u8], mode: u32) -> Result<(), String> {
+    let write_error = |e: std::io::Error| format!("Failed to restore {}: {}", full.display(), e);
+    if let Some(parent) = full.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    // Replace rather than write through an existing symlink
+    if fs::symlink_metadata(full).map(|m| m.file_type().is_symlink()).unwrap_or(false) {
+        fs::remove_file(full).map_err(write_error)?;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if mode == u32::from(FileMode::Link) {
+            if full.exists() {
+                fs::remove_file(full).map_err(write_error)?;
+            }
+            let target = String::from_utf8_lossy(content).into_owned();
+            return std::os::unix::fs::symlink(target, full).map_err(write_error);
+        }
+        fs::write(full, content).map_err(write_error)?;
+        let perms = if mode == u32::from(FileMode::BlobExecutable) { 0o755 } else { 0o644 };
+        fs::set_permissions(full, fs::Permissions::from_mode(perms)).map_err(write_error)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = mode;
+        fs::write(full, content).map_err(write_error)
+    }
+}
+
+fn index_entries(repo: &Repository, files: &[String]) -> Result<BTreeMap<String, Vec<IndexStage>>, String> {
+    let index = repo.index().map_err(toe)?;
+    let mut entries = BTreeMap::new();
+    for path in files {
+        let stages: Vec<IndexStage> = (0..=3)
+            .filter_map(|stage| index.get_path(Path::new(path), stage))
+            .map(|e| IndexStage { stage: (e.flags >> 12) & 0x3, mode: e.mode, oid: e.id.to_string() })
+            .collect();
+        entries.insert(path.clone(), stages);
+    }
+    Ok(entries)
+}
+
+fn workdir(repo: &Repository) -> Result<&Path, String> {
+    repo.workdir().ok_or_else(|| "Repository has no working directory".to_string())
+}
+
+fn capture(repo: &Repository, refs: &[String], files: &[String]) -> Result<Snapshot, String> {
+    let head_ref = repo.find_reference("HEAD").map_err(toe)?;
+    let head = match head_ref.symbolic_target() {
+        Some(target) => HeadState::Symbolic(target.to_string()),
+        None => HeadState::Detached(head_ref.target().map(|o| o.to_string()).unwrap_or_default()),
+    };
+
+    let index_tree = repo.index()
+        .and_then(|mut index| index.write_tree())
+        .ok()
+        .map(|oid| oid.to_string());
+
+    let refs = refs
+        .iter()
+        .map(|name| {
+            let oid = repo.find_reference(name).ok().and_then(|r| r.target()).map(|o| o.to_string());
+            (name.clone(), oid)
+        })
+        .collect();
+
+    let root = workdir(repo)?;
+    let mut snapshot_files = BTreeMap::new();
+    let mut modes = BTreeMap::new();
+    for path in files {
+        let blob = match read_workdir_file(&root.join(path))? {
+            Some((content, mode)) => {
+                modes.insert(path.clone(), mode);
+                Some(repo.blob(&content).map_err(toe)?.to_string())
+            }
+            None => None,
+        };
+        snapshot_files.insert(path.clone(), blob);
+    }
+
+    // A conflicted index has no tree, so keep the stages of the files we touch
+    let index_entries = if index_tree.is_none() { index_entries(repo, files)? } else { BTreeMap::new() };
+
+    Ok(Snapshot { head, index_tree, refs, files: snapshot_files, modes, index_entries })
+}
+
+// True if the repository still looks exactly like `snapshot`
+fn matches_current(repo: &Repository, snapshot: &Snapshot) -> Result<bool, String> {
+    let refs: Vec<String> = snapshot.refs.keys().cloned().collect();
+    let mut current = capture(repo, &refs, &[])?;
+
+    if current.head != snapshot.head || current.refs != snapshot.refs {
+        return Ok(false);
+    }
+    if snapshot.index_tree.is_some() && current.index_tree != snapshot.index_tree {
+        return Ok(false);
+    }
+
+    if snapshot.index_tree.is_none() && !snapshot.index_entries.is_empty() {
+        let paths: Vec<String> = snapshot.index_entries.keys().cloned().collect();
+        if index_entries(repo, &paths)? != snapshot.index_entries {
+            return Ok(false);
+        }
+    }
+
+    // Compare working tree files by hash without writing new blobs. Journals
+    // written before modes were recorded have none to compare.
+    let root = workdir(repo)?;
+    for path in snapshot.files.keys() {
+        let hash = match read_workdir_file(&root.join(path))? {
+            Some((content, mode)) => {
+                current.modes.insert(path.clone(), mode);
+                Some(Oid::hash_object(ObjectType::Blob, &content).map_err(toe)?.to_string())
+            }
+            None => None,
+        };
+        current.files.insert(path.clone(), hash);
+    }
+
+    let modes_match = snapshot.modes.is_empty() || current.modes == snapshot.modes;
+    Ok(current.files == snapshot.files && modes_match)
+}
+
+// Bring the index in line with `tree`, rewriting only entries that differ so
+// unchanged files keep their stat cache
+fn restore_index(repo: &Repository, tree: &Tree) -> Result<(), String> {
+    let mut index = repo.index().map_err(toe)?;
+
+    if index.has_conflicts() {
+        index.read_tree(tree).map_err(toe)?;
+        return index.write().map_err(toe);
+    }
+
+    let current = repo.find_tree(index.write_tree().map_err(toe)?).map_err(toe)?;
+    let diff = repo.diff_tree_to_tree(Some(&current), Some(tree), None).map_err(toe)?;
+
+    for delta in diff.deltas() {
+        let Some(path) = delta.new_file().path().or(delta.old_file().path()) else { continue };
+        match tree.get_path(path) {
+            Ok(entry) => {
+                let size = repo.find_blob(entry.id()).map(|b| b.size() as u32).unwrap_or(0);
+                index.add(&git2::IndexEntry {
+                    ctime: git2::IndexTime::new(0, 0),
+                    mtime: git2::IndexTime::new(0, 0),
+                    dev: 0,
+                    ino: 0,
+                    mode: entry.filemode() as u32,
+                    uid: 0,
+                    gid: 0,
+                    file_size: size,
+                    id: entry.id(),
+                    flags: 0,
+                    flags_extended: 0,
+                    path: path.to_string_lossy().as_bytes().to_vec(),
+                }).map_err(toe)?;
+            }
+            Err(_) => index.remove_path(path).map_err(toe)?,
+        }
+    }
+
+    index.write().map_err(toe)
+}
+
+// Put back the exact index entries (conflict stages included) of some paths
+fn restore_index_entries(repo: &Repository, entries: &BTreeMap<String, Vec<IndexStage>>) -> Result<(), String> {
+    let mut index = repo.index().map_err(toe)?;
+    for (path, stages) in entries {
+        for stage in 0..=3 {
+            let _ = index.remove(Path::new(path), stage);
+        }
+        for entry in stages {
+            let id = Oid::from_str(&entry.oid).map_err(toe)?;
+            let size = repo.find_blob(id).map(|b| b.size() as u32).unwrap_or(0);
+            index.add(&git2::IndexEntry {
+                ctime: git2::IndexTime::new(0, 0),
+                mtime: git2::IndexTime::new(0, 0),
+                dev: 0,
+                ino: 0,
+                mode: entry.mode,
+                uid: 0,
+                gid: 0,
+                file_size: size,
+                id,
+                flags: entry.stage << 12,
+                flags_extended: 0,
+                path: path.as_bytes().to_vec(),
+            }).map_err(toe)?;
+        }
+    }
+    index.write().map_err(toe)
+}
+
+// Declare the refs and files restoring `snapshot` writes, and the journal
+// saved after it, so the watcher skips them like any other operation's
+fn begin_restore(repo: &Repository, snapshot: &Snapshot) -> OperationToken {
+    let mut refs: Vec<String> = snapshot.refs.keys().cloned().collect();
+    refs.push(JOURNAL_REF.to_string());
+    let files: Vec<String> = snapshot.files.keys().cloned().collect();
+    operations::begin_operation(repo, &refs, &files)
+}
+
+fn restore(repo: &Repository, snapshot: &Snapshot) -> Result<(), String> {
+    for (name, oid) in &snapshot.refs {
+        match oid {
+            Some(oid) => {
+                repo.reference(name, Oid::from_str(oid).map_err(toe)?, true, "gitfrisky: undo/redo").map_err(toe)?;
+            }
+            None => {
+                if let Ok(mut reference) = repo.find_reference(name) {
+                    reference.delete().map_err(toe)?;
+                }
+            }
+        }
+    }
+
+    match &snapshot.head {
+        HeadState::Symbolic(name) => repo.set_head(name).map_err(toe)?,
+        HeadState::Detached(oid) => repo.set_head_detached(Oid::from_str(oid).map_err(toe)?).map_err(toe)?,
+    }
+
+    match &snapshot.index_tree {
+        Some(tree) => {
+            let tree = repo.find_tree(Oid::from_str(tree).map_err(toe)?).map_err(toe)?;
+            restore_index(repo, &tree)?;
+        }
+        None => restore_index_entries(repo, &snapshot.index_entries)?,
+    }
+
+    let root = workdir(repo)?;
+    for (path, blob) in &snapshot.files {
+        let full = root.join(path);
+        match blob {
+            Some(oid) => {
+                let blob = repo.find_blob(Oid::from_str(oid).map_err(toe)?).map_err(toe)?;
+                let mode = snapshot.modes.get(path).copied().unwrap_or(FileMode::Blob.into());
+                write_workdir_file(&full, blob.content(), mode)?;
+            }
+            None => {
+                if fs::symlink_metadata(&full).is_ok() {
+                    fs::remove_file(&full).map_err(|e| format!("Failed to remove {}: {}", path, e))?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Working tree files that differ from `tree` (optionally limited to pathspecs),
+// i.e. what a forced checkout of `tree` would overwrite
+pub(crate) fn files_changed_by_checkout(repo: &Repository, tree: &Tree, pathspecs: &[String]) -> Result<Vec<String>, String> {
+    let mut opts = DiffOptions::new();
+    for spec in pathspecs {
+        opts.pathspec(spec);
+    }
+    let diff = repo.diff_tree_to_workdir_with_index(Some(tree), Some(&mut opts)).map_err(toe)?;
+
+    let mut files = vec![];
+    for delta in diff.deltas() {
+        for file in [delta.old_file(), delta.new_file()] {
+            if let Some(path) = file.path().and_then(|p| p.to_str()) {
+                if !files.iter().any(|f| f == path) {
+                    files.push(path.to_string());
+                }
+            }
+        }
+    }
+    Ok(files)
+}
+
+// "Stage a.txt" / "Stage 3 files"
+pub(crate) fn paths_label(verb: &str, paths: &[String]) -> String {
+    match paths {
+        [single] => format!("{} {}", verb, single),
+        _ => format!("{} {} files", verb, paths.len()),
+    }
+}
+
+// In-flight journal record, created before a command mutates the repository
+pub(crate) struct Recorder {
+    label: String,
+    refs: Vec<String>,
+    files: Vec<String>,
+    clean: bool, // Started on a clean working tree; `files` is worked out when finishing
+    before: Snapshot,
+    _operation: OperationToken, // Lets the watcher skip our own writes until the record is done
+}
+
+// Snapshot the branch HEAD points to (and HEAD itself) plus the given working tree files
+pub(crate) fn begin(repo: &Repository, label: &str, files: Vec<String>) -> Result<Recorder, String> {
+    begin_with_refs(repo, label, vec![], files)
+}
+
+// Like `begin`, also snapshotting refs other than HEAD's branch
+pub(crate) fn begin_with_refs(repo: &Repository, label: &str, extra_refs: Vec<String>, files: Vec<String>) -> Result<Recorder, String> {
+    let mut refs = vec![];
+    if let Ok(head) = repo.find_reference("HEAD") {
+        if let Some(target) = head.symbolic_target() {
+            refs.push(target.to_string());
+        }
+    }
+    for name in extra_refs {
+        if !refs.contains(&name) {
+            refs.push(name);
+        }
+    }
+
+    // Saving the record moves JOURNAL_REF too; that's our write as well
+    let mut written = refs.clone();
+    written.push(JOURNAL_REF.to_string());
+    let operation = operations::begin_operation(repo, &written, &files);
+    let before = capture(repo, &refs, &files)?;
+    Ok(Recorder { label: label.to_string(), refs, files, clean: false, before, _operation: operation })
+}
+
+// For commands that refuse to run on a dirty working tree and may touch any
+// file: every tracked file matched the index when we started, so the files to
+// snapshot are found by diffing against the starting index tree when finishing
+pub(crate) fn begin_clean(repo: &Repository, label: &str) -> Result<Recorder, String> {
+    let mut recorder = begin(repo, label, vec![])?;
+    recorder.clean = true;
+    Ok(recorder)
+}
+
+impl Recorder {
+    // Record the operation; journal failures are logged rather than failing an
+    // operation that already succeeded
+    pub(crate) fn finish(self, repo: &Repository) {
+        if let Err(e) = self.try_finish(repo) {
+            eprintln!("Failed to record '{}' in journal: {}", self.label, e);
+        }
+    }
+
+    fn try_finish(&self, repo: &Repository) -> Result<(), String> {
+        let mut before = self.before.clone();
+        let mut files = self.files.clone();
+        if self.clean {
+            let tree = before.index_tree.as_deref().ok_or("Index had conflicts when the operation started")?;
+            let tree = repo.find_tree(Oid::from_str(tree).map_err(toe)?).map_err(toe)?;
+            files = files_changed_by_checkout(repo, &tree, &[])?;
+            for path in &files {
+                let entry = tree.get_path(Path::new(path)).ok();
+                before.files.insert(path.clone(), entry.as_ref().map(|e| e.id().to_string()));
+                if let Some(entry) = entry {
+                    before.modes.insert(path.clone(), entry.filemode() as u32);
+                }
+            }
+        }
+
+        let after = capture(repo, &self.refs, &files)?;
+        if after == before {
+            return Ok(()); // Nothing changed, nothing to undo
+        }
+
+        let mut journal = load(repo);
+        journal.next_id += 1;
+        journal.undo.push(Record {
+            id: journal.next_id,
+            label: self.label.clone(),
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0),
+            before,
+            after,
+        });
+        if journal.undo.len() > MAX_ENTRIES {
+            journal.undo.remove(0);
+        }
+        journal.redo.clear();
+        save(repo, &journal)
+    }
+}
+
+fn summarize(records: &[Record]) -> Vec<JournalEntry> {
+    records
+        .iter()
+        .map(|r| JournalEntry { id: r.id, label: r.label.clone(), timestamp: r.timestamp })
+        .collect()
+}
+
+pub fn journal(repo_path: &str) -> Result<JournalState, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let journal = load(&repo);
+    Ok(JournalState { undo: summarize(&journal.undo), redo: summarize(&journal.redo) })
+}
+
+pub fn undo(repo_path: &str) -> Result<Option<JournalEntry>, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let mut journal = load(&repo);
+
+    let Some(record) = journal.undo.pop() else { return Ok(None) };
+    if !matches_current(&repo, &record.after)? {
+        return Err(format!("Cannot undo '{}': the repository has changed since", record.label));
+    }
+
+    let _operation = begin_restore(&repo, &record.before);
+    restore(&repo, &record.before)?;
+    let entry = JournalEntry { id: record.id, label: record.label.clone(), timestamp: record.timestamp };
+    journal.redo.push(record);
+    save(&repo, &journal)?;
+    Ok(Some(entry))
+}
+
+pub fn redo(repo_path: &str) -> Result<Option<JournalEntry>, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let mut journal = load(&repo);
+
+    let Some(record) = journal.redo.pop() else { return Ok(None) };
+    if !matches_current(&repo, &record.before)? {
+        return Err(format!("Cannot redo '{}': the repository has changed since", record.label));
+    }
+
+    let _operation = begin_restore(&repo, &record.after);
+    restore(&repo, &record.after)?;
+    let entry = JournalEntry { id: record.id, label: record.label.clone(), timestamp: record.timestamp };
+    journal.undo.push(record);
+    save(&repo, &journal)?;
+    Ok(Some(entry))
+}
@@ -0,0 +1,23 @@
+// GitFrisky's git logic, independent of any UI. The desktop app exposes these
+// functions as Tauri commands and the gitfrisky CLI calls them directly.
+// Functions take the repository's root directory and return errors as
+// messages fit to show a user.
+
+pub mod domain;
+pub mod util;
+
+pub mod repo;
+pub mod operations;
+pub mod status_cache;
+pub mod cli_status;
+pub mod conflict;
+pub mod rebase;
+pub mod sequencer;
+pub mod reset;
+pub mod journal;
+pub mod reflog;
+pub mod bisect;
+pub mod blame;
+pub mod history;
+pub mod search;
+pub mod tree;
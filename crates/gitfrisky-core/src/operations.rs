@@ -0,0 +1,183 @@
+use git2::Repository;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+// Bookkeeping shared by every command that writes to a repository and the
+// desktop app's file watcher, which skips the writes recorded here.
+
+// Where a repository keeps its files. In linked worktrees and submodules
+// `.git` is a file pointing elsewhere, and a linked worktree keeps only its
+// own HEAD and index there, sharing refs and config with the main repository.
+#[derive(Clone)]
+pub struct RepoLayout {
+    pub workdir: Option<PathBuf>, // None for bare repositories
+    pub git_dir: PathBuf,
+    pub common_dir: PathBuf,
+}
+
+impl RepoLayout {
+    pub fn resolve(repo: &Repository) -> Self {
+        // Canonical, to match the paths file watchers report
+        let canonical = |p: &Path| fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+        let git_dir = canonical(repo.path());
+        // git2 doesn't expose the common dir; linked worktrees name it in `commondir`
+        let common_dir = match fs::read_to_string(git_dir.join("commondir")) {
+            Ok(dir) => canonical(&git_dir.join(dir.trim())),
+            Err(_) => git_dir.clone(),
+        };
+        Self { workdir: repo.workdir().map(canonical), git_dir, common_dir }
+    }
+}
+
+// What a file looked like, or None while it doesn't exist. Besides mtime and
+// size, Unix gives us the inode (a file renamed into place gets a new one) and
+// the change time, which any write moves and nobody can set back. What still
+// slips through: an in-place write of the same size within the same timestamp
+// tick, which only coarse filesystems (FAT, HFS+, ext3) make likely, and any
+// same-size write within the mtime tick on other platforms.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Stamp {
+    modified: SystemTime,
+    len: u64,
+    inode: u64,
+    changed: (i64, i64), // ctime seconds and nanoseconds
+}
+
+type Fingerprint = Option<Stamp>;
+
+fn fingerprint(path: &Path) -> Fingerprint {
+    let metadata = fs::symlink_metadata(path).ok()?;
+
+    #[cfg(unix)]
+    let (inode, changed) = {
+        use std::os::unix::fs::MetadataExt;
+        (metadata.ino(), (metadata.ctime(), metadata.ctime_nsec()))
+    };
+    #[cfg(not(unix))]
+    let (inode, changed) = (0, (0, 0));
+
+    Some(Stamp { modified: metadata.modified().ok()?, len: metadata.len(), inode, changed })
+}
+
+// How long a recorded write waits for the watcher to report it, and how long
+// it keeps matching once it has (one write can show up as several events)
+const UNREPORTED_TTL: Duration = Duration::from_secs(60);
+const REPORTED_TTL: Duration = Duration::from_secs(1);
+
+// A file as one of our operations left it, until `expires`
+struct Written {
+    fingerprint: Fingerprint,
+    expires: Instant,
+}
+
+// Operations GitFrisky itself is running or has run, so the watcher can tell
+// its own writes (which the UI already refreshes after) from anyone else's
+struct Operations {
+    next_id: u64,
+    finished: u64,
+    running: Vec<(u64, Vec<PathBuf>)>, // Files each operation in flight may write
+    written: BTreeMap<PathBuf, Written>, // What finished operations left files as
+}
+
+static OPERATIONS: Mutex<Operations> = Mutex::new(Operations { next_id: 0, finished: 0, running: vec![], written: BTreeMap::new() });
+
+// How many of our own status-changing operations have finished, to tell if any ran since
+pub fn finished_operations() -> u64 {
+    OPERATIONS.lock().unwrap().finished
+}
+
+impl Operations {
+    // Still as a finished operation left it. Only what's on disk counts: a file
+    // an operation is still writing may be written by someone else meanwhile,
+    // so its changes are reported until the operation records what it wrote.
+    fn wrote(&mut self, path: &Path) -> bool {
+        let now = Instant::now();
+        self.written.retain(|_, written| written.expires > now);
+        let Some(written) = self.written.get_mut(path) else { return false };
+        if written.fingerprint != fingerprint(path) {
+            // Someone else has written it since
+            self.written.remove(path);
+            return false;
+        }
+        written.expires = written.expires.min(now + REPORTED_TTL);
+        true
+    }
+
+    fn is_running(&self, path: &Path) -> bool {
+        self.running.iter().any(|(_, paths)| paths.iter().any(|p| p == path))
+    }
+}
+
+// Held while a command mutates the repository; dropping it records what the
+// operation left behind
+pub struct OperationToken {
+    id: u64,
+    files: Vec<(PathBuf, Fingerprint)>, // Declared files as they were before
+    changes_status: bool,
+}
+
+// Declare an operation that may write the index, HEAD (and ORIG_HEAD), `refs`
+// and the working tree `files` (relative to the repo root)
+pub fn begin_operation(repo: &Repository, refs: &[String], files: &[String]) -> OperationToken {
+    let layout = RepoLayout::resolve(repo);
+    let mut paths: Vec<PathBuf> = ["index", "HEAD", "ORIG_HEAD"].iter().map(|name| layout.git_dir.join(name)).collect();
+    paths.extend(refs.iter().map(|name| layout.common_dir.join(name)));
+    if let Some(workdir) = &layout.workdir {
+        paths.extend(files.iter().map(|file| workdir.join(file)));
+    }
+    begin(paths, true)
+}
+
+// Declare a command that only rewrites the index's cached file stats, as
+// `git status` does, leaving every file's status as it was
+pub fn begin_index_refresh(repo: &Repository) -> OperationToken {
+    begin(vec![RepoLayout::resolve(repo).git_dir.join("index")], false)
+}
+
+fn begin(paths: Vec<PathBuf>, changes_status: bool) -> OperationToken {
+    let files = paths.iter().map(|path| (path.clone(), fingerprint(path))).collect();
+
+    let mut operations = OPERATIONS.lock().unwrap();
+    operations.next_id += 1;
+    let id = operations.next_id;
+    operations.running.push((id, paths));
+    OperationToken { id, files, changes_status }
+}
+
+impl Drop for OperationToken {
+    fn drop(&mut self) {
+        let mut operations = OPERATIONS.lock().unwrap();
+        operations.running.retain(|(id, _)| *id != self.id);
+        if self.changes_status {
+            operations.finished += 1;
+        }
+        let expires = Instant::now() + UNREPORTED_TTL;
+        for (path, before) in self.files.drain(..) {
+            let after = fingerprint(&path);
+            if after != before {
+                operations.written.insert(path, Written { fingerprint: after, expires });
+            }
+        }
+    }
+}
+
+// Whether a change to `path` came from one of our own operations
+pub fn is_own_write(path: &Path) -> bool {
+    let mut operations = OPERATIONS.lock().unwrap();
+    if operations.wrote(path) {
+        return true;
+    }
+    // Git writes through lock files renamed into place; one is ours if the
+    // file it guards is, unless it's still held after the operation finished.
+    // While one of ours runs nobody else can hold the lock, so it's ours too.
+    match path.to_str().and_then(|p| p.strip_suffix(".lock")) {
+        Some(target) => {
+            let target = Path::new(target);
+            operations.is_running(target) || (!path.exists() && operations.wrote(target))
+        }
+        None => false,
+    }
+}
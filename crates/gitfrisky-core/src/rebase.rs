@@ -0,0 +1,581 @@
+use crate::domain::types::{RebaseAction, RebaseStatus, RebaseStopReason, RebaseTodoEntry};
+use super::journal;
+use super::repo::{ensure_clean_worktree, signature, toe};
+use git2::{build::CheckoutBuilder, Oid, Repository, RepositoryState, Sort};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+// State lives in .git/rebase-merge using the same file names as `git rebase -i`,
+// so libgit2 reports RebaseInteractive and the app can pick up after a restart.
+// Two extra files hold what git has no slot for: the stop reason and
+// replacement messages supplied with the plan, keyed by step number (1-based,
+// like msgnum) so the same commit can appear more than once.
+const STATE_DIR: &str = "rebase-merge";
+const STOP_FILE: &str = "gitfrisky-stop";
+const MESSAGES_FILE: &str = "gitfrisky-messages";
+const DETACHED: &str = "detached HEAD";
+
+struct RebaseDir {
+    path: PathBuf,
+}
+
+impl RebaseDir {
+    fn of(repo: &Repository) -> Self {
+        Self { path: repo.path().join(STATE_DIR) }
+    }
+
+    fn exists(&self) -> bool {
+        self.path.is_dir()
+    }
+
+    fn read(&self, name: &str) -> Option<String> {
+        fs::read_to_string(self.path.join(name)).ok()
+    }
+
+    fn read_line(&self, name: &str) -> Option<String> {
+        self.read(name).map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+    }
+
+    fn write(&self, name: &str, content: &str) -> Result<(), String> {
+        fs::write(self.path.join(name), content).map_err(|e| format!("Failed to write rebase state: {}", e))
+    }
+
+    fn remove(&self, name: &str) {
+        let _ = fs::remove_file(self.path.join(name));
+    }
+}
+
+fn keyword(action: RebaseAction) -> &'static str {
+    match action {
+        RebaseAction::Pick => "pick",
+        RebaseAction::Reword => "reword",
+        RebaseAction::Edit => "edit",
+        RebaseAction::Squash => "squash",
+        RebaseAction::Fixup => "fixup",
+        RebaseAction::Drop => "drop",
+        RebaseAction::Exec => "exec",
+    }
+}
+
+fn parse_action(word: &str) -> Option<RebaseAction> {
+    match word {
+        "p" | "pick" => Some(RebaseAction::Pick),
+        "r" | "reword" => Some(RebaseAction::Reword),
+        "e" | "edit" => Some(RebaseAction::Edit),
+        "s" | "squash" => Some(RebaseAction::Squash),
+        "f" | "fixup" => Some(RebaseAction::Fixup),
+        "d" | "drop" => Some(RebaseAction::Drop),
+        "x" | "exec" => Some(RebaseAction::Exec),
+        _ => None,
+    }
+}
+
+fn format_todo(entries: &[RebaseTodoEntry]) -> String {
+    let mut text = String::new();
+    for entry in entries {
+        match entry.action {
+            RebaseAction::Exec => {
+                text.push_str(&format!("exec {}\n", entry.command.as_deref().unwrap_or("")));
+            }
+            action => {
+                text.push_str(&format!(
+                    "{} {} {}\n",
+                    keyword(action),
+                    entry.oid.as_deref().unwrap_or(""),
+                    entry.summary.as_deref().unwrap_or("")
+                ));
+            }
+        }
+    }
+    text
+}
+
+// Parse a todo file whose first entry is step `first_step`; abbreviated oids
+// (as written by command-line git) are expanded
+fn parse_todo(repo: &Repository, text: &str, first_step: usize, messages: &HashMap<String, String>) -> Result<Vec<RebaseTodoEntry>, String> {
+    let mut entries = vec![];
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (word, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let action = parse_action(word).ok_or_else(|| format!("Unsupported rebase command: {}", word))?;
+
+        if action == RebaseAction::Exec {
+            entries.push(RebaseTodoEntry {
+                action,
+                oid: None,
+                summary: None,
+                message: None,
+                command: Some(rest.trim().to_string()),
+            });
+            continue;
+        }
+
+        let (short, summary) = rest.trim().split_once(' ').unwrap_or((rest.trim(), ""));
+        let oid = repo.revparse_single(short).map_err(toe)?.peel_to_commit().map_err(toe)?.id().to_string();
+        let step = first_step + entries.len();
+        entries.push(RebaseTodoEntry {
+            action,
+            message: messages.get(&step.to_string()).cloned(),
+            oid: Some(oid),
+            summary: Some(summary.to_string()),
+            command: None,
+        });
+    }
+
+    Ok(entries)
+}
+
+fn read_messages(dir: &RebaseDir) -> HashMap<String, String> {
+    dir.read(MESSAGES_FILE)
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+// Number of entries in a todo file
+fn count_steps(text: &str) -> usize {
+    text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')).count()
+}
+
+fn read_entries(repo: &Repository, dir: &RebaseDir, name: &str) -> Result<Vec<RebaseTodoEntry>, String> {
+    let messages = read_messages(dir);
+    // The todo list picks up where the done list ends
+    let first_step = if name == "done" { 1 } else { count_steps(&dir.read("done").unwrap_or_default()) + 1 };
+    parse_todo(repo, &dir.read(name).unwrap_or_default(), first_step, &messages)
+}
+
+fn read_stop(dir: &RebaseDir) -> Option<RebaseStopReason> {
+    match dir.read_line(STOP_FILE)?.as_str() {
+        "conflict" => Some(RebaseStopReason::Conflict),
+        "edit" => Some(RebaseStopReason::Edit),
+        "reword" => Some(RebaseStopReason::Reword),
+        "exec" => Some(RebaseStopReason::ExecFailed),
+        "empty" => Some(RebaseStopReason::Empty),
+        _ => None,
+    }
+}
+
+fn write_stop(dir: &RebaseDir, reason: RebaseStopReason) -> Result<(), String> {
+    let word = match reason {
+        RebaseStopReason::Conflict => "conflict",
+        RebaseStopReason::Edit => "edit",
+        RebaseStopReason::Reword => "reword",
+        RebaseStopReason::ExecFailed => "exec",
+        RebaseStopReason::Empty => "empty",
+    };
+    dir.write(STOP_FILE, word)
+}
+
+fn clear_stop(dir: &RebaseDir) {
+    dir.remove(STOP_FILE);
+    dir.remove("stopped-sha");
+    dir.remove("message");
+}
+
+fn commit_message(commit: &git2::Commit) -> String {
+    String::from_utf8_lossy(commit.message_bytes()).into_owned()
+}
+
+fn head_commit(repo: &Repository) -> Result<git2::Commit<'_>, String> {
+    repo.head().map_err(toe)?.peel_to_commit().map_err(toe)
+}
+
+fn run_exec(repo: &Repository, command: &str) -> Result<bool, String> {
+    let workdir = repo.workdir().ok_or("Repository has no working directory")?;
+
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut c = Command::new("cmd");
+        c.args(["/C", command]);
+        c
+    };
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut c = Command::new("sh");
+        c.args(["-c", command]);
+        c
+    };
+
+    let status = cmd.current_dir(workdir).status().map_err(|e| format!("Failed to run '{}': {}", command, e))?;
+    Ok(status.success())
+}
+
+// Apply a commit's changes to the index and working tree. The cherry-pick
+// bookkeeping libgit2 writes is dropped; the rebase directory tracks the step.
+fn apply_changes(repo: &Repository, commit: &git2::Commit) -> Result<bool, String> {
+    repo.cherrypick(commit, None).map_err(toe)?;
+    let _ = fs::remove_file(repo.path().join("CHERRY_PICK_HEAD"));
+    let _ = repo.remove_message();
+
+    Ok(!repo.index().map_err(toe)?.has_conflicts())
+}
+
+// Record the current index as the result of `entry`. Returns a stop reason if
+// the step needs user input before the rebase can move on.
+fn commit_step(repo: &Repository, dir: &RebaseDir, entry: &RebaseTodoEntry, message: Option<&str>) -> Result<Option<RebaseStopReason>, String> {
+    let oid = entry.oid.as_deref().ok_or("Rebase step has no commit")?;
+    let original = repo.find_commit(Oid::from_str(oid).map_err(toe)?).map_err(toe)?;
+    let message = message.map(str::to_string).or_else(|| entry.message.clone());
+
+    if entry.action == RebaseAction::Reword && message.is_none() {
+        dir.write("message", &commit_message(&original))?;
+        dir.write("stopped-sha", oid)?;
+        return Ok(Some(RebaseStopReason::Reword));
+    }
+
+    let sig = signature(repo)?;
+    let mut index = repo.index().map_err(toe)?;
+    let tree = repo.find_tree(index.write_tree().map_err(toe)?).map_err(toe)?;
+    let head = head_commit(repo)?;
+
+    match entry.action {
+        RebaseAction::Squash | RebaseAction::Fixup => {
+            let head_message = commit_message(&head);
+            let message = message.unwrap_or_else(|| {
+                if entry.action == RebaseAction::Squash {
+                    format!("{}\n\n{}", head_message.trim_end(), commit_message(&original))
+                } else {
+                    head_message
+                }
+            });
+            head.amend(Some("HEAD"), None, Some(&sig), None, Some(&message), Some(&tree)).map_err(toe)?;
+        }
+        _ => {
+            let message = message.unwrap_or_else(|| commit_message(&original));
+            // Changes already upstream (or a conflict resolved to exactly HEAD)
+            // leave nothing to commit. Stop like git does rather than silently
+            // dropping the step, which may have been marked for edit or reword.
+            if tree.id() == head.tree_id() && original.tree_id() != original.parent(0).map(|p| p.tree_id()).unwrap_or(Oid::zero()) {
+                dir.write("message", &message)?;
+                dir.write("stopped-sha", oid)?;
+                return Ok(Some(RebaseStopReason::Empty));
+            }
+            repo.commit(Some("HEAD"), &original.author(), &sig, &message, &tree, &[&head]).map_err(toe)?;
+        }
+    }
+
+    if entry.action == RebaseAction::Edit {
+        dir.write("stopped-sha", oid)?;
+        return Ok(Some(RebaseStopReason::Edit));
+    }
+
+    Ok(None)
+}
+
+// Carry out one todo entry
+fn perform(repo: &Repository, dir: &RebaseDir, entry: &RebaseTodoEntry) -> Result<Option<RebaseStopReason>, String> {
+    match entry.action {
+        RebaseAction::Drop => Ok(None),
+        RebaseAction::Exec => {
+            let command = entry.command.as_deref().unwrap_or("");
+            if run_exec(repo, command)? {
+                Ok(None)
+            } else {
+                Ok(Some(RebaseStopReason::ExecFailed))
+            }
+        }
+        _ => {
+            let oid = Oid::from_str(entry.oid.as_deref().ok_or("Rebase step has no commit")?).map_err(toe)?;
+            let commit = repo.find_commit(oid).map_err(toe)?;
+            let head = head_commit(repo)?;
+
+            // Unchanged picks on top of their own parent are reused as-is
+            let reusable = matches!(entry.action, RebaseAction::Pick | RebaseAction::Edit)
+                && entry.message.is_none()
+                && commit.parent_count() == 1
+                && commit.parent_id(0).map_err(toe)? == head.id();
+            if reusable {
+                let mut checkout = CheckoutBuilder::new();
+                checkout.safe();
+                repo.checkout_tree(commit.as_object(), Some(&mut checkout)).map_err(toe)?;
+                repo.set_head_detached(oid).map_err(toe)?;
+                if entry.action == RebaseAction::Edit {
+                    dir.write("stopped-sha", &oid.to_string())?;
+                    return Ok(Some(RebaseStopReason::Edit));
+                }
+                return Ok(None);
+            }
+
+            if !apply_changes(repo, &commit)? {
+                dir.write("message", &commit_message(&commit))?;
+                dir.write("stopped-sha", &oid.to_string())?;
+                return Ok(Some(RebaseStopReason::Conflict));
+            }
+
+            commit_step(repo, dir, entry, None)
+        }
+    }
+}
+
+// Like `reset --hard`, but without libgit2's reset also wiping the rebase state
+fn discard_changes(repo: &Repository, commit: &git2::Commit) -> Result<(), String> {
+    let mut checkout = CheckoutBuilder::new();
+    checkout.force();
+    repo.checkout_tree(commit.as_object(), Some(&mut checkout)).map_err(toe)?;
+
+    // Reload the index from the tree so no conflict entries survive
+    let mut index = repo.index().map_err(toe)?;
+    index.read_tree(&commit.tree().map_err(toe)?).map_err(toe)?;
+    index.write().map_err(toe)
+}
+
+// Move the rebased branch to the new HEAD and remove the rebase state
+fn finish(repo: &Repository, dir: &RebaseDir) -> Result<(), String> {
+    let new_head = head_commit(repo)?.id();
+    let head_name = dir.read_line("head-name").unwrap_or_else(|| DETACHED.to_string());
+
+    if let Some(orig) = dir.read_line("orig-head") {
+        let orig = Oid::from_str(&orig).map_err(toe)?;
+        repo.reference("ORIG_HEAD", orig, true, "rebase").map_err(toe)?;
+    }
+
+    if head_name.starts_with("refs/") {
+        let log_message = format!("rebase (finish): {} onto {}", head_name, new_head);
+        repo.reference(&head_name, new_head, true, &log_message).map_err(toe)?;
+        repo.set_head(&head_name).map_err(toe)?;
+    }
+
+    fs::remove_dir_all(&dir.path).map_err(|e| format!("Failed to remove rebase state: {}", e))
+}
+
+// Work through the remaining todo list until it is empty or a step stops
+fn run(repo: &Repository, dir: &RebaseDir) -> Result<RebaseStatus, String> {
+    loop {
+        let mut todo = read_entries(repo, dir, "git-rebase-todo")?;
+        if todo.is_empty() {
+            finish(repo, dir)?;
+            return status_of(repo);
+        }
+
+        let entry = todo.remove(0);
+        let mut done = read_entries(repo, dir, "done")?;
+        done.push(entry.clone());
+        dir.write("git-rebase-todo", &format_todo(&todo))?;
+        dir.write("done", &format_todo(&done))?;
+        dir.write("msgnum", &done.len().to_string())?;
+
+        if let Some(reason) = perform(repo, dir, &entry)? {
+            write_stop(dir, reason)?;
+            return status_of(repo);
+        }
+    }
+}
+
+fn status_of(repo: &Repository) -> Result<RebaseStatus, String> {
+    let dir = RebaseDir::of(repo);
+    if !dir.exists() {
+        return Ok(RebaseStatus {
+            in_progress: false,
+            head_name: None,
+            onto: None,
+            orig_head: None,
+            done: vec![],
+            remaining: vec![],
+            stopped: None,
+            message: None,
+            conflicts: vec![],
+        });
+    }
+
+    let index = repo.index().map_err(toe)?;
+    let mut conflicts = vec![];
+    for conflict in index.conflicts().map_err(toe)? {
+        let conflict = conflict.map_err(toe)?;
+        if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+            conflicts.push(String::from_utf8_lossy(&entry.path).into_owned());
+        }
+    }
+
+    Ok(RebaseStatus {
+        in_progress: true,
+        head_name: dir.read_line("head-name").filter(|h| h != DETACHED),
+        onto: dir.read_line("onto"),
+        orig_head: dir.read_line("orig-head"),
+        done: read_entries(repo, &dir, "done")?,
+        remaining: read_entries(repo, &dir, "git-rebase-todo")?,
+        stopped: read_stop(&dir),
+        message: dir.read("message"),
+        conflicts,
+    })
+}
+
+fn require_rebase(repo: &Repository) -> Result<RebaseDir, String> {
+    let dir = RebaseDir::of(repo);
+    if !dir.exists() {
+        return Err("No rebase in progress".to_string());
+    }
+    Ok(dir)
+}
+
+pub fn rebase_plan(repo_path: &str, upstream: &str) -> Result<Vec<RebaseTodoEntry>, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let upstream = repo.revparse_single(upstream).map_err(toe)?.peel_to_commit().map_err(toe)?;
+    let head = head_commit(&repo)?;
+
+    // upstream..HEAD, oldest first; merge commits are flattened like plain `git rebase -i`
+    let mut revwalk = repo.revwalk().map_err(toe)?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE).map_err(toe)?;
+    revwalk.push(head.id()).map_err(toe)?;
+    revwalk.hide(upstream.id()).map_err(toe)?;
+
+    let mut entries = vec![];
+    for oid in revwalk {
+        let commit = repo.find_commit(oid.map_err(toe)?).map_err(toe)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        entries.push(RebaseTodoEntry {
+            action: RebaseAction::Pick,
+            oid: Some(commit.id().to_string()),
+            summary: Some(commit.summary().unwrap_or("").to_string()),
+            message: None,
+            command: None,
+        });
+    }
+
+    Ok(entries)
+}
+
+pub fn rebase_start(repo_path: &str, onto: &str, todo: &[RebaseTodoEntry]) -> Result<RebaseStatus, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+
+    if repo.state() != RepositoryState::Clean {
+        return Err(format!("Cannot rebase during {:?}. Please complete or abort the current operation.", repo.state()));
+    }
+    ensure_clean_worktree(&repo)?;
+
+    if let Some(first) = todo.iter().find(|e| !matches!(e.action, RebaseAction::Drop | RebaseAction::Exec)) {
+        if matches!(first.action, RebaseAction::Squash | RebaseAction::Fixup) {
+            return Err("Cannot squash or fixup without a previous commit".to_string());
+        }
+    }
+
+    let head = repo.head().map_err(toe)?;
+    let head_name = if repo.head_detached().unwrap_or(false) {
+        DETACHED.to_string()
+    } else {
+        head.name().ok_or("HEAD has an invalid name")?.to_string()
+    };
+    let orig_head = head.peel_to_commit().map_err(toe)?.id();
+    let onto = repo.revparse_single(onto).map_err(toe)?.peel_to_commit().map_err(toe)?;
+    let recorder = journal::begin_clean(&repo, &format!("Rebase onto {}", onto.summary().unwrap_or("")))?;
+
+    let dir = RebaseDir::of(&repo);
+    fs::create_dir_all(&dir.path).map_err(|e| format!("Failed to create rebase state: {}", e))?;
+
+    let messages: HashMap<String, String> = todo
+        .iter()
+        .enumerate()
+        .filter_map(|(i, e)| Some(((i + 1).to_string(), e.message.clone()?)))
+        .collect();
+
+    dir.write("head-name", &head_name)?;
+    dir.write("onto", &onto.id().to_string())?;
+    dir.write("orig-head", &orig_head.to_string())?;
+    dir.write("interactive", "")?;
+    dir.write("git-rebase-todo", &format_todo(todo))?;
+    dir.write("done", "")?;
+    dir.write("msgnum", "0")?;
+    dir.write("end", &todo.len().to_string())?;
+    dir.write(MESSAGES_FILE, &serde_json::to_string(&messages).map_err(toe)?)?;
+
+    // Detach onto the new base and replay from there
+    let mut checkout = CheckoutBuilder::new();
+    checkout.safe();
+    repo.checkout_tree(onto.as_object(), Some(&mut checkout)).map_err(toe)?;
+    repo.set_head_detached(onto.id()).map_err(toe)?;
+
+    // Only a rebase that ran to the end is journaled; one that stopped is undone with abort
+    let status = run(&repo, &dir)?;
+    if !status.in_progress {
+        recorder.finish(&repo);
+    }
+    Ok(status)
+}
+
+pub fn rebase_status(repo_path: &str) -> Result<RebaseStatus, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    status_of(&repo)
+}
+
+pub fn rebase_continue(repo_path: &str, message: Option<&str>) -> Result<RebaseStatus, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let dir = require_rebase(&repo)?;
+
+    if repo.index().map_err(toe)?.has_conflicts() {
+        return Err("Resolve all conflicts before continuing".to_string());
+    }
+
+    let stopped = read_stop(&dir);
+    let current = read_entries(&repo, &dir, "done")?.pop();
+
+    // Like `git rebase --continue`, refuse to move on from an empty step
+    // until something is staged; skipping is how to drop it
+    let mut message = message.map(str::to_string);
+    if stopped == Some(RebaseStopReason::Empty) {
+        let tree_id = repo.index().map_err(toe)?.write_tree().map_err(toe)?;
+        if tree_id == head_commit(&repo)?.tree_id() {
+            return Err("Nothing to commit: the changes are already applied. Stage changes to continue, or skip this step.".to_string());
+        }
+        message = message.or_else(|| dir.read("message"));
+    }
+    clear_stop(&dir);
+
+    match (stopped, current) {
+        (Some(RebaseStopReason::Conflict | RebaseStopReason::Reword | RebaseStopReason::Empty), Some(entry)) => {
+            if let Some(reason) = commit_step(&repo, &dir, &entry, message.as_deref())? {
+                write_stop(&dir, reason)?;
+                return status_of(&repo);
+            }
+        }
+        (Some(RebaseStopReason::Edit), Some(_)) => {
+            // Fold anything staged while stopped (and a new message) into the commit
+            let head = head_commit(&repo)?;
+            let tree_id = repo.index().map_err(toe)?.write_tree().map_err(toe)?;
+            if tree_id != head.tree_id() || message.is_some() {
+                let sig = signature(&repo)?;
+                let tree = repo.find_tree(tree_id).map_err(toe)?;
+                head.amend(Some("HEAD"), None, Some(&sig), None, message.as_deref(), Some(&tree)).map_err(toe)?;
+            }
+        }
+        _ => {}
+    }
+
+    run(&repo, &dir)
+}
+
+pub fn rebase_skip(repo_path: &str) -> Result<RebaseStatus, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let dir = require_rebase(&repo)?;
+
+    // Throw away whatever the current step left in the index and working tree
+    discard_changes(&repo, &head_commit(&repo)?)?;
+    clear_stop(&dir);
+
+    run(&repo, &dir)
+}
+
+pub fn rebase_abort(repo_path: &str) -> Result<RebaseStatus, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let dir = require_rebase(&repo)?;
+
+    let orig_head = dir.read_line("orig-head").ok_or("Rebase state is missing orig-head")?;
+    let orig_head = repo.find_commit(Oid::from_str(&orig_head).map_err(toe)?).map_err(toe)?;
+    let head_name = dir.read_line("head-name").unwrap_or_else(|| DETACHED.to_string());
+
+    discard_changes(&repo, &orig_head)?;
+    if head_name.starts_with("refs/") {
+        repo.set_head(&head_name).map_err(toe)?;
+    } else {
+        repo.set_head_detached(orig_head.id()).map_err(toe)?;
+    }
+
+    fs::remove_dir_all(&dir.path).map_err(|e| format!("Failed to remove rebase state: {}", e))?;
+    status_of(&repo)
+}
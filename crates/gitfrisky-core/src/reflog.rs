@@ -0,0 +1,142 @@
+use crate::domain::types::{DanglingCommit, ReflogEntry};
+use super::journal;
+use super::repo::{toe, to_commit};
+use git2::{ObjectType, Oid, Repository};
+use std::collections::{HashMap, HashSet};
+
+// Accept "HEAD", full ref names, and short branch names
+fn resolve_ref_name(repo: &Repository, name: &str) -> Result<String, String> {
+    if name == "HEAD" || name.starts_with("refs/") {
+        return Ok(name.to_string());
+    }
+    let reference = repo.resolve_reference_from_short_name(name).map_err(toe)?;
+    reference
+        .name()
+        .map(|n| n.to_string())
+        .ok_or_else(|| format!("Invalid reference name: {}", name))
+}
+
+pub fn reflog(repo_path: &str, ref_name: Option<&str>) -> Result<Vec<ReflogEntry>, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let name = resolve_ref_name(&repo, ref_name.unwrap_or("HEAD"))?;
+    let reflog = repo.reflog(&name).map_err(toe)?;
+
+    // Newest first, like `git reflog`
+    let entries = reflog
+        .iter()
+        .map(|entry| {
+            let committer = entry.committer();
+            ReflogEntry {
+                old_oid: entry.id_old().to_string(),
+                new_oid: entry.id_new().to_string(),
+                message: entry.message().map(|m| m.to_string()),
+                committer: committer.name().unwrap_or("Unknown").to_string(),
+                timestamp: committer.when().seconds(),
+                // The commit may have been garbage collected since
+                commit: repo.find_commit(entry.id_new()).ok().map(|c| to_commit(&c)),
+            }
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+// Commits not reachable from any ref or HEAD. Only the tips are returned: a
+// lost commit whose child is also lost is recovered along with that child.
+// Commits a reflog still records (say, after a reset) are labeled with that
+// reflog; stash entries are left out since they aren't lost.
+pub fn dangling_commits(repo_path: &str) -> Result<Vec<DanglingCommit>, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+
+    let mut tips = vec![];
+    let mut names = vec!["HEAD".to_string()];
+    for reference in repo.references().map_err(toe)?.flatten() {
+        if let Ok(commit) = reference.peel_to_commit() {
+            tips.push(commit.id());
+        }
+        names.extend(reference.name().map(|n| n.to_string()));
+    }
+    if let Ok(head) = repo.head() {
+        if let Ok(commit) = head.peel_to_commit() {
+            tips.push(commit.id());
+        }
+    }
+
+    let mut walk = repo.revwalk().map_err(toe)?;
+    for tip in &tips {
+        walk.push(*tip).map_err(toe)?;
+    }
+    let mut reachable: HashSet<Oid> = walk.flatten().collect();
+
+    // First reflog (HEAD before branches) that reaches each commit refs don't
+    let mut in_reflog: HashMap<Oid, String> = HashMap::new();
+    for name in names {
+        let Ok(reflog) = repo.reflog(&name) else { continue };
+        let mut walk = repo.revwalk().map_err(toe)?;
+        for entry in reflog.iter() {
+            if repo.find_commit(entry.id_new()).is_ok() {
+                walk.push(entry.id_new()).map_err(toe)?;
+            }
+        }
+        for tip in &tips {
+            walk.hide(*tip).map_err(toe)?;
+        }
+        for oid in walk.flatten() {
+            if name == "refs/stash" {
+                reachable.insert(oid);
+            } else {
+                in_reflog.entry(oid).or_insert_with(|| name.clone());
+            }
+        }
+    }
+
+    // Every commit in the object database, loose or packed
+    let odb = repo.odb().map_err(toe)?;
+    let mut commits = vec![];
+    odb.foreach(|oid| {
+        if let Ok((_, ObjectType::Commit)) = odb.read_header(*oid) {
+            commits.push(*oid);
+        }
+        true
+    })
+    .map_err(toe)?;
+
+    let unreachable: Vec<git2::Commit> = commits
+        .into_iter()
+        .filter(|oid| !reachable.contains(oid))
+        .filter_map(|oid| repo.find_commit(oid).ok())
+        .collect();
+
+    // Drop lost commits that are parents of other lost commits
+    let parents: HashSet<Oid> = unreachable.iter().flat_map(|c| c.parent_ids()).collect();
+    let mut dangling: Vec<DanglingCommit> = unreachable
+        .iter()
+        .filter(|c| !parents.contains(&c.id()))
+        .map(|c| DanglingCommit { commit: to_commit(c), reflog: in_reflog.get(&c.id()).cloned() })
+        .collect();
+    dangling.sort_by_key(|d| std::cmp::Reverse(d.commit.timestamp));
+
+    Ok(dangling)
+}
+
+// Create a branch at a lost commit; returns the full ref name
+pub fn restore_commit(repo_path: &str, oid: &str, branch_name: &str) -> Result<String, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let commit = repo
+        .find_commit(Oid::from_str(oid).map_err(toe)?)
+        .map_err(|_| format!("Commit {} no longer exists", oid))?;
+
+    if repo.find_branch(branch_name, git2::BranchType::Local).is_ok() {
+        return Err(format!("Branch '{}' already exists", branch_name));
+    }
+
+    let refname = format!("refs/heads/{}", branch_name);
+    let recorder = journal::begin_with_refs(&repo, &format!("Restore {} as {}", &commit.id().to_string()[..7], branch_name), vec![refname], vec![])?;
+    let branch = repo.branch(branch_name, &commit, false).map_err(toe)?;
+    recorder.finish(&repo);
+    branch
+        .get()
+        .name()
+        .map(|n| n.to_string())
+        .ok_or_else(|| "Invalid branch name".to_string())
+}
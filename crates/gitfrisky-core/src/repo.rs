@@ -0,0 +1,1242 @@
+use crate::domain::types::{Branch, RepoSummary, RepoState, StatusPayload, FileChange, FileStatus, DiffHunk, DiffLine, LineType, Commit, FileDiff, IntralineMode, DiffViewOptions, DiffAlgorithm, RenameDetection};
+use crate::util::intraline;
+use crate::util::io::{decode, decode_line, decode_with};
+use super::cli_status;
+use super::journal;
+use git2::{Repository, BranchType, RepositoryState, StatusOptions, StatusShow, Status, DiffOptions, Signature, ApplyLocation, Diff, DiffFindOptions};
+
+// Error conversion helper
+pub(crate) fn toe<E: std::fmt::Display>(e: E) -> String {
+    e.to_string()
+}
+
+// Files bigger than this get a size/hash summary instead of a line diff
+const MAX_DIFF_BYTES: u64 = 20 * 1024 * 1024;
+// Lines returned by default; the UI asks again with a higher limit to load more
+const DEFAULT_MAX_DIFF_LINES: usize = 5000;
+const DEFAULT_CONTEXT_LINES: u32 = 3;
+
+// .gitattributes settings that change how a file is diffed
+struct DiffAttributes {
+    binary: bool,             // `binary` or `-diff`
+    force_text: bool,         // `diff` set explicitly
+    encoding: Option<String>, // `working-tree-encoding`
+}
+
+fn diff_attributes(repo: &Repository, rel_path: &str) -> DiffAttributes {
+    let path = std::path::Path::new(rel_path);
+    let flags = git2::AttrCheckFlags::FILE_THEN_INDEX;
+    let diff = repo.get_attr(path, "diff", flags).ok().flatten();
+    let (binary, force_text) = match git2::AttrValue::from_string(diff) {
+        git2::AttrValue::False => (true, false),
+        git2::AttrValue::True => (false, true),
+        _ => (false, false),
+    };
+    let encoding = repo
+        .get_attr(path, "working-tree-encoding", flags)
+        .ok()
+        .flatten()
+        .map(|e| e.to_string());
+
+    DiffAttributes { binary, force_text, encoding }
+}
+
+fn configured_algorithm(repo: &Repository) -> Option<DiffAlgorithm> {
+    let name = repo.config().and_then(|c| c.get_string("diff.algorithm")).ok()?;
+    match name.as_str() {
+        "minimal" => Some(DiffAlgorithm::Minimal),
+        "patience" => Some(DiffAlgorithm::Patience),
+        "histogram" => Some(DiffAlgorithm::Histogram),
+        _ => Some(DiffAlgorithm::Myers),
+    }
+}
+
+// diff.renames: a boolean or "copies"; git detects renames when it's unset
+fn rename_detection(repo: &Repository, view: &DiffViewOptions) -> RenameDetection {
+    if let Some(detection) = view.renames {
+        return detection;
+    }
+    let Ok(config) = repo.config() else { return RenameDetection::Renames };
+    match config.get_string("diff.renames").map(|v| v.to_ascii_lowercase()) {
+        Ok(v) if v == "copies" || v == "copy" => RenameDetection::Copies,
+        _ if config.get_bool("diff.renames").ok() == Some(false) => RenameDetection::Off,
+        _ => RenameDetection::Renames,
+    }
+}
+
+// The algorithm a diff is actually computed with: libgit2 has no histogram
+// diff, so patience, the closest it has, stands in for it
+fn diff_algorithm(repo: &Repository, view: &DiffViewOptions) -> DiffAlgorithm {
+    match view.algorithm.or_else(|| configured_algorithm(repo)) {
+        Some(DiffAlgorithm::Histogram) => DiffAlgorithm::Patience,
+        Some(algorithm) => algorithm,
+        None => DiffAlgorithm::Myers,
+    }
+}
+
+fn view_diff_options(repo: &Repository, view: &DiffViewOptions, attrs: &DiffAttributes) -> DiffOptions {
+    let mut opts = DiffOptions::new();
+    opts.context_lines(view.context_lines.unwrap_or(DEFAULT_CONTEXT_LINES))
+        .ignore_whitespace(view.ignore_all_space)
+        .ignore_whitespace_change(view.ignore_space_change)
+        .ignore_whitespace_eol(view.ignore_space_at_eol)
+        .ignore_blank_lines(view.ignore_blank_lines)
+        .force_text(attrs.force_text)
+        .force_binary(attrs.binary);
+    match diff_algorithm(repo, view) {
+        DiffAlgorithm::Minimal => {
+            opts.minimal(true);
+        }
+        DiffAlgorithm::Patience => {
+            opts.patience(true);
+        }
+        _ => {}
+    }
+    opts
+}
+
+fn empty_file_diff(rel_path: &str) -> FileDiff {
+    FileDiff {
+        path: rel_path.to_string(),
+        old_path: None,
+        is_binary: false,
+        too_large: false,
+        old_oid: None,
+        new_oid: None,
+        old_size: None,
+        new_size: None,
+        encoding: "utf-8".to_string(),
+        algorithm: DiffAlgorithm::Myers,
+        hunks: vec![],
+        total_lines: 0,
+        truncated: false,
+    }
+}
+
+// Keep at most `max_lines` lines, cutting at a hunk boundary where possible
+fn cap_lines(file_diff: &mut FileDiff, hunks: Vec<DiffHunk>, max_lines: usize) {
+    file_diff.total_lines = hunks.iter().map(|h| h.lines.len()).sum();
+    file_diff.truncated = file_diff.total_lines > max_lines;
+    if !file_diff.truncated {
+        file_diff.hunks = hunks;
+        return;
+    }
+
+    let mut remaining = max_lines;
+    for mut hunk in hunks {
+        if remaining == 0 || (hunk.lines.len() > remaining && !file_diff.hunks.is_empty()) {
+            break;
+        }
+        // Its header still describes every line, so it can't be applied as is
+        hunk.truncated = hunk.lines.len() > remaining;
+        hunk.lines.truncate(remaining);
+        remaining -= hunk.lines.len();
+        file_diff.hunks.push(hunk);
+    }
+}
+
+// Helper to show untracked file as all additions
+fn get_untracked_file_diff(repo: &Repository, rel_path: &str, attrs: &DiffAttributes, max_lines: usize) -> Result<FileDiff, String> {
+    use std::fs;
+
+    let repo_path = repo.path().parent().ok_or("Invalid repo path")?;
+    let file_path = repo_path.join(rel_path);
+
+    let mut file_diff = empty_file_diff(rel_path);
+    let size = fs::metadata(&file_path).map_err(|e| format!("Failed to read file: {}", e))?.len();
+    file_diff.new_size = Some(size);
+    file_diff.new_oid = git2::Oid::hash_file(git2::ObjectType::Blob, &file_path).ok().map(|o| o.to_string());
+
+    if size > MAX_DIFF_BYTES {
+        file_diff.too_large = true;
+        return Ok(file_diff);
+    }
+    if attrs.binary {
+        file_diff.is_binary = true;
+        return Ok(file_diff);
+    }
+
+    let bytes = fs::read(&file_path).map_err(|e| format!("Failed to read file content: {}", e))?;
+    let declared = attrs.encoding.as_deref().and_then(|enc| decode_with(&bytes, enc).map(|text| (text, enc.to_string())));
+    let content = match declared {
+        Some((text, encoding)) => {
+            file_diff.encoding = encoding;
+            text
+        }
+        None => {
+            let decoded = decode(&bytes);
+            match decoded.text {
+                Some(text) => {
+                    file_diff.encoding = decoded.encoding.to_string();
+                    text
+                }
+                None if attrs.force_text => bytes.split(|b| *b == b'\n').map(decode_line).collect::<Vec<_>>().join("\n"),
+                None => {
+                    file_diff.is_binary = true;
+                    return Ok(file_diff);
+                }
+            }
+        }
+    };
+
+    // Split into lines
+    let lines: Vec<&str> = content.lines().collect();
+    let line_count = lines.len() as u32;
+
+    // Create a single hunk with all lines as additions
+    let mut hunk_lines = vec![];
+    for (idx, line) in lines.iter().enumerate() {
+        hunk_lines.push(DiffLine {
+            content: line.to_string(),
+            line_type: LineType::Addition,
+            old_lineno: None,
+            new_lineno: Some((idx + 1) as u32),
+            changes: vec![],
+        });
+    }
+
+    let hunk = DiffHunk {
+        header: format!("@@ -0,0 +1,{} @@", line_count),
+        old_start: 0,
+        old_lines: 0,
+        new_start: 1,
+        new_lines: line_count,
+        lines: hunk_lines,
+        truncated: false,
+    };
+
+    cap_lines(&mut file_diff, vec![hunk], max_lines);
+    Ok(file_diff)
+}
+
+// Convert a git2 commit into the domain type used by the history views
+pub(crate) fn to_commit(commit: &git2::Commit) -> Commit {
+    // Get parent OIDs
+    let parents: Vec<String> = commit.parents().map(|p| p.id().to_string()).collect();
+
+    // Get commit signature
+    let author = commit.author();
+    let author_name = author.name().unwrap_or("Unknown").to_string();
+    let author_email = author.email().unwrap_or("").to_string();
+
+    // Get commit message
+    let summary = commit.summary().unwrap_or("").to_string();
+    let message = commit.message().map(|m| m.to_string());
+
+    Commit {
+        oid: commit.id().to_string(),
+        author: author_name,
+        email: author_email,
+        timestamp: commit.time().seconds(),
+        summary,
+        message,
+        parents,
+        refs: vec![], // TODO: Add branch/tag refs in Phase 5
+        lane: None,   // Will be computed on frontend for MVP
+    }
+}
+
+// Fail if tracked files have staged or unstaged changes (untracked files are fine)
+pub(crate) fn ensure_clean_worktree(repo: &Repository) -> Result<(), String> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false).include_ignored(false);
+    let statuses = repo.statuses(Some(&mut opts)).map_err(toe)?;
+    if !statuses.is_empty() {
+        return Err("Working tree has uncommitted changes. Commit or stash them first.".to_string());
+    }
+    Ok(())
+}
+
+// Map git2 RepositoryState to our domain RepoState
+pub fn map_repo_state(state: RepositoryState) -> RepoState {
+    match state {
+        RepositoryState::Clean => RepoState::Clean,
+        RepositoryState::Merge => RepoState::Merge,
+        RepositoryState::Rebase => RepoState::Rebase,
+        RepositoryState::RebaseInteractive => RepoState::RebaseInteractive,
+        RepositoryState::RebaseMerge => RepoState::RebaseMerge,
+        RepositoryState::Revert | RepositoryState::RevertSequence => RepoState::Revert,
+        RepositoryState::CherryPick | RepositoryState::CherryPickSequence => RepoState::CherryPick,
+        RepositoryState::Bisect => RepoState::Bisect,
+        _ => RepoState::Clean,
+    }
+}
+
+pub fn open_repo(path: &str) -> Result<RepoSummary, String> {
+    let repo = Repository::open(path).map_err(toe)?;
+
+    // Check if repo is bare
+    let is_bare = repo.is_bare();
+
+    // Check if HEAD is detached
+    let is_detached = repo.head_detached().unwrap_or(false);
+
+    // Get repository state
+    let state = map_repo_state(repo.state());
+
+    // Get HEAD reference
+    let head = if is_detached {
+        repo.head().ok().and_then(|h| h.target()).map(|oid| oid.to_string())
+    } else {
+        repo.head()
+            .ok()
+            .and_then(|h| h.shorthand().map(|s| s.to_string()))
+    };
+
+    // Collect branches
+    let mut branches = vec![];
+    for br in repo.branches(Some(BranchType::Local)).map_err(toe)? {
+        let (b, _) = br.map_err(toe)?;
+        let name = b.name().ok().flatten().unwrap_or("").to_string();
+        let is_head = b.is_head();
+        let full = format!("refs/heads/{}", name);
+
+        // Get upstream info
+        let upstream = b.upstream()
+            .ok()
+            .and_then(|u| u.name().ok().flatten().map(|s| s.to_string()));
+
+        // Calculate ahead/behind for this branch
+        let (ahead, behind) = if let Some(local_oid) = b.get().target() {
+            if let Some(ref up_name) = upstream {
+                if let Ok(upstream_ref) = repo.find_reference(up_name) {
+                    if let Some(upstream_oid) = upstream_ref.target() {
+                        repo.graph_ahead_behind(local_oid, upstream_oid)
+                            .unwrap_or((0, 0))
+                    } else {
+                        (0, 0)
+                    }
+                } else {
+                    (0, 0)
+                }
+            } else {
+                (0, 0)
+            }
+        } else {
+            (0, 0)
+        };
+
+        branches.push(Branch {
+            name: name.clone(),
+            full_name: full,
+            is_head,
+            is_remote: false,
+            upstream,
+            ahead: ahead as i32,
+            behind: behind as i32,
+        });
+    }
+
+    Ok(RepoSummary {
+        path: path.to_string(),
+        branches,
+        head,
+        is_bare,
+        is_detached,
+        state,
+    })
+}
+
+pub fn status(repo_path: &str) -> Result<StatusPayload, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    collect_status(&repo, &[])
+}
+
+// Status of just `paths` (files or directories), for refreshing the entries
+// the watcher reported without rescanning the whole working tree
+pub fn status_paths(repo_path: &str, paths: &[String]) -> Result<StatusPayload, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    if paths.is_empty() {
+        return Ok(StatusPayload { unstaged: vec![], staged: vec![] });
+    }
+    collect_status(&repo, paths)
+}
+
+fn collect_status(repo: &Repository, paths: &[String]) -> Result<StatusPayload, String> {
+    let mut payload = StatusPayload { unstaged: vec![], staged: vec![] };
+    for (path, flags) in scan_status(repo, paths)? {
+        add_changes(&mut payload, &path, flags);
+    }
+    Ok(payload)
+}
+
+// Status flags of every changed file, or of those in or under `paths`
+pub(crate) fn scan_status(repo: &Repository, paths: &[String]) -> Result<Vec<(String, Status)>, String> {
+    if cli_status::preferred(repo) {
+        match cli_status::status(repo, paths) {
+            Ok(entries) => return Ok(entries),
+            // libgit2 may still manage if git itself is missing
+            Err(e) => eprintln!("Falling back to libgit2 for status: {}", e),
+        }
+    }
+
+    // A scan limited to one path only walks that part of the tree; with
+    // several, libgit2 would walk all of it, so scan them one at a time
+    if paths.len() > 1 {
+        let mut entries = std::collections::BTreeMap::new();
+        for path in paths {
+            entries.extend(scan_status(repo, std::slice::from_ref(path))?);
+        }
+        return Ok(entries.into_iter().collect());
+    }
+
+    // Configure status options to include untracked files
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show(StatusShow::IndexAndWorkdir);
+    for path in paths {
+        opts.pathspec(path);
+    }
+    // They're real paths; a file named `[1].txt` isn't a pattern
+    opts.disable_pathspec_match(true);
+
+    let statuses = repo.statuses(Some(&mut opts)).map_err(toe)?;
+    Ok(statuses
+        .iter()
+        .map(|entry| (entry.path().unwrap_or("").to_string(), entry.status()))
+        .collect())
+}
+
+// Add the staged and unstaged entries for one file's status flags
+pub(crate) fn add_changes(payload: &mut StatusPayload, path: &str, status_flags: Status) {
+    let StatusPayload { staged, unstaged } = payload;
+    let path = path.to_string();
+
+    // Calculate additions/deletions (we'll set to 0 for now, diff will provide real numbers)
+    let additions = 0;
+    let deletions = 0;
+
+    // Check for staged changes (index modifications)
+    if status_flags.intersects(Status::INDEX_NEW) {
+        staged.push(FileChange {
+            path: path.to_string(),
+            status: FileStatus::A,
+            old_path: None,
+            additions,
+            deletions,
+        });
+    } else if status_flags.intersects(Status::INDEX_MODIFIED) {
+        staged.push(FileChange {
+            path: path.to_string(),
+            status: FileStatus::M,
+            old_path: None,
+            additions,
+            deletions,
+        });
+    } else if status_flags.intersects(Status::INDEX_DELETED) {
+        staged.push(FileChange {
+            path: path.to_string(),
+            status: FileStatus::D,
+            old_path: None,
+            additions,
+            deletions,
+        });
+    } else if status_flags.intersects(Status::INDEX_RENAMED) {
+        staged.push(FileChange {
+            path: path.to_string(),
+            status: FileStatus::R,
+            old_path: None, // TODO: get actual old path from diff
+            additions,
+            deletions,
+        });
+    }
+
+    // Check for unstaged changes (working tree modifications)
+    if status_flags.intersects(Status::WT_NEW) {
+        unstaged.push(FileChange {
+            path: path.to_string(),
+            status: FileStatus::U,
+            old_path: None,
+            additions,
+            deletions,
+        });
+    } else if status_flags.intersects(Status::WT_MODIFIED) {
+        unstaged.push(FileChange {
+            path: path.to_string(),
+            status: FileStatus::M,
+            old_path: None,
+            additions,
+            deletions,
+        });
+    } else if status_flags.intersects(Status::WT_DELETED) {
+        unstaged.push(FileChange {
+            path: path.to_string(),
+            status: FileStatus::D,
+            old_path: None,
+            additions,
+            deletions,
+        });
+    } else if status_flags.intersects(Status::WT_RENAMED) {
+        unstaged.push(FileChange {
+            path: path.to_string(),
+            status: FileStatus::R,
+            old_path: None,
+            additions,
+            deletions,
+        });
+    }
+
+    // Check for conflicts
+    if status_flags.intersects(Status::CONFLICTED) {
+        unstaged.push(FileChange {
+            path: path.to_string(),
+            status: FileStatus::C,
+            old_path: None,
+            additions,
+            deletions,
+        });
+    }
+}
+
+// `intraline` additionally marks the changed words or characters of each
+// removed/added line pair
+pub fn get_diff(
+    repo_path: &str,
+    rel_path: &str,
+    staged: bool,
+    max_lines: Option<usize>,
+    intraline: Option<IntralineMode>,
+    options: Option<DiffViewOptions>,
+) -> Result<FileDiff, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let view = options.unwrap_or_default();
+    let max_lines = max_lines.unwrap_or(DEFAULT_MAX_DIFF_LINES);
+    let attrs = diff_attributes(&repo, rel_path);
+
+    // Check if file is tracked in the index
+    let index = repo.index().map_err(toe)?;
+    let path = std::path::Path::new(&rel_path);
+    let is_tracked = index.get_path(path, 0).is_some();
+
+    // For untracked files in unstaged view, show entire file as additions
+    if !staged && !is_tracked {
+        // All additions, which every algorithm agrees on
+        let mut file_diff = get_untracked_file_diff(&repo, rel_path, &attrs, max_lines)?;
+        file_diff.algorithm = diff_algorithm(&repo, &view);
+        return Ok(file_diff);
+    }
+
+    let detection = rename_detection(&repo, &view);
+    let head_tree = if staged {
+        repo.head().and_then(|h| h.peel_to_tree()).ok() // HEAD may not exist (unborn repo)
+    } else {
+        None
+    };
+    let file_diff_for = |paths: &[&str]| -> Result<Diff, String> {
+        let mut opts = view_diff_options(&repo, &view, &attrs);
+        for path in paths {
+            opts.pathspec(path);
+        }
+        opts.include_untracked(true); // Include untracked files
+        opts.show_untracked_content(true); // Show content of untracked files
+
+        if staged {
+            // Staged: diff between HEAD tree and index
+            repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut opts)).map_err(toe)
+        } else {
+            // Unstaged: diff between index and working directory
+            repo.diff_index_to_workdir(None, Some(&mut opts)).map_err(toe)
+        }
+    };
+
+    let mut diff = file_diff_for(&[rel_path])?;
+    let mut file_diff = empty_file_diff(rel_path);
+    file_diff.algorithm = diff_algorithm(&repo, &view);
+
+    // A file added to the index may be a rename or copy of one in HEAD
+    let added = diff.deltas().next().is_some_and(|d| d.status() == git2::Delta::Added);
+    if staged && added && detection != RenameDetection::Off {
+        let mut all = file_diff_for(&[])?;
+        all.find_similar(Some(DiffFindOptions::new().renames(true).copies(detection == RenameDetection::Copies)))
+            .map_err(toe)?;
+        let source = all.deltas().find_map(|d| {
+            let renamed = matches!(d.status(), git2::Delta::Renamed | git2::Delta::Copied);
+            let matches = d.new_file().path().and_then(|p| p.to_str()) == Some(rel_path);
+            if renamed && matches { d.old_file().path().and_then(|p| p.to_str()).map(|p| p.to_string()) } else { None }
+        });
+        if let Some(source) = source {
+            diff = file_diff_for(&[rel_path, &source])?;
+            diff.find_similar(Some(DiffFindOptions::new().renames(true).copies(detection == RenameDetection::Copies)))
+                .map_err(toe)?;
+            file_diff.old_path = Some(source);
+        }
+    }
+
+    let Some(delta) = diff.deltas().find(|d| d.new_file().path() == Some(path)) else { return Ok(file_diff) };
+    // Sizes and hashes of both sides, reported even when there are no lines to show
+    let odb = repo.odb().map_err(toe)?;
+    let workdir_file = repo.workdir().map(|w| w.join(path)).filter(|p| p.is_file());
+    let old_id = delta.old_file().id();
+    let new_id = delta.new_file().id();
+    if !old_id.is_zero() {
+        file_diff.old_oid = Some(old_id.to_string());
+        file_diff.old_size = odb.read_header(old_id).ok().map(|(size, _)| size as u64);
+    }
+    match (&workdir_file, staged) {
+        (Some(file), false) => {
+            file_diff.new_size = std::fs::metadata(file).ok().map(|m| m.len());
+            file_diff.new_oid = git2::Oid::hash_file(git2::ObjectType::Blob, file).ok().map(|o| o.to_string());
+        }
+        (None, false) => {}
+        _ if !new_id.is_zero() => {
+            file_diff.new_oid = Some(new_id.to_string());
+            file_diff.new_size = odb.read_header(new_id).ok().map(|(size, _)| size as u64);
+        }
+        _ => {}
+    }
+
+    if file_diff.old_size.max(file_diff.new_size).unwrap_or(0) > MAX_DIFF_BYTES {
+        file_diff.too_large = true;
+        return Ok(file_diff);
+    }
+    if attrs.binary {
+        file_diff.is_binary = true;
+        return Ok(file_diff);
+    }
+
+    // A working-tree-encoding file is stored as UTF-8; convert the working
+    // copy before comparing so the diff isn't all noise
+    let converted = match (&attrs.encoding, &workdir_file, staged) {
+        (Some(encoding), Some(file), false) => {
+            let bytes = std::fs::read(file).map_err(|e| format!("Failed to read file content: {}", e))?;
+            decode_with(&bytes, encoding).map(|text| (text, encoding.clone()))
+        }
+        _ => None,
+    };
+
+    let hunks = match converted {
+        Some((text, encoding)) => {
+            file_diff.encoding = encoding;
+            let old_blob = if old_id.is_zero() { None } else { Some(repo.find_blob(old_id).map_err(toe)?) };
+            let old_content = old_blob.as_ref().map(|b| b.content()).unwrap_or(&[]);
+            let mut patch_opts = view_diff_options(&repo, &view, &attrs);
+            let mut patch = git2::Patch::from_buffers(old_content, Some(path), text.as_bytes(), Some(path), Some(&mut patch_opts))
+                .map_err(toe)?;
+            let patch_text = patch.to_buf().map_err(toe)?;
+            if patch_text.is_empty() {
+                vec![]
+            } else {
+                collect_hunks(&Diff::from_buffer(&patch_text).map_err(toe)?)?
+            }
+        }
+        None => {
+            let hunks = collect_file_hunks(&diff)?
+                .into_iter()
+                .filter(|(p, _)| *p == rel_path)
+                .flat_map(|(_, hunks)| hunks)
+                .collect();
+            // Content is loaded by now, so libgit2 knows whether it's binary
+            if diff.deltas().find(|d| d.new_file().path() == Some(path)).is_some_and(|d| d.flags().is_binary()) {
+                file_diff.is_binary = true;
+                return Ok(file_diff);
+            }
+
+            // Report how the new side is encoded; lines were decoded the same way
+            let sample = match (&workdir_file, staged) {
+                (Some(file), false) => std::fs::read(file).ok(),
+                _ => repo.find_blob(new_id).ok().map(|b| b.content().to_vec()),
+            };
+            if let Some(sample) = sample {
+                file_diff.encoding = decode(&sample).encoding.to_string();
+            }
+            hunks
+        }
+    };
+
+    cap_lines(&mut file_diff, hunks, max_lines);
+    if let Some(mode) = intraline {
+        intraline::highlight(&mut file_diff.hunks, mode);
+    }
+    Ok(file_diff)
+}
+
+// Convert a diff into hunks with per-line numbers
+pub(crate) fn collect_hunks(diff: &Diff) -> Result<Vec<DiffHunk>, String> {
+    Ok(collect_file_hunks(diff)?.into_iter().flat_map(|(_, hunks)| hunks).collect())
+}
+
+// Like collect_hunks, but grouped by the (new) path of each file in the diff
+pub(crate) fn collect_file_hunks(diff: &Diff) -> Result<Vec<(String, Vec<DiffHunk>)>, String> {
+    // Use git2's native foreach API to extract hunks directly
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let hunks = Rc::new(RefCell::new(vec![]));
+    let current_hunk: Rc<RefCell<Option<(String, DiffHunk)>>> = Rc::new(RefCell::new(None));
+    let old_lineno = Rc::new(RefCell::new(0u32));
+    let new_lineno = Rc::new(RefCell::new(0u32));
+
+    let hunks_clone = hunks.clone();
+    let current_hunk_clone = current_hunk.clone();
+    let current_hunk_clone2 = current_hunk.clone();
+    let old_lineno_clone = old_lineno.clone();
+    let new_lineno_clone = new_lineno.clone();
+
+    diff.foreach(
+        &mut |_delta, _progress| true,
+        None,
+        Some(&mut move |delta, hunk| {
+            // Push previous hunk if exists
+            if let Some(hunk) = current_hunk_clone.borrow_mut().take() {
+                hunks_clone.borrow_mut().push(hunk);
+            }
+
+            // Start new hunk
+            let header = String::from_utf8_lossy(hunk.header()).to_string();
+            let path = delta.new_file().path().or(delta.old_file().path())
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default();
+            *current_hunk_clone.borrow_mut() = Some((path, DiffHunk {
+                header: header.trim().to_string(),
+                old_start: hunk.old_start(),
+                old_lines: hunk.old_lines(),
+                new_start: hunk.new_start(),
+                new_lines: hunk.new_lines(),
+                lines: vec![],
+                truncated: false,
+            }));
+
+            *old_lineno_clone.borrow_mut() = hunk.old_start();
+            *new_lineno_clone.borrow_mut() = hunk.new_start();
+            true
+        }),
+        Some(&mut move |_delta, _hunk, line| {
+            if let Some((_, ref mut hunk)) = *current_hunk_clone2.borrow_mut() {
+                let origin = line.origin();
+                let content = decode_line(line.content());
+
+                let mut old_ln = old_lineno.borrow_mut();
+                let mut new_ln = new_lineno.borrow_mut();
+
+                let (line_type, old_line, new_line) = match origin {
+                    '+' | '>' => {
+                        let nl = *new_ln;
+                        *new_ln += 1;
+                        (LineType::Addition, None, Some(nl))
+                    }
+                    '-' | '<' => {
+                        let ol = *old_ln;
+                        *old_ln += 1;
+                        (LineType::Deletion, Some(ol), None)
+                    }
+                    ' ' | '=' => {
+                        let ol = *old_ln;
+                        let nl = *new_ln;
+                        *old_ln += 1;
+                        *new_ln += 1;
+                        (LineType::Context, Some(ol), Some(nl))
+                    }
+                    _ => return true, // Skip other line types
+                };
+
+                hunk.lines.push(DiffLine {
+                    content: content.trim_end_matches('\n').to_string(),
+                    line_type,
+                    old_lineno: old_line,
+                    new_lineno: new_line,
+                    changes: vec![],
+                });
+            }
+            true
+        }),
+    ).map_err(toe)?;
+
+    // Push last hunk
+    if let Some(hunk) = current_hunk.borrow_mut().take() {
+        hunks.borrow_mut().push(hunk);
+    }
+
+    // Extract final result from Rc<RefCell<>>
+    let final_hunks: Vec<(String, DiffHunk)> = Rc::try_unwrap(hunks)
+        .unwrap_or_else(|_| panic!("Failed to unwrap hunks"))
+        .into_inner();
+
+    // Hunks of one file arrive together; group consecutive runs
+    let mut files: Vec<(String, Vec<DiffHunk>)> = vec![];
+    for (path, hunk) in final_hunks {
+        match files.last_mut() {
+            Some((last, hunks)) if *last == path => hunks.push(hunk),
+            _ => files.push((path, vec![hunk])),
+        }
+    }
+
+    Ok(files)
+}
+
+pub fn stage(repo_path: &str, paths: &[String]) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let recorder = journal::begin(&repo, &journal::paths_label("Stage", paths), vec![])?;
+    let mut index = repo.index().map_err(toe)?;
+
+    let workdir = repo.workdir().ok_or("Cannot stage in a bare repository")?;
+    for path in paths {
+        let path = std::path::Path::new(path);
+        // A file that's gone from the working tree stages as a deletion
+        if workdir.join(path).symlink_metadata().is_ok() {
+            index.add_path(path).map_err(toe)?;
+        } else {
+            index.remove_path(path).map_err(toe)?;
+        }
+    }
+
+    index.write().map_err(toe)?;
+    recorder.finish(&repo);
+    Ok(())
+}
+
+pub fn unstage(repo_path: &str, paths: &[String]) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let recorder = journal::begin(&repo, &journal::paths_label("Unstage", paths), vec![])?;
+
+    // Get HEAD tree to reset index to
+    let head_tree = repo.head()
+        .and_then(|h| h.peel_to_tree())
+        .ok();
+
+    let mut index = repo.index().map_err(toe)?;
+
+    for path in paths {
+        let path_buf = std::path::Path::new(&path);
+
+        if let Some(ref tree) = head_tree {
+            // Reset index entry to HEAD version
+            let tree_entry = tree.get_path(path_buf);
+            if let Ok(entry) = tree_entry {
+                let oid = entry.id();
+                let mode = entry.filemode();
+                let blob = repo.find_blob(oid).map_err(toe)?;
+
+                // Remove and re-add from HEAD to unstage changes
+                index.remove_path(path_buf).map_err(toe)?;
+                index.add_frombuffer(&git2::IndexEntry {
+                    ctime: git2::IndexTime::new(0, 0),
+                    mtime: git2::IndexTime::new(0, 0),
+                    dev: 0,
+                    ino: 0,
+                    mode: mode as u32,
+                    uid: 0,
+                    gid: 0,
+                    file_size: blob.size() as u32,
+                    id: oid,
+                    flags: 0,
+                    flags_extended: 0,
+                    path: path.as_bytes().to_vec(),
+                }, blob.content()).map_err(toe)?;
+            } else {
+                // File was newly added, just remove from index
+                index.remove_path(path_buf).map_err(toe)?;
+            }
+        } else {
+            // No HEAD (initial commit), just remove from index
+            index.remove_path(path_buf).map_err(toe)?;
+        }
+    }
+
+    index.write().map_err(toe)?;
+    recorder.finish(&repo);
+    Ok(())
+}
+
+// Get or create signature from config
+pub(crate) fn signature(repo: &Repository) -> Result<Signature<'static>, String> {
+    repo.signature()
+        .or_else(|_| {
+            // Fallback: read from git config manually
+            let config = repo.config()?;
+            let name = config.get_string("user.name")?;
+            let email = config.get_string("user.email")?;
+            Signature::now(&name, &email)
+        })
+        .map_err(toe)
+}
+
+// Message git left behind for an in-progress merge, revert or cherry-pick,
+// with comment lines (e.g. the "# Conflicts:" list) stripped
+fn prepared_message(repo: &Repository) -> Option<String> {
+    let raw = repo.message().ok()?;
+    let message = git2::message_prettify(raw, Some(b'#')).ok()?;
+    if message.trim().is_empty() { None } else { Some(message) }
+}
+
+pub fn merge_message(repo_path: &str) -> Result<Option<String>, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    Ok(prepared_message(&repo))
+}
+
+pub fn commit(repo_path: &str, message: &str) -> Result<Commit, String> {
+    let mut repo = Repository::open(repo_path).map_err(toe)?;
+
+    // Merges, reverts and cherry-picks can be concluded with a commit; anything
+    // else (rebase, bisect, ...) has to be finished through its own flow
+    let state = repo.state();
+    let concludes_operation = match state {
+        RepositoryState::Clean => false,
+        RepositoryState::Merge
+        | RepositoryState::Revert
+        | RepositoryState::RevertSequence
+        | RepositoryState::CherryPick
+        | RepositoryState::CherryPickSequence => true,
+        _ => {
+            return Err(format!("Cannot commit during {:?}. Please complete or abort the current operation.", state));
+        }
+    };
+
+    // Extra parents recorded by an in-progress merge
+    let mut merge_heads = vec![];
+    if state == RepositoryState::Merge {
+        repo.mergehead_foreach(|oid| {
+            merge_heads.push(*oid);
+            true
+        }).map_err(toe)?;
+    }
+
+    let sig = signature(&repo)?;
+
+    let mut index = repo.index().map_err(toe)?;
+
+    if index.has_conflicts() {
+        return Err("Cannot commit with unresolved conflicts".to_string());
+    }
+
+    // Fall back to MERGE_MSG when the caller left the message empty
+    let message = if message.trim().is_empty() {
+        prepared_message(&repo).ok_or("Commit message is empty")?
+    } else {
+        message.to_string()
+    };
+
+    // Check if index has staged changes. HEAD has no commit yet on an unborn
+    // branch (whatever it's called; `Repository::is_empty` only knows master).
+    let head_commit = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let head_tree = match &head_commit {
+        Some(commit) => Some(commit.tree().map_err(toe)?),
+        None => None,
+    };
+
+    // A merge commit may legitimately record the same tree as HEAD
+    let diff = repo.diff_tree_to_index(head_tree.as_ref(), Some(&index), None).map_err(toe)?;
+    if diff.deltas().count() == 0 && state != RepositoryState::Merge {
+        return Err("Nothing to commit - no changes staged".to_string());
+    }
+
+    // Write tree from index
+    let tree_id = index.write_tree().map_err(toe)?;
+    let tree = repo.find_tree(tree_id).map_err(toe)?;
+
+    // Get parent commit (if not first commit), plus MERGE_HEAD(s) when merging
+    let mut parent_ids: Vec<_> = head_commit.iter().map(|c| c.id()).collect();
+    parent_ids.extend(merge_heads);
+    let parents = parent_ids
+        .iter()
+        .map(|oid| repo.find_commit(*oid))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(toe)?;
+    let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+
+    // A cherry-pick keeps the original author, like `git cherry-pick --continue`
+    let picked_author = if matches!(state, RepositoryState::CherryPick | RepositoryState::CherryPickSequence) {
+        repo.find_reference("CHERRY_PICK_HEAD")
+            .and_then(|r| r.peel_to_commit())
+            .map(|c| c.author().to_owned())
+            .ok()
+    } else {
+        None
+    };
+    let author = picked_author.as_ref().unwrap_or(&sig);
+
+    // Create commit
+    let summary = git2::message_prettify(message.as_str(), None).unwrap_or_default();
+    let recorder = journal::begin(&repo, &format!("Commit \"{}\"", summary.lines().next().unwrap_or("")), vec![])?;
+    let oid = repo.commit(
+        Some("HEAD"),
+        author,
+        &sig,
+        &message,
+        &tree,
+        &parent_refs,
+    ).map_err(toe)?;
+
+    // Drop MERGE_HEAD, MERGE_MSG, CHERRY_PICK_HEAD, REVERT_HEAD and friends;
+    // inside a multi-commit sequence only the current step is marked done
+    if concludes_operation {
+        if super::sequencer::in_sequence(&repo) {
+            super::sequencer::step_committed(&repo)?;
+        } else {
+            repo.cleanup_state().map_err(toe)?;
+        }
+    }
+
+    recorder.finish(&repo);
+
+    let git_commit = repo.find_commit(oid).map_err(toe)?;
+
+    Ok(Commit {
+        oid: oid.to_string(),
+        author: author.name().unwrap_or("").to_string(),
+        email: author.email().unwrap_or("").to_string(),
+        timestamp: git_commit.time().seconds(),
+        summary: git_commit.summary().unwrap_or("").to_string(),
+        message: None, // Don't include full message in response
+        parents: parents.iter().map(|p| p.id().to_string()).collect(),
+        refs: vec![],
+        lane: None,
+    })
+}
+
+// Whether hunks computed with these options can leave out lines that differ,
+// so that they no longer apply to the real index
+fn hides_changes(view: &DiffViewOptions) -> bool {
+    view.ignore_all_space || view.ignore_space_change || view.ignore_space_at_eol || view.ignore_blank_lines
+}
+
+// Patch containing only the changes of `hunks` that `selected` picks. Changes
+// left out either become context or drop away, depending on whether the side
+// the patch is applied to has them. `reverse` builds the patch that undoes the
+// selected changes instead.
+fn partial_patch(file_path: &str, hunks: &[DiffHunk], selected: impl Fn(&DiffLine) -> bool, reverse: bool) -> String {
+    let mut patch_text = format!("diff --git a/{0} b/{0}\n--- a/{0}\n+++ b/{0}\n", file_path);
+    let mut offset: i64 = 0;
+
+    for hunk in hunks {
+        let mut body = String::new();
+        let (mut old_count, mut new_count) = (0i64, 0i64);
+        let mut any = false;
+
+        for line in &hunk.lines {
+            let prefix = match (&line.line_type, selected(line), reverse) {
+                (LineType::Context, _, _) => ' ',
+                (LineType::Deletion, true, false) | (LineType::Addition, true, true) => '-',
+                (LineType::Addition, true, false) | (LineType::Deletion, true, true) => '+',
+                (LineType::Deletion, false, false) | (LineType::Addition, false, true) => ' ',
+                (LineType::Deletion, false, true) | (LineType::Addition, false, false) => continue,
+            };
+            any |= prefix != ' ';
+            if prefix != '+' {
+                old_count += 1;
+            }
+            if prefix != '-' {
+                new_count += 1;
+            }
+            body.push(prefix);
+            body.push_str(&line.content);
+            body.push('\n');
+        }
+        if !any {
+            continue;
+        }
+
+        // Unified diff headers give the line before the hunk when a side is empty
+        let (start, count) = if reverse { (hunk.new_start, hunk.new_lines) } else { (hunk.old_start, hunk.old_lines) };
+        let first_old = if count == 0 { start as i64 + 1 } else { start as i64 };
+        let first_new = first_old + offset;
+        patch_text.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            if old_count == 0 { first_old - 1 } else { first_old },
+            old_count,
+            if new_count == 0 { first_new - 1 } else { first_new },
+            new_count
+        ));
+        patch_text.push_str(&body);
+        offset += new_count - old_count;
+    }
+    patch_text
+}
+
+// Stage (or unstage) a hunk shown with whitespace ignored. Its lines don't
+// match the index, so instead the real changes within the lines it covers are
+// applied, including whitespace-only ones the view was hiding.
+fn apply_covered_changes(repo: &Repository, repo_path: &str, file_path: &str, shown: &DiffHunk, staged: bool, view: DiffViewOptions) -> Result<(), String> {
+    let exact = DiffViewOptions { algorithm: view.algorithm, renames: view.renames, ..Default::default() };
+    let real = get_diff(repo_path, file_path, staged, Some(usize::MAX), None, Some(exact))?;
+
+    let old_range = shown.old_start..shown.old_start + shown.old_lines;
+    let new_range = shown.new_start..shown.new_start + shown.new_lines;
+    let covered = |line: &DiffLine| match line.line_type {
+        LineType::Deletion => line.old_lineno.is_some_and(|n| old_range.contains(&n)),
+        LineType::Addition => line.new_lineno.is_some_and(|n| new_range.contains(&n)),
+        LineType::Context => false,
+    };
+    if !real.hunks.iter().flat_map(|h| &h.lines).any(covered) {
+        return Err("No changes left to apply in this hunk".to_string());
+    }
+
+    let patch_text = partial_patch(file_path, &real.hunks, covered, staged);
+    let diff = Diff::from_buffer(patch_text.as_bytes()).map_err(toe)?;
+    let label = if staged { "Unstage hunk in" } else { "Stage hunk in" };
+    let recorder = journal::begin(repo, &format!("{} {}", label, file_path), vec![])?;
+    repo.apply(&diff, ApplyLocation::Index, None).map_err(toe)?;
+    recorder.finish(repo);
+    Ok(())
+}
+
+// A hunk cut short by the diff's line limit is missing lines its header counts
+fn ensure_whole_hunk(hunk: &DiffHunk) -> Result<(), String> {
+    match hunk.truncated {
+        true => Err("This hunk is only partly shown. Load the rest of the diff to stage it.".to_string()),
+        false => Ok(()),
+    }
+}
+
+pub fn stage_hunk(repo_path: &str, file_path: &str, hunk: &DiffHunk, options: Option<DiffViewOptions>) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    ensure_whole_hunk(hunk)?;
+    if let Some(view) = options.filter(hides_changes) {
+        return apply_covered_changes(&repo, repo_path, file_path, hunk, false, view);
+    }
+
+    // Reconstruct a valid unified diff patch from the hunk
+    // We need the full patch format including headers
+    let mut patch_text = String::new();
+
+    // Add diff header
+    patch_text.push_str(&format!("diff --git a/{} b/{}\n", file_path, file_path));
+    patch_text.push_str(&format!("--- a/{}\n", file_path));
+    patch_text.push_str(&format!("+++ b/{}\n", file_path));
+
+    // Add hunk header
+    patch_text.push_str(&hunk.header);
+    patch_text.push('\n');
+
+    // Add hunk lines with proper prefixes
+    for line in &hunk.lines {
+        let prefix = match line.line_type {
+            LineType::Addition => '+',
+            LineType::Deletion => '-',
+            LineType::Context => ' ',
+        };
+        patch_text.push(prefix);
+        patch_text.push_str(&line.content);
+        if !line.content.ends_with('\n') {
+            patch_text.push('\n');
+        }
+    }
+
+    // Create a Diff object from the patch text
+    let diff = Diff::from_buffer(patch_text.as_bytes()).map_err(toe)?;
+
+    // Apply the diff to the index (staging area)
+    let recorder = journal::begin(&repo, &format!("Stage hunk in {}", file_path), vec![])?;
+    repo.apply(&diff, ApplyLocation::Index, None).map_err(toe)?;
+    recorder.finish(&repo);
+
+    Ok(())
+}
+
+pub fn log(repo_path: &str, limit: Option<usize>) -> Result<Vec<Commit>, String> {
+    use git2::Sort;
+
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let limit = limit.unwrap_or(500); // Default to 500 commits
+
+    // Create revwalk with time sort (equivalent to git log --date-order)
+    // This shows commits in chronological order across all branches
+    let mut revwalk = repo.revwalk().map_err(toe)?;
+    revwalk.set_sorting(Sort::TIME).map_err(toe)?;
+
+    // Push ALL branches (local + remote) to show complete graph like GitKraken
+    // This ensures we see commits from all branches, not just the current one
+    let mut has_commits = false;
+
+    // Push all local branch heads
+    if let Ok(branches) = repo.branches(Some(git2::BranchType::Local)) {
+        for (branch, _) in branches.flatten() {
+            let reference = branch.get();
+            if let Some(oid) = reference.target() {
+                revwalk.push(oid).map_err(toe)?;
+                has_commits = true;
+            }
+        }
+    }
+
+    // Push all remote branch heads
+    if let Ok(branches) = repo.branches(Some(git2::BranchType::Remote)) {
+        for (branch, _) in branches.flatten() {
+            let reference = branch.get();
+            if let Some(oid) = reference.target() {
+                revwalk.push(oid).map_err(toe)?;
+                has_commits = true;
+            }
+        }
+    }
+
+    // If no branches found, try HEAD as fallback
+    if !has_commits {
+        if let Ok(head) = repo.head() {
+            if let Some(head_oid) = head.target() {
+                revwalk.push(head_oid).map_err(toe)?;
+                has_commits = true;
+            }
+        }
+    }
+
+    // No commits yet - return empty list
+    if !has_commits {
+        return Ok(vec![]);
+    }
+
+    let mut commits = vec![];
+
+    for oid_result in revwalk.take(limit) {
+        let oid = oid_result.map_err(toe)?;
+        let commit = repo.find_commit(oid).map_err(toe)?;
+        commits.push(to_commit(&commit));
+    }
+
+    Ok(commits)
+}
+
+pub fn unstage_hunk(repo_path: &str, file_path: &str, hunk: &DiffHunk, options: Option<DiffViewOptions>) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    ensure_whole_hunk(hunk)?;
+    if let Some(view) = options.filter(hides_changes) {
+        return apply_covered_changes(&repo, repo_path, file_path, hunk, true, view);
+    }
+
+    // To unstage a hunk, we need to apply the reverse patch to the index
+    // This means swapping additions and deletions
+    let mut patch_text = String::new();
+
+    // Add diff header (reversed: b -> a)
+    patch_text.push_str(&format!("diff --git a/{} b/{}\n", file_path, file_path));
+    patch_text.push_str(&format!("--- a/{}\n", file_path));
+    patch_text.push_str(&format!("+++ b/{}\n", file_path));
+
+    // Reverse the hunk header (swap old and new line counts)
+    // Original: @@ -old_start,old_lines +new_start,new_lines @@
+    // Reversed: @@ -new_start,new_lines +old_start,old_lines @@
+    let reversed_header = format!(
+        "@@ -{},{} +{},{} @@",
+        hunk.new_start,
+        hunk.new_lines,
+        hunk.old_start,
+        hunk.old_lines
+    );
+
+    patch_text.push_str(&reversed_header);
+    patch_text.push('\n');
+
+    // Add hunk lines with reversed prefixes (+ becomes -, - becomes +)
+    for line in &hunk.lines {
+        let prefix = match line.line_type {
+            LineType::Addition => '-',  // Reverse: addition becomes deletion
+            LineType::Deletion => '+',  // Reverse: deletion becomes addition
+            LineType::Context => ' ',
+        };
+        patch_text.push(prefix);
+        patch_text.push_str(&line.content);
+        if !line.content.ends_with('\n') {
+            patch_text.push('\n');
+        }
+    }
+
+    // Create a Diff object from the reversed patch text
+    let diff = Diff::from_buffer(patch_text.as_bytes()).map_err(toe)?;
+
+    // Apply the reversed diff to the index (unstaging)
+    let recorder = journal::begin(&repo, &format!("Unstage hunk in {}", file_path), vec![])?;
+    repo.apply(&diff, ApplyLocation::Index, None).map_err(toe)?;
+    recorder.finish(&repo);
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use crate::domain::types::{ResetMode, ResetResult};
+use super::journal;
+use super::repo::toe;
+use git2::{build::CheckoutBuilder, Repository, ResetType, StatusOptions};
+use std::path::Path;
+
+// Tracked files with staged or unstaged changes
+fn dirty_files(repo: &Repository) -> Result<Vec<String>, String> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false).include_ignored(false);
+    let statuses = repo.statuses(Some(&mut opts)).map_err(toe)?;
+
+    Ok(statuses
+        .iter()
+        .filter_map(|entry| entry.path().map(|p| p.to_string()))
+        .collect())
+}
+
+pub fn reset(repo_path: &str, target: &str, mode: ResetMode, force: bool) -> Result<ResetResult, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+
+    let previous = repo.head().map_err(toe)?.peel_to_commit().map_err(toe)?;
+    let target_commit = repo.revparse_single(target).map_err(toe)?.peel_to_commit().map_err(toe)?;
+
+    // A hard reset throws away uncommitted work; make the caller confirm it
+    let dirty = if mode == ResetMode::Hard { dirty_files(&repo)? } else { vec![] };
+    if !dirty.is_empty() && !force {
+        return Ok(ResetResult {
+            applied: false,
+            previous_head: previous.id().to_string(),
+            new_head: previous.id().to_string(),
+            dirty_files: dirty,
+        });
+    }
+
+    // A hard reset rewrites the working tree; keep what it overwrites so it can be undone
+    let files = if mode == ResetMode::Hard {
+        journal::files_changed_by_checkout(&repo, &target_commit.tree().map_err(toe)?, &[])?
+    } else {
+        vec![]
+    };
+    let recorder = journal::begin(&repo, &format!("Reset to {}", target), files)?;
+
+    let reset_type = match mode {
+        ResetMode::Soft => ResetType::Soft,
+        ResetMode::Mixed => ResetType::Mixed,
+        ResetMode::Hard => ResetType::Hard,
+    };
+    repo.reset(target_commit.as_object(), reset_type, None).map_err(toe)?;
+
+    // Like git, remember where HEAD was so `reset ORIG_HEAD` undoes this
+    repo.reference("ORIG_HEAD", previous.id(), true, "reset").map_err(toe)?;
+    recorder.finish(&repo);
+
+    Ok(ResetResult {
+        applied: true,
+        previous_head: previous.id().to_string(),
+        new_head: target_commit.id().to_string(),
+        dirty_files: dirty,
+    })
+}
+
+pub fn checkout_commit(repo_path: &str, target: &str, force: bool) -> Result<String, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let commit = repo.revparse_single(target).map_err(toe)?.peel_to_commit().map_err(toe)?;
+
+    let files = journal::files_changed_by_checkout(&repo, &commit.tree().map_err(toe)?, &[])?;
+    let recorder = journal::begin(&repo, &format!("Check out {}", target), files)?;
+
+    // Safe checkout refuses to overwrite local changes unless forced
+    let mut checkout = CheckoutBuilder::new();
+    if force {
+        checkout.force();
+    } else {
+        checkout.safe();
+    }
+    repo.checkout_tree(commit.as_object(), Some(&mut checkout)).map_err(|e| {
+        if e.code() == git2::ErrorCode::Conflict {
+            "Local changes would be overwritten by checkout. Commit or discard them, or force the checkout.".to_string()
+        } else {
+            toe(e)
+        }
+    })?;
+    repo.set_head_detached(commit.id()).map_err(toe)?;
+    recorder.finish(&repo);
+
+    Ok(commit.id().to_string())
+}
+
+pub fn checkout_paths(repo_path: &str, target: &str, paths: &[String]) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let commit = repo.revparse_single(target).map_err(toe)?.peel_to_commit().map_err(toe)?;
+    let tree = commit.tree().map_err(toe)?;
+
+    for path in paths {
+        if tree.get_path(Path::new(path)).is_err() {
+            return Err(format!("{} does not exist in {}", path, target));
+        }
+    }
+
+    let files = journal::files_changed_by_checkout(&repo, &tree, paths)?;
+    let recorder = journal::begin(&repo, &journal::paths_label(&format!("Check out {} of", target), paths), files)?;
+
+    // Overwrites both index and working tree for just these paths, like
+    // `git checkout <rev> -- <paths>`
+    let mut checkout = CheckoutBuilder::new();
+    checkout.force();
+    for path in paths {
+        checkout.path(path);
+    }
+    repo.checkout_tree(commit.as_object(), Some(&mut checkout)).map_err(toe)?;
+    recorder.finish(&repo);
+
+    Ok(())
+}
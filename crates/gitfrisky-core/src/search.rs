@@ -0,0 +1,277 @@
+use crate::domain::types::{Commit, DiffHunk, HistorySearch, LineType, SearchDone, SearchFileMatch, SearchMatch, SearchMode};
+use super::repo::{collect_file_hunks, toe, to_commit};
+use git2::{DiffOptions, Oid, Repository, Revwalk, Sort};
+use regex::{Regex, RegexBuilder};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+const DEFAULT_LIMIT: usize = 100;
+const MAX_WORKERS: usize = 8;
+// Commits handed out but not yet reported. Caps how far workers run ahead of
+// a slow commit, and so how many finished results wait for it.
+const MAX_IN_FLIGHT: usize = 256;
+
+// Running searches, so they can be cancelled by id
+type RunningSearches = Arc<Mutex<HashMap<u64, Arc<AtomicBool>>>>;
+
+pub struct SearchState {
+    next_id: AtomicU64,
+    running: RunningSearches,
+}
+
+impl SearchState {
+    pub fn new() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            running: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl Default for SearchState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn build_matcher(query: &HistorySearch) -> Result<Regex, String> {
+    // -S looks for a literal string unless asked to treat it as a regex
+    let pattern = match query.mode {
+        SearchMode::Pickaxe if !query.pickaxe_regex => regex::escape(&query.pattern),
+        _ => query.pattern.clone(),
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(query.ignore_case)
+        .build()
+        .map_err(|e| format!("Invalid search pattern: {}", e))
+}
+
+fn revisions<'r>(repo: &'r Repository, range: Option<&str>) -> Result<Revwalk<'r>, String> {
+    let mut revwalk = repo.revwalk().map_err(toe)?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME).map_err(toe)?;
+
+    match range {
+        Some(range) if range.contains("..") => revwalk.push_range(range).map_err(toe)?,
+        _ => {
+            let commit = repo
+                .revparse_single(range.unwrap_or("HEAD"))
+                .map_err(toe)?
+                .peel_to_commit()
+                .map_err(toe)?;
+            revwalk.push(commit.id()).map_err(toe)?;
+        }
+    }
+
+    Ok(revwalk)
+}
+
+fn count_matches(repo: &Repository, oid: Oid, matcher: &Regex) -> Result<Option<usize>, String> {
+    if oid.is_zero() {
+        return Ok(Some(0));
+    }
+    let blob = repo.find_blob(oid).map_err(toe)?;
+    if blob.is_binary() {
+        return Ok(None);
+    }
+    Ok(Some(matcher.find_iter(&String::from_utf8_lossy(blob.content())).count()))
+}
+
+// Hunks with an added or removed line matching the pattern
+fn matching_hunks(hunks: Vec<DiffHunk>, matcher: &Regex) -> Vec<DiffHunk> {
+    hunks
+        .into_iter()
+        .filter(|hunk| {
+            hunk.lines
+                .iter()
+                .any(|line| !matches!(line.line_type, LineType::Context) && matcher.is_match(&line.content))
+        })
+        .collect()
+}
+
+// Check one commit against the query. Merges are skipped, as `git log -S/-G` does.
+fn scan_commit(repo: &Repository, oid: Oid, query: &HistorySearch, matcher: &Regex) -> Result<Option<Vec<SearchFileMatch>>, String> {
+    let commit = repo.find_commit(oid).map_err(toe)?;
+    if commit.parent_count() > 1 {
+        return Ok(None);
+    }
+    let tree = commit.tree().map_err(toe)?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree().map_err(toe)?),
+        Err(_) => None,
+    };
+
+    let mut opts = DiffOptions::new();
+    for path in &query.paths {
+        opts.pathspec(path);
+    }
+
+    let files = match query.mode {
+        // -S: the number of occurrences changed. Only files that pass are diffed.
+        SearchMode::Pickaxe => {
+            let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut opts)).map_err(toe)?;
+            let mut changed = vec![];
+            for delta in diff.deltas() {
+                let before = count_matches(repo, delta.old_file().id(), matcher)?;
+                let after = count_matches(repo, delta.new_file().id(), matcher)?;
+                if let (Some(before), Some(after)) = (before, after) {
+                    if before != after {
+                        changed.extend(delta.new_file().path().or(delta.old_file().path()).map(|p| p.to_string_lossy().to_string()));
+                    }
+                }
+            }
+            if changed.is_empty() {
+                return Ok(None);
+            }
+
+            let mut opts = DiffOptions::new();
+            for path in &changed {
+                opts.pathspec(path);
+            }
+            let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut opts)).map_err(toe)?;
+            collect_file_hunks(&diff)?
+                .into_iter()
+                .map(|(path, hunks)| {
+                    // A multi-line pattern won't show up in any single line; keep the whole file then
+                    let matched = matching_hunks(hunks.clone(), matcher);
+                    SearchFileMatch { path, hunks: if matched.is_empty() { hunks } else { matched } }
+                })
+                .collect::<Vec<_>>()
+        }
+        // -G: an added or removed line matches
+        SearchMode::Regex => {
+            let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut opts)).map_err(toe)?;
+            collect_file_hunks(&diff)?
+                .into_iter()
+                .map(|(path, hunks)| SearchFileMatch { path, hunks: matching_hunks(hunks, matcher) })
+                .filter(|file| !file.hunks.is_empty())
+                .collect::<Vec<_>>()
+        }
+    };
+
+    Ok(if files.is_empty() { None } else { Some(files) })
+}
+
+// Scan history on a pool of threads, each with its own repository handle.
+// Commits are taken from the revwalk as workers need them, and results are
+// reported in history order as soon as all earlier commits are done.
+fn run_search(
+    repo_path: &str,
+    query: HistorySearch,
+    matcher: Regex,
+    cancelled: Arc<AtomicBool>,
+    mut on_match: impl FnMut(Commit, Vec<SearchFileMatch>),
+) -> Result<(usize, usize), String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let mut revwalk = revisions(&repo, query.range.as_deref())?;
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    let query = Arc::new(query);
+    let matcher = Arc::new(matcher);
+    let (work_tx, work_rx) = mpsc::channel::<(usize, Oid)>();
+    let work_rx = Arc::new(Mutex::new(work_rx));
+    let (tx, rx) = mpsc::channel();
+
+    let workers = thread::available_parallelism().map(|n| n.get()).unwrap_or(1).min(MAX_WORKERS);
+    for _ in 0..workers {
+        let (work_rx, query, matcher, cancelled, tx) = (work_rx.clone(), query.clone(), matcher.clone(), cancelled.clone(), tx.clone());
+        let repo_path = repo_path.to_string();
+        thread::spawn(move || {
+            let repo = match Repository::open(&repo_path) {
+                Ok(repo) => repo,
+                Err(e) => {
+                    let _ = tx.send((usize::MAX, Oid::zero(), Err(toe(e))));
+                    return;
+                }
+            };
+            while !cancelled.load(Ordering::Relaxed) {
+                // Ends once the search stops handing out work
+                let Ok((i, oid)) = work_rx.lock().unwrap().recv() else { break };
+                if tx.send((i, oid, scan_commit(&repo, oid, &query, &matcher))).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(tx);
+
+    let mut pending = BTreeMap::new();
+    let mut next_to_report = 0;
+    let mut handed_out = 0;
+    let mut walked_all = false;
+    let mut matched = 0;
+    while !cancelled.load(Ordering::Relaxed) {
+        while !walked_all && handed_out < next_to_report + MAX_IN_FLIGHT {
+            match revwalk.next() {
+                Some(oid) => {
+                    let _ = work_tx.send((handed_out, oid.map_err(toe)?));
+                    handed_out += 1;
+                }
+                None => walked_all = true,
+            }
+        }
+        if next_to_report == handed_out {
+            break; // Everything walked has been reported
+        }
+
+        let Ok((i, oid, result)) = rx.recv() else { break };
+        pending.insert(i, (oid, result?));
+
+        while let Some((oid, files)) = pending.remove(&next_to_report) {
+            if let Some(files) = files {
+                on_match(to_commit(&repo.find_commit(oid).map_err(toe)?), files);
+                matched += 1;
+                if matched >= limit {
+                    cancelled.store(true, Ordering::Relaxed);
+                    return Ok((next_to_report + 1, matched));
+                }
+            }
+            next_to_report += 1;
+        }
+    }
+
+    Ok((next_to_report, matched))
+}
+
+impl SearchState {
+    // Start a pickaxe (-S) or diff regex (-G) search on a background thread.
+    // Returns the search id right away; matches go to `on_match` in history
+    // order and `on_done` reports the outcome.
+    pub fn start(
+        &self,
+        repo_path: &str,
+        query: HistorySearch,
+        mut on_match: impl FnMut(SearchMatch) + Send + 'static,
+        on_done: impl FnOnce(SearchDone) + Send + 'static,
+    ) -> Result<u64, String> {
+        let matcher = build_matcher(&query)?;
+        let search_id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.running.lock().map_err(toe)?.insert(search_id, cancelled.clone());
+
+        let running = self.running.clone();
+        let repo_path = repo_path.to_string();
+        thread::spawn(move || {
+            let result = run_search(&repo_path, query, matcher, cancelled, |commit, files| {
+                on_match(SearchMatch { search_id, commit, files });
+            });
+
+            // cancel takes the entry out of the map; if it's gone, the user cancelled
+            let cancelled = running.lock().map(|mut r| r.remove(&search_id).is_none()).unwrap_or(false);
+            let (scanned, matched, error) = match result {
+                Ok((scanned, matched)) => (scanned, matched, None),
+                Err(e) => (0, 0, Some(e)),
+            };
+            on_done(SearchDone { search_id, scanned, matched, cancelled, error });
+        });
+
+        Ok(search_id)
+    }
+
+    pub fn cancel(&self, search_id: u64) -> Result<(), String> {
+        if let Some(cancelled) = self.running.lock().map_err(toe)?.remove(&search_id) {
+            cancelled.store(true, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,478 @@
+use crate::domain::types::{Commit, GitError, PickOptions, SequenceKind, SequenceStatus};
+use super::journal;
+use super::repo::{ensure_clean_worktree, signature, to_commit};
+use git2::{build::CheckoutBuilder, CherrypickOptions, Oid, Repository, RepositoryState, RevertOptions};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Multi-commit cherry-picks and reverts keep their remaining steps in
+// .git/sequencer (todo, head, opts) like command-line git, so a conflict
+// leaves RepoState::CherryPick / Revert and the app can continue or abort later.
+const SEQUENCER_DIR: &str = "sequencer";
+// Branch to return to after a --no-commit sequence, which runs on a detached HEAD
+const HEAD_NAME_FILE: &str = "gitfrisky-head-name";
+
+fn op_failed<E: std::fmt::Display>(e: E) -> GitError {
+    GitError::OperationFailed(e.to_string())
+}
+
+fn sequencer_dir(repo: &Repository) -> PathBuf {
+    repo.path().join(SEQUENCER_DIR)
+}
+
+fn keyword(kind: SequenceKind) -> &'static str {
+    match kind {
+        SequenceKind::CherryPick => "pick",
+        SequenceKind::Revert => "revert",
+    }
+}
+
+fn write_file(path: &Path, content: &str) -> Result<(), GitError> {
+    fs::write(path, content).map_err(|e| GitError::OperationFailed(format!("Failed to write sequencer state: {}", e)))
+}
+
+fn write_todo(repo: &Repository, steps: &[(SequenceKind, Oid)]) -> Result<(), GitError> {
+    let mut text = String::new();
+    for (kind, oid) in steps {
+        let summary = repo.find_commit(*oid).ok().and_then(|c| c.summary().map(|s| s.to_string())).unwrap_or_default();
+        text.push_str(&format!("{} {} {}\n", keyword(*kind), oid, summary));
+    }
+    write_file(&sequencer_dir(repo).join("todo"), &text)
+}
+
+fn read_todo(repo: &Repository) -> Result<Vec<(SequenceKind, Oid)>, GitError> {
+    let text = fs::read_to_string(sequencer_dir(repo).join("todo")).unwrap_or_default();
+    let mut steps = vec![];
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let kind = match parts.next() {
+            Some("p") | Some("pick") => SequenceKind::CherryPick,
+            Some("revert") => SequenceKind::Revert,
+            Some(other) => return Err(GitError::OperationFailed(format!("Unsupported sequencer command: {}", other))),
+            None => continue,
+        };
+        let rev = parts.next().ok_or_else(|| GitError::OperationFailed("Malformed sequencer todo".to_string()))?;
+        steps.push((kind, repo.revparse_single(rev)?.peel_to_commit()?.id()));
+    }
+
+    Ok(steps)
+}
+
+// Options are stored in git's own format: `[options]` followed by `key = value`
+fn write_opts(repo: &Repository, options: &PickOptions) -> Result<(), GitError> {
+    let mut text = String::from("[options]\n");
+    if options.no_commit {
+        text.push_str("\tno-commit = true\n");
+    }
+    if options.record_origin {
+        text.push_str("\trecord-origin = true\n");
+    }
+    if let Some(mainline) = options.mainline {
+        text.push_str(&format!("\tmainline = {}\n", mainline));
+    }
+    write_file(&sequencer_dir(repo).join("opts"), &text)
+}
+
+fn read_opts(repo: &Repository) -> PickOptions {
+    let text = fs::read_to_string(sequencer_dir(repo).join("opts")).unwrap_or_default();
+    let mut options = PickOptions::default();
+
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once('=') else { continue };
+        match key.trim() {
+            "no-commit" => options.no_commit = value.trim() == "true",
+            "record-origin" => options.record_origin = value.trim() == "true",
+            "mainline" => options.mainline = value.trim().parse().ok(),
+            _ => {}
+        }
+    }
+
+    options
+}
+
+// Drop the files describing the step in progress, keeping the sequencer itself
+fn clear_step_files(repo: &Repository) {
+    let _ = fs::remove_file(repo.path().join("CHERRY_PICK_HEAD"));
+    let _ = fs::remove_file(repo.path().join("REVERT_HEAD"));
+    let _ = repo.remove_message();
+}
+
+fn conflicted_paths(repo: &Repository) -> Result<Vec<String>, GitError> {
+    let index = repo.index()?;
+    let mut paths = vec![];
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+            paths.push(String::from_utf8_lossy(&entry.path).into_owned());
+        }
+    }
+    Ok(paths)
+}
+
+// Append the -x trailer; no blank line is added when the message already ends in trailers.
+// The subject is never a trailer block, so "docs: update readme" still gets its blank line.
+fn with_origin(message: &str, oid: Oid) -> String {
+    let message = message.trim_end();
+    let last_line = message.lines().last().unwrap_or("");
+    let has_body = message.contains("\n\n");
+    let is_trailer = has_body
+        && last_line.split_once(": ").map(|(key, _)| !key.is_empty() && !key.contains(' ')).unwrap_or(false);
+    let separator = if is_trailer { "\n" } else { "\n\n" };
+    format!("{}{}(cherry picked from commit {})\n", message, separator, oid)
+}
+
+fn step_message(repo: &Repository, kind: SequenceKind, commit: &git2::Commit, options: &PickOptions) -> String {
+    match kind {
+        SequenceKind::CherryPick => {
+            let message = String::from_utf8_lossy(commit.message_bytes()).into_owned();
+            if options.record_origin { with_origin(&message, commit.id()) } else { message }
+        }
+        // libgit2 prepares the "Revert ..." message in MERGE_MSG
+        SequenceKind::Revert => repo.message()
+            .ok()
+            .and_then(|m| git2::message_prettify(m, Some(b'#')).ok())
+            .unwrap_or_else(|| format!("Revert \"{}\"\n\nThis reverts commit {}.\n", commit.summary().unwrap_or(""), commit.id())),
+    }
+}
+
+// Apply one commit (or its inverse) to the index and working tree
+fn apply_step(repo: &Repository, kind: SequenceKind, commit: &git2::Commit, options: &PickOptions) -> Result<(), GitError> {
+    let mainline = match (commit.parent_count() > 1, options.mainline) {
+        (true, None) => {
+            return Err(GitError::OperationFailed(format!("Commit {} is a merge but no mainline was given", commit.id())));
+        }
+        (false, Some(_)) => {
+            return Err(GitError::OperationFailed(format!("Mainline was specified but commit {} is not a merge", commit.id())));
+        }
+        (_, mainline) => mainline.unwrap_or(0),
+    };
+
+    match kind {
+        SequenceKind::CherryPick => {
+            let mut opts = CherrypickOptions::new();
+            opts.mainline(mainline);
+            repo.cherrypick(commit, Some(&mut opts))?;
+        }
+        SequenceKind::Revert => {
+            let mut opts = RevertOptions::new();
+            opts.mainline(mainline);
+            repo.revert(commit, Some(&mut opts))?;
+        }
+    }
+
+    // Keep the final message (with -x trailer) where `commit` and continue will find it
+    let message = step_message(repo, kind, commit, options);
+    write_file(&repo.path().join("MERGE_MSG"), &message)?;
+
+    let conflicts = conflicted_paths(repo)?;
+    if !conflicts.is_empty() {
+        return Err(GitError::MergeConflict(conflicts.join(", ")));
+    }
+
+    Ok(())
+}
+
+fn commit_step(repo: &Repository, kind: SequenceKind, commit: &git2::Commit) -> Result<Oid, GitError> {
+    let sig = signature(repo).map_err(GitError::OperationFailed)?;
+    let message = repo.message().ok()
+        .and_then(|m| git2::message_prettify(m, Some(b'#')).ok())
+        .unwrap_or_else(|| String::from_utf8_lossy(commit.message_bytes()).into_owned());
+
+    let mut index = repo.index()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let head = repo.head()?.peel_to_commit()?;
+
+    // Cherry-picks keep the original author; reverts are authored by the user
+    let author = match kind {
+        SequenceKind::CherryPick => commit.author().to_owned(),
+        SequenceKind::Revert => sig.clone(),
+    };
+
+    let oid = repo.commit(Some("HEAD"), &author, &sig, &message, &tree, &[&head])?;
+    clear_step_files(repo);
+    Ok(oid)
+}
+
+// Discard the working tree changes of the current step without touching the sequencer
+fn discard_step(repo: &Repository) -> Result<(), GitError> {
+    let head = repo.head()?.peel_to_commit()?;
+    let mut checkout = CheckoutBuilder::new();
+    checkout.force();
+    repo.checkout_tree(head.as_object(), Some(&mut checkout))?;
+
+    let mut index = repo.index()?;
+    index.read_tree(&head.tree()?)?;
+    index.write()?;
+    clear_step_files(repo);
+    Ok(())
+}
+
+// Remove the sequencer, returning a --no-commit sequence to its branch. Staged
+// changes stay in the index since only HEAD moves.
+fn finish(repo: &Repository) -> Result<(), GitError> {
+    let dir = sequencer_dir(repo);
+    if let Ok(head_name) = fs::read_to_string(dir.join(HEAD_NAME_FILE)) {
+        let head_name = head_name.trim();
+        if head_name.starts_with("refs/") {
+            repo.set_head(head_name)?;
+        } else if let Ok(head) = fs::read_to_string(dir.join("head")) {
+            repo.set_head_detached(Oid::from_str(head.trim())?)?;
+        }
+    }
+    fs::remove_dir_all(&dir).map_err(op_failed)
+}
+
+// Work through the todo list; the current step stays first in the list until done
+fn run(repo: &Repository) -> Result<Vec<Commit>, GitError> {
+    let options = read_opts(repo);
+    let mut created = vec![];
+
+    loop {
+        let mut steps = read_todo(repo)?;
+        if steps.is_empty() {
+            finish(repo)?;
+            return Ok(created);
+        }
+
+        let (kind, oid) = steps[0];
+        let commit = repo.find_commit(oid)?;
+        apply_step(repo, kind, &commit, &options)?;
+
+        if options.no_commit && steps.len() == 1 {
+            // Last step of a --no-commit run: leave it staged
+            clear_step_files(repo);
+        } else {
+            // Intermediate --no-commit steps are committed on the detached HEAD
+            // so the next pick sees them; finish() moves HEAD back
+            let new_oid = commit_step(repo, kind, &commit)?;
+            if !options.no_commit {
+                created.push(to_commit(&repo.find_commit(new_oid)?));
+            }
+        }
+
+        steps.remove(0);
+        write_todo(repo, &steps)?;
+    }
+}
+
+fn start(repo_path: &str, kind: SequenceKind, oids: &[String], options: PickOptions) -> Result<Vec<Commit>, GitError> {
+    let repo = Repository::open(repo_path)?;
+
+    if repo.state() != RepositoryState::Clean || sequencer_dir(&repo).exists() {
+        return Err(GitError::UnsafeState(super::repo::map_repo_state(repo.state())));
+    }
+    if oids.is_empty() {
+        return Err(GitError::OperationFailed("No commits selected".to_string()));
+    }
+    ensure_clean_worktree(&repo).map_err(GitError::OperationFailed)?;
+
+    let steps = oids
+        .iter()
+        .map(|rev| Ok((kind, repo.revparse_single(rev)?.peel_to_commit()?.id())))
+        .collect::<Result<Vec<_>, GitError>>()?;
+
+    let label = match (kind, steps.as_slice()) {
+        (SequenceKind::CherryPick, [(_, oid)]) => format!("Cherry-pick {}", repo.find_commit(*oid)?.summary().unwrap_or("")),
+        (SequenceKind::Revert, [(_, oid)]) => format!("Revert {}", repo.find_commit(*oid)?.summary().unwrap_or("")),
+        (SequenceKind::CherryPick, _) => format!("Cherry-pick {} commits", steps.len()),
+        (SequenceKind::Revert, _) => format!("Revert {} commits", steps.len()),
+    };
+    let recorder = journal::begin_clean(&repo, &label).map_err(GitError::OperationFailed)?;
+
+    let head = repo.head()?;
+    let head_oid = head.peel_to_commit()?.id();
+
+    let dir = sequencer_dir(&repo);
+    fs::create_dir_all(&dir).map_err(op_failed)?;
+    write_file(&dir.join("head"), &format!("{}\n", head_oid))?;
+    write_opts(&repo, &options)?;
+    write_todo(&repo, &steps)?;
+
+    if options.no_commit && steps.len() > 1 {
+        let head_name = if repo.head_detached()? { "HEAD".to_string() } else { head.name().unwrap_or("HEAD").to_string() };
+        write_file(&dir.join(HEAD_NAME_FILE), &head_name)?;
+        repo.set_head_detached(head_oid)?;
+    }
+
+    // A sequence that stops is undone with abort rather than the journal
+    let created = run(&repo)?;
+    recorder.finish(&repo);
+    Ok(created)
+}
+
+// Called by `commit` when the user commits a resolved step of a sequence:
+// mark the step done instead of wiping the whole sequencer
+pub(crate) fn step_committed(repo: &Repository) -> Result<(), String> {
+    clear_step_files(repo);
+    let mut steps = read_todo(repo).map_err(|e| e.to_string())?;
+    if !steps.is_empty() {
+        steps.remove(0);
+    }
+    if steps.is_empty() {
+        finish(repo).map_err(|e| e.to_string())
+    } else {
+        write_todo(repo, &steps).map_err(|e| e.to_string())
+    }
+}
+
+// The kind of pick git reports as in progress, if any
+fn step_kind(repo: &Repository) -> Option<SequenceKind> {
+    match repo.state() {
+        RepositoryState::CherryPick | RepositoryState::CherryPickSequence => Some(SequenceKind::CherryPick),
+        RepositoryState::Revert | RepositoryState::RevertSequence => Some(SequenceKind::Revert),
+        _ => None,
+    }
+}
+
+fn nothing_in_progress() -> GitError {
+    GitError::OperationFailed("No cherry-pick or revert in progress".to_string())
+}
+
+pub(crate) fn in_sequence(repo: &Repository) -> bool {
+    sequencer_dir(repo).join("todo").exists()
+}
+
+pub fn cherry_pick(repo_path: &str, oids: &[String], options: Option<PickOptions>) -> Result<Vec<Commit>, GitError> {
+    start(repo_path, SequenceKind::CherryPick, oids, options.unwrap_or_default())
+}
+
+pub fn revert(repo_path: &str, oids: &[String], options: Option<PickOptions>) -> Result<Vec<Commit>, GitError> {
+    let options = PickOptions { record_origin: false, ..options.unwrap_or_default() };
+    start(repo_path, SequenceKind::Revert, oids, options)
+}
+
+pub fn sequencer_status(repo_path: &str) -> Result<SequenceStatus, GitError> {
+    let repo = Repository::open(repo_path)?;
+    let steps = if in_sequence(&repo) { read_todo(&repo)? } else { vec![] };
+
+    let kind = match repo.state() {
+        RepositoryState::CherryPick | RepositoryState::CherryPickSequence => Some(SequenceKind::CherryPick),
+        RepositoryState::Revert | RepositoryState::RevertSequence => Some(SequenceKind::Revert),
+        _ => steps.first().map(|(kind, _)| *kind),
+    };
+
+    Ok(SequenceStatus {
+        in_progress: kind.is_some(),
+        kind,
+        remaining: steps.iter().map(|(_, oid)| oid.to_string()).collect(),
+        conflicts: conflicted_paths(&repo)?,
+    })
+}
+
+pub fn sequencer_continue(repo_path: &str) -> Result<Vec<Commit>, GitError> {
+    let repo = Repository::open(repo_path)?;
+
+    let conflicts = conflicted_paths(&repo)?;
+    if !conflicts.is_empty() {
+        return Err(GitError::MergeConflict(conflicts.join(", ")));
+    }
+
+    let kind = match repo.state() {
+        RepositoryState::CherryPick | RepositoryState::CherryPickSequence => Some(SequenceKind::CherryPick),
+        RepositoryState::Revert | RepositoryState::RevertSequence => Some(SequenceKind::Revert),
+        RepositoryState::Clean => None,
+        state => return Err(GitError::UnsafeState(super::repo::map_repo_state(state))),
+    };
+
+    let mut created = vec![];
+
+    // Commit the resolved step, unless it is the final step of a --no-commit run
+    if let Some(kind) = kind {
+        let pending = ["CHERRY_PICK_HEAD", "REVERT_HEAD"]
+            .iter()
+            .find_map(|name| repo.find_reference(name).ok()?.peel_to_commit().ok())
+            .ok_or_else(|| GitError::OperationFailed("Cannot find the commit being applied".to_string()))?;
+
+        let options = read_opts(&repo);
+        let remaining = if in_sequence(&repo) { read_todo(&repo)?.len() } else { 1 };
+        if options.no_commit && remaining <= 1 {
+            clear_step_files(&repo);
+        } else {
+            let oid = commit_step(&repo, kind, &pending)?;
+            if !options.no_commit {
+                created.push(to_commit(&repo.find_commit(oid)?));
+            }
+        }
+
+        if !in_sequence(&repo) {
+            return Ok(created);
+        }
+        let mut steps = read_todo(&repo)?;
+        if !steps.is_empty() {
+            steps.remove(0);
+        }
+        write_todo(&repo, &steps)?;
+    } else if !in_sequence(&repo) {
+        return Err(GitError::OperationFailed("No cherry-pick or revert in progress".to_string()));
+    }
+
+    created.extend(run(&repo)?);
+    Ok(created)
+}
+
+pub fn sequencer_skip(repo_path: &str) -> Result<Vec<Commit>, GitError> {
+    let repo = Repository::open(repo_path)?;
+    // Skipping drops the step's changes, so never touch unrelated edits
+    if step_kind(&repo).is_none() && !in_sequence(&repo) {
+        return Err(nothing_in_progress());
+    }
+    discard_step(&repo)?;
+
+    if !in_sequence(&repo) {
+        return Ok(vec![]);
+    }
+    let mut steps = read_todo(&repo)?;
+    if !steps.is_empty() {
+        steps.remove(0);
+    }
+    write_todo(&repo, &steps)?;
+    run(&repo)
+}
+
+pub fn sequencer_abort(repo_path: &str) -> Result<(), GitError> {
+    let repo = Repository::open(repo_path)?;
+    let dir = sequencer_dir(&repo);
+    let kind = step_kind(&repo);
+
+    // Without a sequencer this was a single step: just drop its changes.
+    // Anything else would throw away edits that have nothing to do with a pick.
+    let Ok(head) = fs::read_to_string(dir.join("head")) else {
+        return match kind {
+            Some(_) => discard_step(&repo),
+            None => Err(nothing_in_progress()),
+        };
+    };
+    let kind = kind.or_else(|| read_todo(&repo).ok()?.first().map(|(kind, _)| *kind));
+    let reflog_message = match kind {
+        Some(SequenceKind::Revert) => "revert: abort",
+        _ => "cherry-pick: abort",
+    };
+
+    // Rewind everything the sequence committed, then return to the original branch
+    let original = repo.find_commit(Oid::from_str(head.trim())?)?;
+    discard_step(&repo)?;
+    let head_name = fs::read_to_string(dir.join(HEAD_NAME_FILE)).ok();
+    match head_name.as_deref().map(str::trim) {
+        Some(name) if name.starts_with("refs/") => {
+            repo.reference(name, original.id(), true, reflog_message)?;
+            repo.set_head(name)?;
+        }
+        Some(_) => repo.set_head_detached(original.id())?,
+        None => {
+            if repo.head_detached()? {
+                repo.set_head_detached(original.id())?;
+            } else {
+                let name = repo.head()?.name().unwrap_or("HEAD").to_string();
+                repo.reference(&name, original.id(), true, reflog_message)?;
+            }
+        }
+    }
+    discard_step(&repo)?;
+
+    fs::remove_dir_all(&dir).map_err(op_failed)
+}
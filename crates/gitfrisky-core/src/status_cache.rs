@@ -0,0 +1,91 @@
+use crate::domain::types::{StatusDelta, StatusPayload};
+use super::repo::{add_changes, scan_status};
+use git2::{Repository, Status};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+// Status of a watched repository, kept current by the file watcher so that
+// asking for it doesn't mean walking the whole working tree again. Paths the
+// watcher reports are re-checked one by one; anything that can change the
+// status of files nobody touched (the index, HEAD, ignore rules) means a full
+// rescan.
+
+// Past this many changed paths one full scan is cheaper than checking each
+const MAX_INCREMENTAL_PATHS: usize = 1000;
+
+pub struct StatusCache {
+    pub git_dir: PathBuf, // Canonical, to match requests against the watched repository
+    entries: BTreeMap<String, Status>, // Every file with changes
+    pub operations: u64, // Our own operations finished when it was last brought up to date
+}
+
+impl StatusCache {
+    pub fn scan(repo: &Repository, git_dir: PathBuf, operations: u64) -> Result<Self, String> {
+        let entries = scan_status(repo, &[])?.into_iter().collect();
+        Ok(Self { git_dir, entries, operations })
+    }
+
+    pub fn payload(&self) -> StatusPayload {
+        let mut payload = StatusPayload { unstaged: vec![], staged: vec![] };
+        for (path, flags) in &self.entries {
+            add_changes(&mut payload, path, *flags);
+        }
+        payload
+    }
+
+    // Scan everything again, reporting what changed since the last scan
+    pub fn rescan(&mut self, repo: &Repository, operations: u64) -> Result<StatusDelta, String> {
+        let entries: BTreeMap<String, Status> = scan_status(repo, &[])?.into_iter().collect();
+        let changed: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(path, flags)| entries.get(*path) != Some(flags))
+            .chain(entries.iter().filter(|(path, _)| !self.entries.contains_key(*path)))
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        self.entries = entries;
+        self.operations = operations;
+        Ok(self.delta(changed))
+    }
+
+    // Re-check the files at or under `paths` (relative to the repo root)
+    pub fn update(&mut self, repo: &Repository, paths: &[String], operations: u64) -> Result<StatusDelta, String> {
+        if paths.len() > MAX_INCREMENTAL_PATHS {
+            return self.rescan(repo, operations);
+        }
+
+        // Entries that were at or under the paths and aren't any more are clean now
+        let current: BTreeMap<String, Status> = scan_status(repo, paths)?.into_iter().collect();
+        let prefixes: Vec<String> = paths.iter().map(|p| format!("{}/", p)).collect();
+        let stale: Vec<String> = self
+            .entries
+            .keys()
+            .filter(|p| !current.contains_key(*p) && (paths.contains(p) || prefixes.iter().any(|prefix| p.starts_with(prefix))))
+            .cloned()
+            .collect();
+
+        let mut changed = BTreeSet::new();
+        for path in stale {
+            self.entries.remove(&path);
+            changed.insert(path);
+        }
+        for (path, flags) in current {
+            if self.entries.insert(path.clone(), flags) != Some(flags) {
+                changed.insert(path);
+            }
+        }
+        Ok(self.delta(changed))
+    }
+
+    fn delta(&self, paths: impl IntoIterator<Item = String>) -> StatusDelta {
+        let mut payload = StatusPayload { unstaged: vec![], staged: vec![] };
+        let paths: Vec<String> = paths.into_iter().collect();
+        for path in &paths {
+            if let Some(flags) = self.entries.get(path) {
+                add_changes(&mut payload, path, *flags);
+            }
+        }
+        StatusDelta { paths, unstaged: payload.unstaged, staged: payload.staged }
+    }
+}
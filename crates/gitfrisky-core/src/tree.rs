@@ -0,0 +1,106 @@
+use crate::domain::types::{BlobContent, TreeEntry, TreeEntryKind};
+use crate::util::io::decode;
+use super::repo::toe;
+use git2::{ObjectType, Repository, Tree};
+use std::path::Path;
+
+// Larger blobs are cut off unless the caller asks for more
+const DEFAULT_MAX_BYTES: usize = 1024 * 1024;
+
+fn tree_at<'r>(repo: &'r Repository, rev: Option<&str>) -> Result<Tree<'r>, String> {
+    repo.revparse_single(rev.unwrap_or("HEAD"))
+        .map_err(toe)?
+        .peel_to_tree()
+        .map_err(toe)
+}
+
+fn entry_kind(mode: i32) -> TreeEntryKind {
+    match mode {
+        0o040000 => TreeEntryKind::Directory,
+        0o160000 => TreeEntryKind::Submodule,
+        0o120000 => TreeEntryKind::Symlink,
+        0o100755 => TreeEntryKind::Executable,
+        _ => TreeEntryKind::File,
+    }
+}
+
+// One directory level of the tree at `rev`; expand subdirectories by calling
+// again with their path. Directories come first, then files, by name.
+pub fn list_tree(repo_path: &str, rev: Option<&str>, path: Option<&str>) -> Result<Vec<TreeEntry>, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let root = tree_at(&repo, rev)?;
+    let dir = path.unwrap_or_default();
+    let dir = dir.trim_matches('/');
+
+    let tree = if dir.is_empty() {
+        root
+    } else {
+        let entry = root.get_path(Path::new(dir)).map_err(|_| format!("{} does not exist", dir))?;
+        if entry.kind() != Some(ObjectType::Tree) {
+            return Err(format!("{} is not a directory", dir));
+        }
+        repo.find_tree(entry.id()).map_err(toe)?
+    };
+
+    let odb = repo.odb().map_err(toe)?;
+    let mut entries: Vec<TreeEntry> = tree
+        .iter()
+        .map(|entry| {
+            let name = String::from_utf8_lossy(entry.name_bytes()).to_string();
+            let kind = entry_kind(entry.filemode());
+            // Header reads give the size without loading the blob
+            let size = match kind {
+                TreeEntryKind::File | TreeEntryKind::Executable | TreeEntryKind::Symlink => {
+                    odb.read_header(entry.id()).ok().map(|(size, _)| size as u64)
+                }
+                _ => None,
+            };
+            TreeEntry {
+                path: if dir.is_empty() { name.clone() } else { format!("{}/{}", dir, name) },
+                name,
+                oid: entry.id().to_string(),
+                mode: entry.filemode() as u32,
+                kind,
+                size,
+            }
+        })
+        .collect();
+
+    entries.sort_by(|a, b| {
+        let a_dir = a.kind == TreeEntryKind::Directory;
+        let b_dir = b.kind == TreeEntryKind::Directory;
+        b_dir.cmp(&a_dir).then_with(|| a.name.cmp(&b.name))
+    });
+
+    Ok(entries)
+}
+
+// Content of a file at `rev`, decoded for display. Binary files return no
+// content; anything over `max_bytes` is truncated.
+pub fn read_blob(repo_path: &str, rev: Option<&str>, path: &str, max_bytes: Option<usize>) -> Result<BlobContent, String> {
+    let repo = Repository::open(repo_path).map_err(toe)?;
+    let tree = tree_at(&repo, rev)?;
+    let entry = tree.get_path(Path::new(&path)).map_err(|_| format!("{} does not exist", path))?;
+
+    match entry_kind(entry.filemode()) {
+        TreeEntryKind::Directory => return Err(format!("{} is a directory", path)),
+        TreeEntryKind::Submodule => return Err(format!("{} is a submodule at {}", path, entry.id())),
+        _ => {}
+    }
+
+    let blob = repo.find_blob(entry.id()).map_err(toe)?;
+    let bytes = blob.content();
+    let max_bytes = max_bytes.unwrap_or(DEFAULT_MAX_BYTES);
+    let truncated = bytes.len() > max_bytes;
+    let decoded = decode(&bytes[..bytes.len().min(max_bytes)]);
+
+    Ok(BlobContent {
+        path: path.to_string(),
+        oid: blob.id().to_string(),
+        size: bytes.len() as u64,
+        is_binary: decoded.is_binary,
+        encoding: decoded.encoding.to_string(),
+        content: decoded.text,
+        truncated,
+    })
+}
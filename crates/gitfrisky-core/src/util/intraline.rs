@@ -0,0 +1,250 @@
+// Intra-line highlighting: pair up the removed and added lines of each change
+// block and mark which words (or characters) within them actually changed.
+
+use crate::domain::types::{ChangeRange, DiffHunk, IntralineMode, LineType};
+
+// Lines whose changed middles would need a bigger comparison table than this
+// are marked as changed wholesale
+const MAX_CELLS: usize = 1_000_000;
+
+struct Token<'a> {
+    text: &'a str,
+    start: u32, // UTF-16 offsets into the line
+    end: u32,
+}
+
+#[derive(PartialEq)]
+enum Class {
+    Word,
+    Space,
+    Other,
+}
+
+fn class(c: char) -> Class {
+    if c.is_alphanumeric() || c == '_' {
+        Class::Word
+    } else if c.is_whitespace() {
+        Class::Space
+    } else {
+        Class::Other
+    }
+}
+
+// Words are runs of letters, digits and underscores; whitespace runs are one
+// token and every other character stands alone
+fn tokenize(line: &str, mode: IntralineMode) -> Vec<Token<'_>> {
+    let mut tokens: Vec<Token> = vec![];
+    let mut offset = 0u32;
+    let mut byte_start = 0;
+    let mut token_start = 0u32;
+    let mut prev: Option<Class> = None;
+
+    for (i, c) in line.char_indices() {
+        let current = class(c);
+        let joins = mode == IntralineMode::Word && current != Class::Other && prev.as_ref() == Some(&current);
+        if !joins && i > 0 {
+            tokens.push(Token { text: &line[byte_start..i], start: token_start, end: offset });
+            byte_start = i;
+            token_start = offset;
+        }
+        offset += c.len_utf16() as u32;
+        prev = Some(current);
+    }
+    if byte_start < line.len() {
+        tokens.push(Token { text: &line[byte_start..], start: token_start, end: offset });
+    }
+    tokens
+}
+
+// Which tokens of `a` and `b` are not part of their longest common subsequence
+fn changed_tokens(a: &[Token], b: &[Token]) -> (Vec<bool>, Vec<bool>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x.text == y.text).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x.text == y.text)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut a_changed = vec![false; a.len()];
+    let mut b_changed = vec![false; b.len()];
+    let (n, m) = (a_mid.len(), b_mid.len());
+
+    if n * m > MAX_CELLS {
+        a_changed[prefix..prefix + n].fill(true);
+        b_changed[prefix..prefix + m].fill(true);
+        return (a_changed, b_changed);
+    }
+
+    // lcs[i][j]: length of the LCS of a_mid[i..] and b_mid[j..]
+    let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[at(i, j)] = if a_mid[i].text == b_mid[j].text {
+                lcs[at(i + 1, j + 1)] + 1
+            } else {
+                lcs[at(i + 1, j)].max(lcs[at(i, j + 1)])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && a_mid[i].text == b_mid[j].text {
+            i += 1;
+            j += 1;
+        } else if j == m || (i < n && lcs[at(i + 1, j)] >= lcs[at(i, j + 1)]) {
+            a_changed[prefix + i] = true;
+            i += 1;
+        } else {
+            b_changed[prefix + j] = true;
+            j += 1;
+        }
+    }
+    (a_changed, b_changed)
+}
+
+fn to_ranges(tokens: &[Token], changed: &[bool]) -> Vec<ChangeRange> {
+    let mut ranges: Vec<ChangeRange> = vec![];
+    for (token, _) in tokens.iter().zip(changed).filter(|(_, changed)| **changed) {
+        match ranges.last_mut() {
+            Some(last) if last.end == token.start => last.end = token.end,
+            _ => ranges.push(ChangeRange { start: token.start, end: token.end }),
+        }
+    }
+    ranges
+}
+
+// Ranges for a removed/added pair, or None when the lines share nothing but
+// whitespace and highlighting every part of them would add nothing
+fn compare(old: &str, new: &str, mode: IntralineMode) -> Option<(Vec<ChangeRange>, Vec<ChangeRange>)> {
+    let (a, b) = (tokenize(old, mode), tokenize(new, mode));
+    let (a_changed, b_changed) = changed_tokens(&a, &b);
+
+    let shares_text = a
+        .iter()
+        .zip(&a_changed)
+        .any(|(token, changed)| !changed && !token.text.trim().is_empty());
+    if !shares_text {
+        return None;
+    }
+    Some((to_ranges(&a, &a_changed), to_ranges(&b, &b_changed)))
+}
+
+// Fill in `changes` on the lines of each hunk. Within a block of removed lines
+// followed by added lines, the nth removed line is paired with the nth added one.
+pub fn highlight(hunks: &mut [DiffHunk], mode: IntralineMode) {
+    for hunk in hunks {
+        let lines = &mut hunk.lines;
+        let mut i = 0;
+        while i < lines.len() {
+            let deletions = lines[i..].iter().take_while(|l| matches!(l.line_type, LineType::Deletion)).count();
+            let additions = lines[i + deletions..]
+                .iter()
+                .take_while(|l| matches!(l.line_type, LineType::Addition))
+                .count();
+
+            for k in 0..deletions.min(additions) {
+                let (old, new) = (i + k, i + deletions + k);
+                if let Some((old_ranges, new_ranges)) = compare(&lines[old].content, &lines[new].content, mode) {
+                    lines[old].changes = old_ranges;
+                    lines[new].changes = new_ranges;
+                }
+            }
+            i += (deletions + additions).max(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::types::DiffLine;
+
+    type Spans = Vec<(u32, u32)>;
+
+    fn spans(ranges: &[ChangeRange]) -> Spans {
+        ranges.iter().map(|r| (r.start, r.end)).collect()
+    }
+
+    fn changes(old: &str, new: &str, mode: IntralineMode) -> (Spans, Spans) {
+        let (old, new) = compare(old, new, mode).expect("lines share text");
+        (spans(&old), spans(&new))
+    }
+
+    fn line(content: &str, line_type: LineType) -> DiffLine {
+        DiffLine { content: content.to_string(), line_type, old_lineno: None, new_lineno: None, changes: vec![] }
+    }
+
+    #[test]
+    fn word_mode_marks_whole_words() {
+        let (old, new) = changes("let total = count + 1;", "let total = amount + 1;", IntralineMode::Word);
+        assert_eq!(old, vec![(12, 17)]);
+        assert_eq!(new, vec![(12, 18)]);
+    }
+
+    #[test]
+    fn char_mode_marks_only_the_characters_that_changed() {
+        let (old, new) = changes("let total = count + 1;", "let total = amount + 1;", IntralineMode::Char);
+        // "count" -> "amount": c→am, the "ount" in between is shared
+        assert_eq!(old, vec![(12, 13)]);
+        assert_eq!(new, vec![(12, 14)]);
+    }
+
+    #[test]
+    fn offsets_are_utf16_code_units() {
+        // "é" is one UTF-16 unit but two bytes; "😀" is two units and four bytes
+        let (old, new) = changes("café 😀 old", "café 😀 new", IntralineMode::Word);
+        assert_eq!(old, vec![(8, 11)]);
+        assert_eq!(new, vec![(8, 11)]);
+
+        let (old, new) = changes("a😀b", "a😁b", IntralineMode::Char);
+        assert_eq!(old, vec![(1, 3)]);
+        assert_eq!(new, vec![(1, 3)]);
+    }
+
+    #[test]
+    fn lines_sharing_only_whitespace_are_left_unmarked() {
+        assert!(compare("alpha beta", "gamma delta", IntralineMode::Word).is_none());
+    }
+
+    #[test]
+    fn long_lines_fall_back_to_marking_the_changed_middle() {
+        let old = format!("same {} end", "ab".repeat(600));
+        let new = format!("same {} end", "ba".repeat(600));
+        let (old_ranges, new_ranges) = changes(&old, &new, IntralineMode::Char);
+        assert_eq!(old_ranges, vec![(5, 1205)]);
+        assert_eq!(new_ranges, vec![(5, 1205)]);
+    }
+
+    #[test]
+    fn highlight_pairs_removed_and_added_lines_in_order() {
+        let mut hunks = vec![DiffHunk {
+            header: String::new(),
+            old_start: 1,
+            old_lines: 3,
+            new_start: 1,
+            new_lines: 3,
+            truncated: false,
+            lines: vec![
+                line("fn one()", LineType::Deletion),
+                line("fn two()", LineType::Deletion),
+                line("fn uno()", LineType::Addition),
+                line("fn dos()", LineType::Addition),
+                line("}", LineType::Context),
+                line("extra", LineType::Addition),
+            ],
+        }];
+        highlight(&mut hunks, IntralineMode::Word);
+
+        let lines = &hunks[0].lines;
+        assert_eq!(spans(&lines[0].changes), vec![(3, 6)]);
+        assert_eq!(spans(&lines[2].changes), vec![(3, 6)]);
+        assert_eq!(spans(&lines[1].changes), vec![(3, 6)]);
+        assert_eq!(spans(&lines[3].changes), vec![(3, 6)]);
+        // An addition with nothing to pair with stays whole
+        assert!(lines[5].changes.is_empty());
+    }
+}
@@ -0,0 +1,81 @@
+// Decoding file content for display. Git stores bytes, not text, so old
+// revisions can be in any encoding; this recognises the common ones.
+
+// Same heuristic as git: a NUL byte in the first 8000 bytes means binary
+const BINARY_CHECK_LEN: usize = 8000;
+
+pub struct DecodedText {
+    pub text: Option<String>, // None for binary content
+    pub encoding: &'static str,
+    pub is_binary: bool,
+}
+
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_CHECK_LEN)].contains(&0)
+}
+
+fn decode_utf16(bytes: &[u8], little_endian: bool) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| if little_endian { u16::from_le_bytes([pair[0], pair[1]]) } else { u16::from_be_bytes([pair[0], pair[1]]) })
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+// `bytes` may be a truncated prefix of the file; a multi-byte character cut
+// off at the end is dropped rather than treated as invalid
+pub fn decode(bytes: &[u8]) -> DecodedText {
+    let text = |text: String, encoding| DecodedText { text: Some(text), encoding, is_binary: false };
+
+    // Byte order marks first: UTF-16 text is full of NUL bytes
+    if let Some(rest) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return text(String::from_utf8_lossy(rest).to_string(), "utf-8-bom");
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        return text(decode_utf16(rest, true), "utf-16le");
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        return text(decode_utf16(rest, false), "utf-16be");
+    }
+
+    if looks_binary(bytes) {
+        return DecodedText { text: None, encoding: "binary", is_binary: true };
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(s) => text(s.to_string(), "utf-8"),
+        Err(e) if e.error_len().is_none() => {
+            text(String::from_utf8_lossy(&bytes[..e.valid_up_to()]).to_string(), "utf-8")
+        }
+        // Not UTF-8: fall back to Latin-1, which maps every byte to a character
+        Err(_) => text(bytes.iter().map(|&b| b as char).collect(), "iso-8859-1"),
+    }
+}
+
+// A single diff line: UTF-8 where valid, otherwise Latin-1
+pub fn decode_line(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => s.to_string(),
+        Err(_) => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+// Decode with an encoding named in .gitattributes (`working-tree-encoding`).
+// Returns None for encodings we can't convert.
+pub fn decode_with(bytes: &[u8], encoding: &str) -> Option<String> {
+    let name: String = encoding.to_ascii_lowercase().chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    let text = match name.as_str() {
+        "utf8" | "ascii" | "usascii" => Some(String::from_utf8_lossy(bytes).to_string()),
+        "utf16le" => Some(decode_utf16(bytes, true)),
+        "utf16be" => Some(decode_utf16(bytes, false)),
+        // Plain UTF-16 carries a byte order mark; big-endian without one
+        "utf16" => Some(match bytes {
+            [0xFF, 0xFE, rest @ ..] => decode_utf16(rest, true),
+            [0xFE, 0xFF, rest @ ..] => decode_utf16(rest, false),
+            _ => decode_utf16(bytes, false),
+        }),
+        "iso88591" | "latin1" => Some(bytes.iter().map(|&b| b as char).collect()),
+        _ => None,
+    };
+    text.map(|t| t.strip_prefix('\u{feff}').map(|s| s.to_string()).unwrap_or(t))
+}
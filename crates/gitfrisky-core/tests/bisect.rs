@@ -0,0 +1,158 @@
+mod common;
+
+use common::TestRepo;
+use gitfrisky_core::bisect;
+use gitfrisky_core::domain::types::BisectMark;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Eight commits writing their number to n.txt; the sixth introduces "bug".
+// Returns the oids, oldest first.
+fn history(repo: &TestRepo) -> Vec<String> {
+    (1..=8)
+        .map(|n| {
+            let content = if n >= 6 { format!("{}\nbug\n", n) } else { format!("{}\n", n) };
+            repo.commit_file("n.txt", &content, &format!("Commit {}", n))
+        })
+        .collect()
+}
+
+#[test]
+fn bisect_narrows_down_to_the_first_bad_commit() {
+    let repo = TestRepo::new();
+    let oids = history(&repo);
+
+    let mut status = bisect::bisect_start(&repo.path, None, &[oids[0].clone()], &[]).unwrap();
+    assert!(status.in_progress);
+    assert_eq!(status.bad.as_deref(), Some(oids[7].as_str()));
+    assert_eq!(status.good, vec![oids[0].clone()]);
+
+    while status.first_bad.is_none() {
+        let current = status.current.clone().expect("a commit to test");
+        assert_eq!(repo.head(), current);
+        let mark = if repo.read("n.txt").contains("bug") { BisectMark::Bad } else { BisectMark::Good };
+        status = bisect::bisect_mark(&repo.path, mark, None).unwrap();
+    }
+    assert_eq!(status.first_bad.unwrap().oid, oids[5]);
+
+    let status = bisect::bisect_status(&repo.path).unwrap();
+    assert!(status.in_progress);
+    assert_eq!(status.first_bad.unwrap().oid, oids[5]);
+}
+
+#[test]
+fn bisect_mark_can_skip_a_commit() {
+    let repo = TestRepo::new();
+    let oids = history(&repo);
+
+    let status = bisect::bisect_start(&repo.path, Some(&oids[7]), &[oids[0].clone()], &[]).unwrap();
+    let current = status.current.unwrap();
+    let status = bisect::bisect_mark(&repo.path, BisectMark::Skip, Some(&current)).unwrap();
+
+    assert_eq!(status.skipped, vec![current.clone()]);
+    assert_ne!(status.current, Some(current));
+}
+
+#[test]
+fn bisect_run_judges_each_commit_with_a_command() {
+    let repo = TestRepo::new();
+    let oids = history(&repo);
+    bisect::bisect_start(&repo.path, None, &[oids[0].clone()], &[]).unwrap();
+
+    let mut steps = vec![];
+    let status = bisect::bisect_run(&repo.path, "! grep -q bug n.txt", &AtomicBool::new(false), |step| steps.push((step.oid.clone(), step.mark))).unwrap();
+
+    assert_eq!(status.first_bad.unwrap().oid, oids[5]);
+    assert!(!steps.is_empty());
+    for (oid, mark) in &steps {
+        let index = oids.iter().position(|o| o == oid).unwrap();
+        assert_eq!(*mark, if index >= 5 { BisectMark::Bad } else { BisectMark::Good });
+    }
+}
+
+#[test]
+fn cancelling_bisect_run_kills_the_script_and_keeps_the_bisect() {
+    let repo = TestRepo::new();
+    let oids = history(&repo);
+    bisect::bisect_start(&repo.path, None, &[oids[0].clone()], &[]).unwrap();
+    let current = bisect::bisect_status(&repo.path).unwrap().current;
+
+    let cancelled = AtomicBool::new(false);
+    let started = Instant::now();
+    let result = thread::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(Duration::from_millis(200));
+            cancelled.store(true, Ordering::Relaxed);
+        });
+        bisect::bisect_run(&repo.path, "sleep 30", &cancelled, |_| {})
+    });
+
+    assert!(result.is_err());
+    assert!(started.elapsed() < Duration::from_secs(10));
+    assert_eq!(bisect::bisect_status(&repo.path).unwrap().current, current);
+}
+
+#[test]
+fn bisect_reset_returns_to_the_starting_branch() {
+    let repo = TestRepo::new();
+    let oids = history(&repo);
+    bisect::bisect_start(&repo.path, None, &[oids[0].clone()], &[]).unwrap();
+    bisect::bisect_mark(&repo.path, BisectMark::Good, None).unwrap();
+
+    bisect::bisect_reset(&repo.path).unwrap();
+
+    assert_eq!(repo.head_name().as_deref(), Some("refs/heads/main"));
+    assert_eq!(repo.head(), oids[7]);
+    assert!(!bisect::bisect_status(&repo.path).unwrap().in_progress);
+    assert!(repo.repo.references_glob("refs/bisect/*").unwrap().next().is_none());
+}
+
+#[test]
+fn bisect_commands_need_a_bisect_in_progress() {
+    let repo = TestRepo::new();
+    let oids = history(&repo);
+
+    assert!(bisect::bisect_mark(&repo.path, BisectMark::Good, None).is_err());
+    assert!(bisect::bisect_run(&repo.path, "true", &AtomicBool::new(false), |_| {}).is_err());
+    assert!(bisect::bisect_reset(&repo.path).is_err());
+
+    bisect::bisect_start(&repo.path, None, &[oids[0].clone()], &[]).unwrap();
+    assert!(bisect::bisect_start(&repo.path, None, &[oids[0].clone()], &[]).is_err());
+}
+
+// Nothing of a bisect is left behind when it can't start
+fn assert_no_bisect(repo: &TestRepo) {
+    assert!(!bisect::bisect_status(&repo.path).unwrap().in_progress);
+    assert_eq!(repo.repo.state(), git2::RepositoryState::Clean);
+    assert_eq!(repo.repo.references_glob("refs/bisect/*").unwrap().count(), 0);
+}
+
+#[test]
+fn bisect_start_refuses_a_dirty_working_tree() {
+    let repo = TestRepo::new();
+    let oids = history(&repo);
+    repo.write("n.txt", "edited\n");
+
+    assert!(bisect::bisect_start(&repo.path, None, &[oids[0].clone()], &[]).is_err());
+    assert_no_bisect(&repo);
+    assert_eq!(repo.read("n.txt"), "edited\n");
+}
+
+#[test]
+fn bisect_start_cleans_up_when_the_first_checkout_fails() {
+    let repo = TestRepo::new();
+    let first = repo.commit_file("n.txt", "1\n", "Commit 1");
+    repo.write("x.txt", "tracked\n");
+    for n in 2..=6 {
+        repo.commit_file("n.txt", &format!("{}\n", n), &format!("Commit {}", n));
+    }
+    repo.remove("x.txt");
+    repo.commit_all("Remove x.txt");
+    // Untracked, and in the way of every commit bisect could test
+    repo.write("x.txt", "untracked\n");
+
+    assert!(bisect::bisect_start(&repo.path, None, &[first], &[]).is_err());
+    assert_no_bisect(&repo);
+    assert_eq!(repo.read("x.txt"), "untracked\n");
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::TestRepo;
+use gitfrisky_core::blame;
+use gitfrisky_core::domain::types::BlameOptions;
+
+// Which commit each line of the result came from
+fn line_owners(result: &gitfrisky_core::domain::types::BlameResult) -> Vec<String> {
+    let mut owners = vec![];
+    for range in &result.ranges {
+        for _ in 0..range.line_count {
+            owners.push(range.oid.clone());
+        }
+    }
+    owners
+}
+
+#[test]
+fn blame_attributes_each_line_to_its_commit() {
+    let repo = TestRepo::new();
+    let first = repo.commit_file("a.txt", "one\ntwo\nthree\n", "First");
+    let second = repo.commit_file("a.txt", "one\n2\nthree\n", "Second");
+
+    let result = blame::blame(&repo.path, "a.txt", None, None).unwrap();
+    assert_eq!(result.oid, second);
+    assert_eq!(result.lines, vec!["one", "2", "three"]);
+    assert_eq!(line_owners(&result), vec![first.clone(), second.clone(), first.clone()]);
+    assert_eq!(result.ranges[1].summary, "Second");
+
+    let older = blame::blame(&repo.path, "a.txt", Some("HEAD~1"), None).unwrap();
+    assert_eq!(older.lines, vec!["one", "two", "three"]);
+    assert_eq!(line_owners(&older), vec![first.clone(); 3]);
+}
+
+#[test]
+fn blame_can_look_past_ignored_commits() {
+    let repo = TestRepo::new();
+    let first = repo.commit_file("a.txt", "one\ntwo\n", "First");
+    let reformat = repo.commit_file("a.txt", "one\ntwo \n", "Reformat");
+
+    let options = BlameOptions { ignore_revs: vec![reformat], ..Default::default() };
+    let result = blame::blame(&repo.path, "a.txt", None, Some(options)).unwrap();
+    assert_eq!(line_owners(&result), vec![first.clone(), first]);
+}
+
+#[test]
+fn blame_parent_follows_a_rename() {
+    let repo = TestRepo::new();
+    let first = repo.commit_file("old.txt", "one\ntwo\nthree\nfour\n", "First");
+    repo.remove("old.txt");
+    repo.write("new.txt", "one\ntwo\nthree\nfour\nfive\n");
+    let rename = repo.commit_all("Rename and extend");
+
+    let result = blame::blame_parent(&repo.path, &rename, "new.txt", None).unwrap();
+    assert_eq!(result.path, "old.txt");
+    assert_eq!(result.oid, first);
+    assert_eq!(result.lines.len(), 4);
+
+    assert!(blame::blame_parent(&repo.path, &first, "old.txt", None).is_err());
+}
+
+#[test]
+fn blame_parent_refuses_a_file_the_commit_added() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "a\n", "First");
+    let added = repo.commit_file("b.txt", "b\n", "Add b");
+
+    let error = blame::blame_parent(&repo.path, &added, "b.txt", None).unwrap_err();
+    assert!(error.contains("did not exist"));
+}
+
+#[test]
+fn blame_parent_of_a_merge_uses_the_first_parent() {
+    let repo = TestRepo::new();
+    let base = repo.commit_file("a.txt", "one\ntwo\n", "Base");
+    repo.branch("topic", &base);
+    let ours = repo.commit_file("a.txt", "one\nours\n", "Ours");
+    repo.checkout("topic");
+    repo.commit_file("b.txt", "b\n", "Theirs");
+    repo.checkout("main");
+    repo.merge("topic");
+    repo.add_all();
+
+    let mut index = repo.repo.index().unwrap();
+    let tree = repo.repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = git2::Signature::now("Test User", "test@example.com").unwrap();
+    let parents = [ours.as_str(), "topic"].map(|rev| repo.repo.revparse_single(rev).unwrap().peel_to_commit().unwrap());
+    let merge = repo.repo.commit(Some("HEAD"), &sig, &sig, "Merge topic", &tree, &[&parents[0], &parents[1]]).unwrap().to_string();
+
+    let result = blame::blame_parent(&repo.path, &merge, "a.txt", None).unwrap();
+    assert_eq!(result.oid, ours);
+    assert_eq!(result.lines, vec!["one", "ours"]);
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use std::process::{Command, Output};
+
+use common::TestRepo;
+
+fn gitfrisky(repo: &TestRepo, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_gitfrisky")).arg("-C").arg(&repo.path).args(args).output().unwrap()
+}
+
+fn stdout(output: Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn status_prints_short_format() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("a.txt", "two\n");
+    repo.write("b.txt", "b\n");
+
+    assert_eq!(stdout(gitfrisky(&repo, &["status"])), " M a.txt\n?? b.txt\n");
+}
+
+#[test]
+fn status_json_is_the_app_payload() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("a.txt", "two\n");
+
+    let payload: serde_json::Value = serde_json::from_str(&stdout(gitfrisky(&repo, &["--json", "status"]))).unwrap();
+    assert_eq!(payload["staged"], serde_json::json!([]));
+    assert_eq!(payload["unstaged"][0]["path"], "a.txt");
+}
+
+#[test]
+fn log_lists_commits_newest_first() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "First");
+    let second = repo.commit_file("a.txt", "two\n", "Second");
+
+    let lines: Vec<_> = stdout(gitfrisky(&repo, &["log"])).lines().map(String::from).collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], format!("{} Second", &second[..7]));
+    assert!(lines[1].ends_with(" First"));
+
+    let limited: serde_json::Value = serde_json::from_str(&stdout(gitfrisky(&repo, &["log", "-n", "1", "--json"]))).unwrap();
+    assert_eq!(limited.as_array().unwrap().len(), 1);
+    assert_eq!(limited[0]["oid"], second);
+}
+
+#[test]
+fn usage_errors_exit_with_2() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+
+    assert_eq!(gitfrisky(&repo, &["frobnicate"]).status.code(), Some(2));
+    assert_eq!(gitfrisky(&repo, &["log", "-n", "many"]).status.code(), Some(2));
+}
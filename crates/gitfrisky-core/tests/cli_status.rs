@@ -0,0 +1,126 @@
+mod common;
+
+use common::{diverged, strings, TestRepo};
+use gitfrisky_core::domain::types::StatusPayload;
+use gitfrisky_core::repo;
+use git2::Oid;
+use std::path::Path;
+
+// (list, path, status) of every change, in a form both engines can be compared by
+type Entries = Vec<(&'static str, String, String)>;
+
+fn entries(payload: &StatusPayload) -> Entries {
+    let mut entries: Vec<_> = [("staged", &payload.staged), ("unstaged", &payload.unstaged)]
+        .into_iter()
+        .flat_map(|(list, changes)| changes.iter().map(move |c| (list, c.path.clone(), format!("{:?}", c.status))))
+        .collect();
+    entries.sort();
+    entries
+}
+
+// Status from libgit2, then from `git status` once the untracked cache is on
+fn both_engines(repo: &TestRepo, paths: Option<&[String]>) -> (Entries, Entries) {
+    let scan = || match paths {
+        Some(paths) => repo::status_paths(&repo.path, paths).unwrap(),
+        None => repo::status(&repo.path).unwrap(),
+    };
+    let mut config = repo.repo.config().unwrap();
+    let _ = config.remove("core.untrackedCache");
+    let libgit2 = entries(&scan());
+    config.set_bool("core.untrackedCache", true).unwrap();
+    let git = entries(&scan());
+
+    // Only git writes the untracked cache extension, so this proves it ran
+    let index = std::fs::read(repo.repo.path().join("index")).unwrap();
+    assert!(index.windows(4).any(|w| w == b"UNTR"), "git status was not used");
+    (libgit2, git)
+}
+
+fn mixed_changes(repo: &TestRepo) {
+    repo.write("modified.txt", "one\n");
+    repo.write("deleted.txt", "gone\n");
+    repo.write("staged.txt", "before\n");
+    repo.write("dir/nested.txt", "nested\n");
+    repo.commit_all("Initial");
+
+    repo.write("modified.txt", "two\n");
+    repo.remove("deleted.txt");
+    repo.write("staged.txt", "after\n");
+    repo.write("added.txt", "new\n");
+    repo::stage(&repo.path, &strings(&["staged.txt", "added.txt"])).unwrap();
+    repo.write("staged.txt", "after that\n");
+    repo.write("dir/nested.txt", "changed\n");
+    repo.write("dir/untracked/deep.txt", "?\n");
+    repo.write("name with spaces.txt", "?\n");
+}
+
+#[test]
+fn git_status_matches_libgit2_for_a_mix_of_changes() {
+    let repo = TestRepo::new();
+    mixed_changes(&repo);
+
+    let (libgit2, git) = both_engines(&repo, None);
+    assert_eq!(libgit2.len(), 8);
+    assert_eq!(git, libgit2);
+}
+
+#[test]
+fn git_status_matches_libgit2_for_selected_paths() {
+    let repo = TestRepo::new();
+    mixed_changes(&repo);
+
+    let (libgit2, git) = both_engines(&repo, Some(&strings(&["dir", "staged.txt"])));
+    assert!(libgit2.iter().all(|(_, path, _)| path.starts_with("dir/") || path == "staged.txt"));
+    assert_eq!(git, libgit2);
+}
+
+#[test]
+fn git_status_matches_libgit2_during_a_conflict() {
+    let repo = TestRepo::new();
+    diverged(&repo);
+    repo.merge("topic");
+
+    let (libgit2, git) = both_engines(&repo, None);
+    assert!(libgit2.iter().any(|(_, path, status)| path == "a.txt" && status == "C"));
+    assert_eq!(git, libgit2);
+}
+
+#[test]
+fn git_status_matches_libgit2_for_submodules() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+
+    // A submodule at sub/, checked out and then edited
+    let sub = TestRepo::new();
+    let sub_head = sub.commit_file("s.txt", "one\n", "Sub");
+    std::fs::rename(&sub.path, repo.file("sub")).unwrap();
+    repo.write(".gitmodules", "[submodule \"sub\"]\n\tpath = sub\n\turl = ./sub\n");
+    let mut index = repo.repo.index().unwrap();
+    index.add_path(Path::new(".gitmodules")).unwrap();
+    let mut entry = index.get_path(Path::new(".gitmodules"), 0).unwrap();
+    entry.path = b"sub".to_vec();
+    entry.mode = 0o160000;
+    entry.id = Oid::from_str(&sub_head).unwrap();
+    index.add(&entry).unwrap();
+    index.write().unwrap();
+    repo.commit_all("Add submodule");
+    repo.write("sub/s.txt", "edited\n");
+
+    let (libgit2, git) = both_engines(&repo, None);
+    assert!(libgit2.iter().any(|(_, path, _)| path == "sub"));
+    assert_eq!(git, libgit2);
+
+    // Both honor diff.ignoreSubmodules and the submodule's own ignore setting
+    let mut config = repo.repo.config().unwrap();
+    config.set_str("diff.ignoreSubmodules", "all").unwrap();
+    let (libgit2, git) = both_engines(&repo, None);
+    assert!(libgit2.is_empty());
+    assert_eq!(git, libgit2);
+
+    config.remove("diff.ignoreSubmodules").unwrap();
+    repo.write(".gitmodules", "[submodule \"sub\"]\n\tpath = sub\n\turl = ./sub\n\tignore = dirty\n");
+    repo.commit_all("Ignore dirty");
+    let (libgit2, git) = both_engines(&repo, None);
+    assert!(libgit2.is_empty());
+    assert_eq!(git, libgit2);
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::{diverged, strings, TestRepo};
+use gitfrisky_core::domain::types::RepoState;
+use gitfrisky_core::repo;
+use git2::{Oid, Signature, Time};
+
+fn state(repo: &TestRepo) -> RepoState {
+    repo::open_repo(&repo.path).unwrap().state
+}
+
+#[test]
+fn commit_concludes_a_merge() {
+    let repo = TestRepo::new();
+    let (_, ours, theirs) = diverged(&repo);
+    repo.merge("topic");
+    assert!(matches!(state(&repo), RepoState::Merge));
+
+    let message = repo::merge_message(&repo.path).unwrap().unwrap();
+    assert!(message.contains("topic"));
+
+    repo.write("a.txt", "one\nboth\nthree\n");
+    repo::stage(&repo.path, &strings(&["a.txt"])).unwrap();
+    let commit = repo::commit(&repo.path, &message).unwrap();
+
+    assert_eq!(commit.parents, vec![ours, theirs]);
+    assert!(matches!(state(&repo), RepoState::Clean));
+    assert_eq!(repo::merge_message(&repo.path).unwrap(), None);
+}
+
+#[test]
+fn commit_refuses_unresolved_conflicts() {
+    let repo = TestRepo::new();
+    diverged(&repo);
+    repo.merge("topic");
+
+    assert!(repo::commit(&repo.path, "Merge").is_err());
+    assert!(matches!(state(&repo), RepoState::Merge));
+}
+
+#[test]
+fn empty_message_falls_back_to_the_prepared_one() {
+    let repo = TestRepo::new();
+    diverged(&repo);
+    repo.merge("topic");
+    repo.write("a.txt", "one\nboth\nthree\n");
+    repo::stage(&repo.path, &strings(&["a.txt"])).unwrap();
+
+    let commit = repo::commit(&repo.path, "").unwrap();
+    assert!(commit.summary.contains("topic"));
+}
+
+#[test]
+fn commit_concludes_a_cherry_pick_keeping_the_original_author() {
+    let repo = TestRepo::new();
+    diverged(&repo);
+
+    // A commit on topic by someone else that conflicts with main
+    repo.checkout("topic");
+    repo.write("a.txt", "one\nsomeone\nthree\n");
+    repo.add_all();
+    let mut index = repo.repo.index().unwrap();
+    let tree = repo.repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let author = Signature::new("Someone Else", "else@example.com", &Time::new(1_600_000_000, 0)).unwrap();
+    let parent = repo.repo.head().unwrap().peel_to_commit().unwrap();
+    let picked = repo.repo.commit(Some("HEAD"), &author, &author, "Their change", &tree, &[&parent]).unwrap();
+    repo.checkout("main");
+
+    repo.repo.cherrypick(&repo.repo.find_commit(picked).unwrap(), None).unwrap();
+    assert!(matches!(state(&repo), RepoState::CherryPick));
+
+    repo.write("a.txt", "one\nresolved\nthree\n");
+    repo::stage(&repo.path, &strings(&["a.txt"])).unwrap();
+    let commit = repo::commit(&repo.path, "Their change").unwrap();
+
+    assert_eq!(commit.author, "Someone Else");
+    assert!(matches!(state(&repo), RepoState::Clean));
+    let created = repo.repo.find_commit(Oid::from_str(&commit.oid).unwrap()).unwrap();
+    assert_eq!(created.committer().name(), Some("Test User"));
+    assert_eq!(created.parent_count(), 1);
+}
@@ -0,0 +1,137 @@
+#![allow(dead_code)]
+
+use git2::{Oid, Repository, Signature, Time};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI64, Ordering};
+use tempfile::TempDir;
+
+// Commits get increasing times so history order doesn't depend on how fast the test runs
+static CLOCK: AtomicI64 = AtomicI64::new(1_700_000_000);
+
+// A throwaway repository with an identity configured, deleted when dropped
+pub struct TestRepo {
+    _dir: TempDir,
+    pub path: String,
+    pub repo: Repository,
+}
+
+impl TestRepo {
+    pub fn new() -> Self {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let repo = Repository::init(dir.path()).expect("init repository");
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test User").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        // Make sure HEAD is `main` whatever init.defaultBranch says
+        repo.set_head("refs/heads/main").unwrap();
+
+        let path = dir.path().to_str().unwrap().to_string();
+        Self { _dir: dir, path, repo }
+    }
+
+    pub fn file(&self, rel_path: &str) -> PathBuf {
+        PathBuf::from(&self.path).join(rel_path)
+    }
+
+    pub fn write(&self, rel_path: &str, content: &str) {
+        let path = self.file(rel_path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    pub fn read(&self, rel_path: &str) -> String {
+        fs::read_to_string(self.file(rel_path)).unwrap()
+    }
+
+    pub fn remove(&self, rel_path: &str) {
+        fs::remove_file(self.file(rel_path)).unwrap();
+    }
+
+    // Stage everything in the working tree, like `git add -A`
+    pub fn add_all(&self) {
+        let mut index = self.repo.index().unwrap();
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+        index.update_all(["*"], None).unwrap();
+        index.write().unwrap();
+    }
+
+    // Stage everything and commit it on HEAD, returning the new commit's oid
+    pub fn commit_all(&self, message: &str) -> String {
+        self.add_all();
+        let mut index = self.repo.index().unwrap();
+        let tree = self.repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let time = Time::new(CLOCK.fetch_add(60, Ordering::Relaxed), 0);
+        let sig = Signature::new("Test User", "test@example.com", &time).unwrap();
+        let parent = self.repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<_> = parent.iter().collect();
+        self.repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap().to_string()
+    }
+
+    // Write a file and commit it in one go
+    pub fn commit_file(&self, rel_path: &str, content: &str, message: &str) -> String {
+        self.write(rel_path, content);
+        self.commit_all(message)
+    }
+
+    pub fn branch(&self, name: &str, oid: &str) {
+        let commit = self.repo.find_commit(Oid::from_str(oid).unwrap()).unwrap();
+        self.repo.branch(name, &commit, true).unwrap();
+    }
+
+    // Switch HEAD and the working tree to an existing branch
+    pub fn checkout(&self, name: &str) {
+        let refname = format!("refs/heads/{}", name);
+        let object = self.repo.revparse_single(&refname).unwrap();
+        self.repo.checkout_tree(&object, Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+        self.repo.set_head(&refname).unwrap();
+    }
+
+    pub fn head(&self) -> String {
+        self.repo.head().unwrap().peel_to_commit().unwrap().id().to_string()
+    }
+
+    pub fn head_name(&self) -> Option<String> {
+        let head = self.repo.find_reference("HEAD").unwrap();
+        head.symbolic_target().map(|t| t.to_string())
+    }
+
+    pub fn summary(&self, oid: &str) -> String {
+        let commit = self.repo.find_commit(Oid::from_str(oid).unwrap()).unwrap();
+        commit.summary().unwrap_or("").to_string()
+    }
+
+    // Oid of a file in the index, if it's there at stage 0
+    pub fn index_blob(&self, rel_path: &str) -> Option<Oid> {
+        let mut index = self.repo.index().unwrap();
+        index.read(true).unwrap();
+        index.get_path(std::path::Path::new(rel_path), 0).map(|e| e.id)
+    }
+
+    pub fn blob(&self, content: &str) -> Oid {
+        self.repo.blob(content.as_bytes()).unwrap()
+    }
+
+    // Start merging a branch into HEAD, leaving any conflicts for the test to resolve
+    pub fn merge(&self, branch: &str) {
+        let reference = self.repo.find_reference(&format!("refs/heads/{}", branch)).unwrap();
+        let annotated = self.repo.reference_to_annotated_commit(&reference).unwrap();
+        self.repo.merge(&[&annotated], None, None).unwrap();
+    }
+}
+
+pub fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+// `main` and `topic` both edit line 2 of a.txt, starting from a shared base.
+// Returns (base, main tip, topic tip) with `main` checked out.
+pub fn diverged(repo: &TestRepo) -> (String, String, String) {
+    let base = repo.commit_file("a.txt", "one\ntwo\nthree\n", "Base");
+    repo.branch("topic", &base);
+    let ours = repo.commit_file("a.txt", "one\nours\nthree\n", "Ours");
+    repo.checkout("topic");
+    let theirs = repo.commit_file("a.txt", "one\ntheirs\nthree\n", "Theirs");
+    repo.checkout("main");
+    (base, ours, theirs)
+}
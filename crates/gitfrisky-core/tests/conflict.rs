@@ -0,0 +1,128 @@
+mod common;
+
+use common::{diverged, strings, TestRepo};
+use gitfrisky_core::conflict;
+use gitfrisky_core::domain::types::{ConflictChoice, ConflictRegion, ConflictResolution, ConflictStyle};
+
+// A merge of `topic` into `main` that conflicts on line 2 of a.txt
+fn conflicted() -> TestRepo {
+    let repo = TestRepo::new();
+    diverged(&repo);
+    repo.merge("topic");
+    repo
+}
+
+fn conflicts(regions: &[ConflictRegion]) -> Vec<(&str, Option<&str>, &str)> {
+    regions
+        .iter()
+        .filter_map(|r| match r {
+            ConflictRegion::Conflict { ours, base, theirs, .. } => Some((ours.as_str(), base.as_deref(), theirs.as_str())),
+            ConflictRegion::Resolved { .. } => None,
+        })
+        .collect()
+}
+
+#[test]
+fn list_conflicts_names_conflicted_files() {
+    let repo = conflicted();
+    assert_eq!(conflict::list_conflicts(&repo.path).unwrap(), vec!["a.txt"]);
+}
+
+#[test]
+fn list_conflicts_is_empty_without_a_merge() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    assert!(conflict::list_conflicts(&repo.path).unwrap().is_empty());
+}
+
+#[test]
+fn get_conflict_returns_all_three_sides_and_regions() {
+    let repo = conflicted();
+
+    let file = conflict::get_conflict(&repo.path, "a.txt", Some(ConflictStyle::Diff3)).unwrap();
+    assert_eq!(file.base.unwrap().content.as_deref(), Some("one\ntwo\nthree\n"));
+    assert_eq!(file.ours.unwrap().content.as_deref(), Some("one\nours\nthree\n"));
+    assert_eq!(file.theirs.unwrap().content.as_deref(), Some("one\ntheirs\nthree\n"));
+    assert_eq!(conflicts(&file.regions), vec![("ours\n", Some("two\n"), "theirs\n")]);
+
+    let merge = conflict::get_conflict(&repo.path, "a.txt", Some(ConflictStyle::Merge)).unwrap();
+    assert_eq!(conflicts(&merge.regions), vec![("ours\n", None, "theirs\n")]);
+}
+
+#[test]
+fn resolve_conflict_takes_one_side_for_the_whole_file() {
+    let repo = conflicted();
+
+    let resolution = ConflictResolution::File { choice: ConflictChoice::Theirs };
+    conflict::resolve_conflict(&repo.path, "a.txt", resolution).unwrap();
+
+    assert_eq!(repo.read("a.txt"), "one\ntheirs\nthree\n");
+    assert!(conflict::list_conflicts(&repo.path).unwrap().is_empty());
+    assert_eq!(repo.index_blob("a.txt"), Some(repo.blob("one\ntheirs\nthree\n")));
+}
+
+#[test]
+fn resolve_conflict_applies_a_choice_per_region() {
+    let repo = conflicted();
+
+    let resolution = ConflictResolution::Regions { choices: vec![ConflictChoice::Union], style: None };
+    conflict::resolve_conflict(&repo.path, "a.txt", resolution).unwrap();
+
+    assert_eq!(repo.read("a.txt"), "one\nours\ntheirs\nthree\n");
+    assert!(conflict::list_conflicts(&repo.path).unwrap().is_empty());
+}
+
+#[test]
+fn resolve_conflict_applies_choices_to_the_zdiff3_regions() {
+    let repo = TestRepo::new();
+    let base = repo.commit_file("a.txt", "one\ntwo\nthree\n", "Base");
+    repo.branch("topic", &base);
+    repo.commit_file("a.txt", "one\nsame\nours\nalike\nthree\n", "Ours");
+    repo.checkout("topic");
+    repo.commit_file("a.txt", "one\nsame\ntheirs\nalike\nthree\n", "Theirs");
+    repo.checkout("main");
+    repo.merge("topic");
+
+    let file = conflict::get_conflict(&repo.path, "a.txt", Some(ConflictStyle::Zdiff3)).unwrap();
+    assert_eq!(conflicts(&file.regions), vec![("ours\n", Some("two\n"), "theirs\n")]);
+
+    let resolution = ConflictResolution::Regions { choices: vec![ConflictChoice::Union], style: Some(ConflictStyle::Zdiff3) };
+    conflict::resolve_conflict(&repo.path, "a.txt", resolution).unwrap();
+    assert_eq!(repo.read("a.txt"), "one\nsame\nours\ntheirs\nalike\nthree\n");
+}
+
+#[test]
+fn mark_resolved_stages_the_edited_file() {
+    let repo = conflicted();
+    repo.write("a.txt", "one\nby hand\nthree\n");
+
+    conflict::mark_resolved(&repo.path, &strings(&["a.txt"])).unwrap();
+
+    assert!(conflict::list_conflicts(&repo.path).unwrap().is_empty());
+    assert_eq!(repo.index_blob("a.txt"), Some(repo.blob("one\nby hand\nthree\n")));
+}
+
+#[test]
+fn delete_modify_conflicts_resolve_by_keeping_or_removing_the_file() {
+    let repo = TestRepo::new();
+    let base = repo.commit_file("a.txt", "one\n", "Base");
+    repo.branch("topic", &base);
+    repo.commit_file("a.txt", "two\n", "Modify");
+    repo.checkout("topic");
+    repo.remove("a.txt");
+    repo.commit_all("Delete");
+    repo.checkout("main");
+    repo.merge("topic");
+
+    let file = conflict::get_conflict(&repo.path, "a.txt", None).unwrap();
+    assert!(file.theirs.is_none());
+    assert!(file.regions.is_empty());
+    let regions = ConflictResolution::Regions { choices: vec![ConflictChoice::Ours], style: None };
+    assert!(conflict::resolve_conflict(&repo.path, "a.txt", regions).is_err());
+
+    let resolution = ConflictResolution::File { choice: ConflictChoice::Theirs };
+    conflict::resolve_conflict(&repo.path, "a.txt", resolution).unwrap();
+    assert!(!repo.file("a.txt").exists());
+    assert!(conflict::list_conflicts(&repo.path).unwrap().is_empty());
+    assert_eq!(repo.index_blob("a.txt"), None);
+}
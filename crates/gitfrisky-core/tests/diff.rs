@@ -0,0 +1,251 @@
+mod common;
+
+use common::{strings, TestRepo};
+use gitfrisky_core::domain::types::{DiffAlgorithm, DiffViewOptions, LineType, RenameDetection};
+use gitfrisky_core::repo;
+
+fn utf16le(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect()
+}
+
+// Non-context lines of the first hunk, without line endings
+fn changes(diff: &gitfrisky_core::domain::types::FileDiff) -> Vec<(char, String)> {
+    diff.hunks[0]
+        .lines
+        .iter()
+        .filter_map(|l| match l.line_type {
+            LineType::Addition => Some(('+', l.content.trim_end().to_string())),
+            LineType::Deletion => Some(('-', l.content.trim_end().to_string())),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn get_diff_truncates_at_max_lines() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "", "Initial");
+    repo.write("a.txt", &"line\n".repeat(50));
+
+    let diff = repo::get_diff(&repo.path, "a.txt", false, Some(10), None, None).unwrap();
+    assert!(diff.truncated);
+    assert_eq!(diff.total_lines, 50);
+    assert_eq!(diff.hunks.iter().map(|h| h.lines.len()).sum::<usize>(), 10);
+
+    // Asking again with a higher limit loads the rest
+    let full = repo::get_diff(&repo.path, "a.txt", false, Some(100), None, None).unwrap();
+    assert!(!full.truncated);
+    assert_eq!(full.hunks.iter().map(|h| h.lines.len()).sum::<usize>(), 50);
+}
+
+#[test]
+fn a_hunk_cut_short_by_the_cap_cannot_be_staged() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "", "Initial");
+    repo.write("a.txt", &"line\n".repeat(50));
+
+    let diff = repo::get_diff(&repo.path, "a.txt", false, Some(10), None, None).unwrap();
+    assert!(diff.hunks[0].truncated);
+    assert!(repo::stage_hunk(&repo.path, "a.txt", &diff.hunks[0], None).is_err());
+    assert_eq!(repo.index_blob("a.txt"), Some(repo.blob("")));
+
+    let full = repo::get_diff(&repo.path, "a.txt", false, Some(100), None, None).unwrap();
+    assert!(!full.hunks[0].truncated);
+    repo::stage_hunk(&repo.path, "a.txt", &full.hunks[0], None).unwrap();
+    assert_eq!(repo.index_blob("a.txt"), Some(repo.blob(&"line\n".repeat(50))));
+}
+
+#[test]
+fn get_diff_flags_binary_files() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.bin", "\0\x01\x02", "Initial");
+    repo.write("a.bin", "\0\x01\x03\x04");
+
+    let diff = repo::get_diff(&repo.path, "a.bin", false, None, None, None).unwrap();
+    assert!(diff.is_binary);
+    assert!(diff.hunks.is_empty());
+    assert_eq!((diff.old_size, diff.new_size), (Some(3), Some(4)));
+    assert_eq!(diff.old_oid, Some(repo.blob("\0\x01\x02").to_string()));
+    assert_eq!(diff.new_oid, Some(repo.blob("\0\x01\x03\x04").to_string()));
+}
+
+#[test]
+fn untracked_binary_files_report_size_and_hash() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "a\n", "Initial");
+    repo.write("new.bin", "\0\x01\x02");
+
+    let diff = repo::get_diff(&repo.path, "new.bin", false, None, None, None).unwrap();
+    assert!(diff.is_binary);
+    assert_eq!(diff.new_size, Some(3));
+    assert_eq!(diff.new_oid, Some(repo.blob("\0\x01\x02").to_string()));
+}
+
+#[test]
+fn gitattributes_can_mark_text_as_binary() {
+    let repo = TestRepo::new();
+    repo.write(".gitattributes", "*.lock -diff\n");
+    repo.commit_file("deps.lock", "one\n", "Initial");
+    repo.write("deps.lock", "two\n");
+
+    let diff = repo::get_diff(&repo.path, "deps.lock", false, None, None, None).unwrap();
+    assert!(diff.is_binary);
+    assert!(diff.hunks.is_empty());
+}
+
+#[test]
+fn latin1_files_are_decoded_instead_of_mangled() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "a\n", "Initial");
+    std::fs::write(repo.file("new.txt"), b"caf\xe9\n").unwrap();
+
+    let diff = repo::get_diff(&repo.path, "new.txt", false, None, None, None).unwrap();
+    assert!(!diff.is_binary);
+    assert_eq!(diff.encoding, "iso-8859-1");
+    assert_eq!(changes(&diff), vec![('+', "café".to_string())]);
+}
+
+#[test]
+fn utf16_files_with_a_byte_order_mark_are_decoded() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "a\n", "Initial");
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(utf16le("héllo\nwörld\n"));
+    std::fs::write(repo.file("new.txt"), bytes).unwrap();
+
+    let diff = repo::get_diff(&repo.path, "new.txt", false, None, None, None).unwrap();
+    assert!(!diff.is_binary);
+    assert_eq!(diff.encoding, "utf-16le");
+    assert_eq!(changes(&diff), vec![('+', "héllo".to_string()), ('+', "wörld".to_string())]);
+}
+
+#[test]
+fn working_tree_encoding_is_honored_for_tracked_files() {
+    let repo = TestRepo::new();
+    repo.write(".gitattributes", "a.txt working-tree-encoding=UTF-16LE\n");
+    // The repository stores UTF-8; only the working tree copy is UTF-16
+    repo.commit_file("a.txt", "one\ntwo\n", "Initial");
+    std::fs::write(repo.file("a.txt"), utf16le("one\nzwei\n")).unwrap();
+
+    let diff = repo::get_diff(&repo.path, "a.txt", false, None, None, None).unwrap();
+    assert!(!diff.is_binary);
+    assert_eq!(diff.encoding, "UTF-16LE");
+    assert_eq!(changes(&diff), vec![('-', "two".to_string()), ('+', "zwei".to_string())]);
+}
+
+fn numbered(lines: usize) -> Vec<String> {
+    (1..=lines).map(|n| format!("line {}", n)).collect()
+}
+
+fn text(lines: &[String]) -> String {
+    format!("{}\n", lines.join("\n"))
+}
+
+fn unstaged(repo: &TestRepo, view: DiffViewOptions) -> gitfrisky_core::domain::types::FileDiff {
+    repo::get_diff(&repo.path, "a.txt", false, None, None, Some(view)).unwrap()
+}
+
+#[test]
+fn whitespace_options_hide_whitespace_only_changes() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "a\n  b\nc\n", "Initial");
+
+    repo.write("a.txt", "a\nb\nc\n");
+    assert_eq!(unstaged(&repo, DiffViewOptions::default()).hunks.len(), 1);
+    assert!(unstaged(&repo, DiffViewOptions { ignore_all_space: true, ..Default::default() }).hunks.is_empty());
+
+    repo.write("a.txt", "a\n    b\nc\n");
+    assert!(unstaged(&repo, DiffViewOptions { ignore_space_change: true, ..Default::default() }).hunks.is_empty());
+
+    repo.write("a.txt", "a\n  b  \nc\n");
+    assert!(unstaged(&repo, DiffViewOptions { ignore_space_at_eol: true, ..Default::default() }).hunks.is_empty());
+
+    repo.write("a.txt", "a\n  b\n\nc\n");
+    assert!(unstaged(&repo, DiffViewOptions { ignore_blank_lines: true, ..Default::default() }).hunks.is_empty());
+}
+
+#[test]
+fn context_lines_sets_the_context_around_changes() {
+    let repo = TestRepo::new();
+    let mut lines = numbered(20);
+    repo.commit_file("a.txt", &text(&lines), "Initial");
+    lines[9] = "changed".to_string();
+    repo.write("a.txt", &text(&lines));
+
+    let lines_shown = |context| unstaged(&repo, DiffViewOptions { context_lines: Some(context), ..Default::default() }).hunks[0].lines.len();
+    assert_eq!(lines_shown(0), 2);
+    assert_eq!(lines_shown(1), 4);
+    assert_eq!(unstaged(&repo, DiffViewOptions::default()).hunks[0].lines.len(), 8);
+}
+
+#[test]
+fn diffs_report_the_algorithm_they_were_computed_with() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("a.txt", "two\n");
+
+    assert_eq!(unstaged(&repo, DiffViewOptions::default()).algorithm, DiffAlgorithm::Myers);
+    let minimal = DiffViewOptions { algorithm: Some(DiffAlgorithm::Minimal), ..Default::default() };
+    assert_eq!(unstaged(&repo, minimal).algorithm, DiffAlgorithm::Minimal);
+
+    // There's no histogram diff in libgit2; the payload says what was used instead
+    let histogram = DiffViewOptions { algorithm: Some(DiffAlgorithm::Histogram), ..Default::default() };
+    assert_eq!(unstaged(&repo, histogram).algorithm, DiffAlgorithm::Patience);
+
+    repo.repo.config().unwrap().set_str("diff.algorithm", "histogram").unwrap();
+    assert_eq!(unstaged(&repo, DiffViewOptions::default()).algorithm, DiffAlgorithm::Patience);
+}
+
+#[test]
+fn staged_renames_follow_the_renames_option() {
+    let repo = TestRepo::new();
+    repo.commit_file("old.txt", &text(&numbered(10)), "Initial");
+    repo.remove("old.txt");
+    repo.write("new.txt", &text(&numbered(10)));
+    repo::stage(&repo.path, &strings(&["old.txt", "new.txt"])).unwrap();
+
+    let diff = |renames| {
+        let view = DiffViewOptions { renames: Some(renames), ..Default::default() };
+        repo::get_diff(&repo.path, "new.txt", true, None, None, Some(view)).unwrap()
+    };
+    let renamed = diff(RenameDetection::Renames);
+    assert_eq!(renamed.old_path.as_deref(), Some("old.txt"));
+    assert!(renamed.hunks.is_empty());
+
+    let added = diff(RenameDetection::Off);
+    assert_eq!(added.old_path, None);
+    assert_eq!(added.hunks[0].lines.len(), 10);
+}
+
+#[test]
+fn staging_a_hunk_from_an_ignore_whitespace_view_stages_the_real_changes() {
+    let repo = TestRepo::new();
+    let original = numbered(20);
+    repo.commit_file("a.txt", &text(&original), "Initial");
+
+    // A reindented line next to a real change, and another change far away
+    let mut changed = original.clone();
+    changed[1] = format!("    {}", original[1]);
+    changed[2] = "three".to_string();
+    changed[17] = "eighteen".to_string();
+    repo.write("a.txt", &text(&changed));
+
+    let view = DiffViewOptions { ignore_all_space: true, ..Default::default() };
+    let shown = unstaged(&repo, view.clone());
+    assert_eq!(shown.hunks.len(), 2);
+    // The reindent shows as context, not as a change
+    let additions: Vec<_> = shown.hunks[0].lines.iter().filter(|l| matches!(l.line_type, LineType::Addition)).collect();
+    assert_eq!(additions.len(), 1);
+    assert_eq!(additions[0].content.trim_end(), "three");
+
+    repo::stage_hunk(&repo.path, "a.txt", &shown.hunks[0], Some(view.clone())).unwrap();
+    let mut expected = original.clone();
+    expected[1] = changed[1].clone();
+    expected[2] = changed[2].clone();
+    assert_eq!(repo.index_blob("a.txt"), Some(repo.blob(&text(&expected))));
+
+    // And back out again from the same kind of view
+    let staged = repo::get_diff(&repo.path, "a.txt", true, None, None, Some(view.clone())).unwrap();
+    repo::unstage_hunk(&repo.path, "a.txt", &staged.hunks[0], Some(view)).unwrap();
+    assert_eq!(repo.index_blob("a.txt"), Some(repo.blob(&text(&original))));
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::TestRepo;
+use gitfrisky_core::domain::types::FileStatus;
+use gitfrisky_core::history;
+
+#[test]
+fn file_history_lists_commits_that_touched_the_file() {
+    let repo = TestRepo::new();
+    let added = repo.commit_file("a.txt", "one\n", "Add a");
+    repo.commit_file("b.txt", "b\n", "Unrelated");
+    let changed = repo.commit_file("a.txt", "one\ntwo\n", "Change a");
+
+    let entries = history::file_history(&repo.path, "a.txt", None, None).unwrap();
+    let oids: Vec<_> = entries.iter().map(|e| e.commit.oid.clone()).collect();
+    assert_eq!(oids, vec![changed, added.clone()]);
+    assert!(matches!(entries[0].status, FileStatus::M));
+    assert!(matches!(entries[1].status, FileStatus::A));
+    assert_eq!(entries[0].hunks.len(), 1);
+
+    let limited = history::file_history(&repo.path, "a.txt", None, Some(1)).unwrap();
+    assert_eq!(limited.len(), 1);
+    let older = history::file_history(&repo.path, "a.txt", Some("HEAD~1"), None).unwrap();
+    assert_eq!(older.iter().map(|e| e.commit.oid.clone()).collect::<Vec<_>>(), vec![added]);
+}
+
+#[test]
+fn file_history_follows_renames() {
+    let repo = TestRepo::new();
+    let added = repo.commit_file("old.txt", "one\ntwo\nthree\nfour\n", "Add");
+    repo.remove("old.txt");
+    repo.write("new.txt", "one\ntwo\nthree\nfour\n");
+    let renamed = repo.commit_all("Rename");
+
+    let entries = history::file_history(&repo.path, "new.txt", None, None).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].commit.oid, renamed);
+    assert!(matches!(entries[0].status, FileStatus::R));
+    assert_eq!(entries[0].old_path.as_deref(), Some("old.txt"));
+    assert_eq!(entries[1].commit.oid, added);
+    assert_eq!(entries[1].path, "old.txt");
+}
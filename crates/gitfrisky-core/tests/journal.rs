@@ -0,0 +1,218 @@
+mod common;
+
+use common::{diverged, strings, TestRepo};
+use gitfrisky_core::domain::types::{ConflictChoice, ConflictResolution, ResetMode};
+use gitfrisky_core::operations::{finished_operations, is_own_write, RepoLayout};
+use gitfrisky_core::{conflict, journal, rebase, reflog, repo, reset, sequencer};
+
+#[test]
+fn journal_starts_empty() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+
+    let state = journal::journal(&repo.path).unwrap();
+    assert!(state.undo.is_empty() && state.redo.is_empty());
+    assert!(journal::undo(&repo.path).unwrap().is_none());
+    assert!(journal::redo(&repo.path).unwrap().is_none());
+}
+
+#[test]
+fn undo_and_redo_a_stage() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("a.txt", "two\n");
+    repo::stage(&repo.path, &strings(&["a.txt"])).unwrap();
+
+    let labels: Vec<_> = journal::journal(&repo.path).unwrap().undo.into_iter().map(|e| e.label).collect();
+    assert_eq!(labels, vec!["Stage a.txt"]);
+
+    let undone = journal::undo(&repo.path).unwrap().unwrap();
+    assert_eq!(undone.label, "Stage a.txt");
+    assert!(repo::status(&repo.path).unwrap().staged.is_empty());
+    assert_eq!(journal::journal(&repo.path).unwrap().redo.len(), 1);
+
+    journal::redo(&repo.path).unwrap().unwrap();
+    assert_eq!(repo.index_blob("a.txt"), Some(repo.blob("two\n")));
+    assert_eq!(journal::journal(&repo.path).unwrap().undo.len(), 1);
+}
+
+#[test]
+fn undo_a_commit_moves_the_branch_back() {
+    let repo = TestRepo::new();
+    let parent = repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("a.txt", "two\n");
+    repo::stage(&repo.path, &strings(&["a.txt"])).unwrap();
+    let commit = repo::commit(&repo.path, "Second").unwrap();
+
+    journal::undo(&repo.path).unwrap().unwrap();
+    assert_eq!(repo.head(), parent);
+    assert_eq!(repo.head_name().as_deref(), Some("refs/heads/main"));
+    assert_eq!(repo.index_blob("a.txt"), Some(repo.blob("two\n")));
+
+    journal::redo(&repo.path).unwrap().unwrap();
+    assert_eq!(repo.head(), commit.oid);
+}
+
+#[test]
+fn undo_a_hard_reset_restores_discarded_work() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "First");
+    let second = repo.commit_file("a.txt", "two\n", "Second");
+    repo.write("a.txt", "uncommitted\n");
+
+    reset::reset(&repo.path, "HEAD~1", ResetMode::Hard, true).unwrap();
+    assert_eq!(repo.read("a.txt"), "one\n");
+
+    journal::undo(&repo.path).unwrap().unwrap();
+    assert_eq!(repo.head(), second);
+    assert_eq!(repo.read("a.txt"), "uncommitted\n");
+}
+
+#[test]
+fn undo_refuses_when_the_repository_changed_since() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("a.txt", "two\n");
+    repo::stage(&repo.path, &strings(&["a.txt"])).unwrap();
+
+    // Changed behind the journal's back
+    repo.commit_file("b.txt", "b\n", "Elsewhere");
+
+    assert!(journal::undo(&repo.path).is_err());
+}
+
+#[test]
+fn new_operations_clear_the_redo_list() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("a.txt", "two\n");
+    repo.write("b.txt", "b\n");
+    repo::stage(&repo.path, &strings(&["a.txt"])).unwrap();
+    journal::undo(&repo.path).unwrap();
+
+    repo::stage(&repo.path, &strings(&["b.txt"])).unwrap();
+    let state = journal::journal(&repo.path).unwrap();
+    assert_eq!(state.undo.len(), 1);
+    assert!(state.redo.is_empty());
+}
+
+#[test]
+fn undo_a_conflict_resolution_brings_the_conflict_back() {
+    let repo = TestRepo::new();
+    diverged(&repo);
+    repo.merge("topic");
+    let conflicted = repo.read("a.txt");
+
+    let theirs = ConflictResolution::File { choice: ConflictChoice::Theirs };
+    conflict::resolve_conflict(&repo.path, "a.txt", theirs).unwrap();
+    assert!(conflict::list_conflicts(&repo.path).unwrap().is_empty());
+
+    journal::undo(&repo.path).unwrap().unwrap();
+    assert_eq!(conflict::list_conflicts(&repo.path).unwrap(), vec!["a.txt"]);
+    assert_eq!(repo.read("a.txt"), conflicted);
+
+    journal::redo(&repo.path).unwrap().unwrap();
+    assert!(conflict::list_conflicts(&repo.path).unwrap().is_empty());
+    assert_eq!(repo.read("a.txt"), "one\ntheirs\nthree\n");
+}
+
+#[test]
+fn undo_a_cherry_pick_and_a_rebase_that_ran_to_the_end() {
+    let repo = TestRepo::new();
+    let base = repo.commit_file("a.txt", "a\n", "Base");
+    repo.branch("topic", &base);
+    repo.checkout("topic");
+    let pick = repo.commit_file("b.txt", "b\n", "Add b");
+    repo.checkout("main");
+    let main = repo.commit_file("c.txt", "c\n", "Add c");
+
+    sequencer::cherry_pick(&repo.path, &[pick], None).unwrap();
+    let label = journal::undo(&repo.path).unwrap().unwrap().label;
+    assert_eq!(label, "Cherry-pick Add b");
+    assert_eq!(repo.head(), main);
+    assert!(!repo.file("b.txt").exists());
+
+    repo.checkout("topic");
+    let plan = rebase::rebase_plan(&repo.path, "main").unwrap();
+    let topic = repo.head();
+    assert!(!rebase::rebase_start(&repo.path, "main", &plan).unwrap().in_progress);
+    assert!(repo.file("c.txt").exists());
+
+    journal::undo(&repo.path).unwrap().unwrap();
+    assert_eq!(repo.head(), topic);
+    assert_eq!(repo.head_name().as_deref(), Some("refs/heads/topic"));
+    assert!(!repo.file("c.txt").exists());
+}
+
+#[test]
+fn a_sequence_that_stops_is_not_journaled() {
+    let repo = TestRepo::new();
+    let (_, _, theirs) = diverged(&repo);
+
+    assert!(sequencer::cherry_pick(&repo.path, &[theirs], None).is_err());
+    assert!(journal::journal(&repo.path).unwrap().undo.is_empty());
+}
+
+#[test]
+fn undo_restoring_a_lost_commit_deletes_the_branch() {
+    let repo = TestRepo::new();
+    let lost = repo.commit_file("a.txt", "one\n", "Initial");
+
+    reflog::restore_commit(&repo.path, &lost, "found").unwrap();
+    journal::undo(&repo.path).unwrap().unwrap();
+    assert!(repo.repo.find_reference("refs/heads/found").is_err());
+}
+
+#[test]
+fn snapshots_are_anchored_under_a_ref() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "First");
+    repo.commit_file("a.txt", "two\n", "Second");
+    repo.write("a.txt", "uncommitted\n");
+    reset::reset(&repo.path, "HEAD~1", ResetMode::Hard, true).unwrap();
+
+    // The discarded content only lives in the object database, kept by the anchor
+    let anchor = repo.repo.find_reference("refs/gitfrisky/journal").unwrap().peel_to_tree().unwrap();
+    let discarded = repo.blob("uncommitted\n");
+    assert!(anchor.get_name(&discarded.to_string()).is_some());
+}
+
+#[cfg(unix)]
+#[test]
+fn undo_restores_the_file_mode() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = TestRepo::new();
+    repo.commit_file("run.sh", "echo one\n", "First");
+    repo.commit_file("run.sh", "echo two\n", "Second");
+    repo.write("run.sh", "echo local\n");
+    std::fs::set_permissions(repo.file("run.sh"), std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    reset::reset(&repo.path, "HEAD~1", ResetMode::Hard, true).unwrap();
+    journal::undo(&repo.path).unwrap().unwrap();
+
+    assert_eq!(repo.read("run.sh"), "echo local\n");
+    let mode = std::fs::metadata(repo.file("run.sh")).unwrap().permissions().mode();
+    assert_eq!(mode & 0o111, 0o111);
+}
+
+#[test]
+fn undo_and_redo_are_our_own_writes() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.commit_file("a.txt", "two\n", "Second");
+    reset::reset(&repo.path, "HEAD~1", ResetMode::Hard, true).unwrap();
+    let layout = RepoLayout::resolve(&repo.repo);
+    let file = layout.workdir.unwrap().join("a.txt");
+
+    let before = finished_operations();
+    journal::undo(&repo.path).unwrap().unwrap();
+    assert!(finished_operations() > before);
+    assert!(is_own_write(&layout.git_dir.join("index")));
+    assert!(is_own_write(&file));
+
+    let before = finished_operations();
+    journal::redo(&repo.path).unwrap().unwrap();
+    assert!(finished_operations() > before);
+    assert!(is_own_write(&file));
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::{strings, TestRepo};
+use gitfrisky_core::domain::types::ResetMode;
+use gitfrisky_core::operations::{begin_operation, finished_operations, is_own_write, RepoLayout};
+use gitfrisky_core::{repo, reset};
+
+#[test]
+fn operations_record_their_own_writes() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("a.txt", "two\n");
+    let layout = RepoLayout::resolve(&repo.repo);
+    let index = layout.git_dir.join("index");
+    let before = finished_operations();
+
+    repo::stage(&repo.path, &strings(&["a.txt"])).unwrap();
+    assert!(finished_operations() > before);
+    assert!(is_own_write(&index));
+
+    // Written by someone else since
+    repo.add_all();
+    repo.write("a.txt", "three\n");
+    repo.add_all();
+    assert!(!is_own_write(&index));
+    assert!(!is_own_write(&layout.workdir.unwrap().join("a.txt")));
+}
+
+// Needs the inode and change time, which only Unix fingerprints record
+#[cfg(unix)]
+#[test]
+fn a_same_size_file_renamed_into_place_is_not_ours() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "First");
+    repo.commit_file("a.txt", "two\n", "Second");
+    reset::reset(&repo.path, "HEAD~1", ResetMode::Hard, true).unwrap();
+
+    let path = RepoLayout::resolve(&repo.repo).workdir.unwrap().join("a.txt");
+    assert!(is_own_write(&path));
+
+    // Same size and, forced back, the same mtime as what the reset left
+    let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+    let temp = path.with_extension("tmp");
+    std::fs::write(&temp, "six\n").unwrap();
+    std::fs::File::options().write(true).open(&temp).unwrap().set_modified(modified).unwrap();
+    std::fs::rename(&temp, &path).unwrap();
+
+    assert!(!is_own_write(&path));
+}
+
+#[test]
+fn writes_during_a_running_operation_are_reported() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    let index = RepoLayout::resolve(&repo.repo).git_dir.join("index");
+
+    let token = begin_operation(&repo.repo, &[], &strings(&["a.txt"]));
+    repo.write("b.txt", "b\n");
+    repo.add_all();
+    assert!(!is_own_write(&index));
+    drop(token);
+}
+
+#[test]
+fn own_writes_expire_once_reported() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("a.txt", "two\n");
+    let index = RepoLayout::resolve(&repo.repo).git_dir.join("index");
+
+    repo::stage(&repo.path, &strings(&["a.txt"])).unwrap();
+    assert!(is_own_write(&index));
+    // Other events for the same write may follow shortly
+    assert!(is_own_write(&index));
+
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    assert!(!is_own_write(&index));
+}
@@ -0,0 +1,236 @@
+mod common;
+
+use common::{diverged, TestRepo};
+use gitfrisky_core::domain::types::{RebaseAction, RebaseStopReason, RebaseTodoEntry};
+use gitfrisky_core::{conflict, rebase, repo};
+
+// `topic` has two commits touching b.txt on top of `base`; `main` moved on with c.txt.
+// Returns (main tip, topic's commits) with `topic` checked out.
+fn stacked(repo: &TestRepo) -> (String, Vec<String>) {
+    let base = repo.commit_file("a.txt", "a\n", "Base");
+    repo.branch("topic", &base);
+    let main = repo.commit_file("c.txt", "c\n", "Main");
+    repo.checkout("topic");
+    let first = repo.commit_file("b.txt", "1\n", "First");
+    let second = repo.commit_file("b.txt", "2\n", "Second");
+    (main, vec![first, second])
+}
+
+fn summaries(repo: &TestRepo, limit: usize) -> Vec<String> {
+    let mut walk = repo.repo.revwalk().unwrap();
+    walk.push_head().unwrap();
+    walk.take(limit).map(|oid| repo.summary(&oid.unwrap().to_string())).collect()
+}
+
+#[test]
+fn rebase_plan_picks_each_commit_oldest_first() {
+    let repo = TestRepo::new();
+    let (_, commits) = stacked(&repo);
+
+    let plan = rebase::rebase_plan(&repo.path, "main").unwrap();
+    let oids: Vec<_> = plan.iter().map(|e| e.oid.clone().unwrap()).collect();
+    assert_eq!(oids, commits);
+    assert!(plan.iter().all(|e| e.action == RebaseAction::Pick));
+    assert_eq!(plan[0].summary.as_deref(), Some("First"));
+}
+
+#[test]
+fn rebase_start_replays_the_plan_onto_the_new_base() {
+    let repo = TestRepo::new();
+    let (main, _) = stacked(&repo);
+
+    let plan = rebase::rebase_plan(&repo.path, "main").unwrap();
+    let status = rebase::rebase_start(&repo.path, "main", &plan).unwrap();
+
+    assert!(!status.in_progress);
+    assert_eq!(repo.head_name().as_deref(), Some("refs/heads/topic"));
+    assert_eq!(summaries(&repo, 3), vec!["Second", "First", "Main"]);
+    let head = repo.repo.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(head.parent(0).unwrap().parent_id(0).unwrap().to_string(), main);
+    assert_eq!(repo.read("b.txt"), "2\n");
+}
+
+#[test]
+fn rebase_start_applies_reword_squash_and_drop() {
+    let repo = TestRepo::new();
+    stacked(&repo);
+    let third = repo.commit_file("d.txt", "d\n", "Third");
+
+    let mut plan = rebase::rebase_plan(&repo.path, "main").unwrap();
+    plan[0].action = RebaseAction::Reword;
+    plan[0].message = Some("Reworded".to_string());
+    plan[1].action = RebaseAction::Fixup;
+    plan[2].action = RebaseAction::Drop;
+    let status = rebase::rebase_start(&repo.path, "main", &plan).unwrap();
+
+    assert!(!status.in_progress);
+    assert_eq!(summaries(&repo, 2), vec!["Reworded", "Main"]);
+    assert_eq!(repo.read("b.txt"), "2\n");
+    assert!(!repo.file("d.txt").exists());
+    assert_ne!(repo.head(), third);
+}
+
+#[test]
+fn rebase_stops_on_edit_until_continued() {
+    let repo = TestRepo::new();
+    stacked(&repo);
+
+    let mut plan = rebase::rebase_plan(&repo.path, "main").unwrap();
+    plan[0].action = RebaseAction::Edit;
+    let status = rebase::rebase_start(&repo.path, "main", &plan).unwrap();
+    assert!(status.in_progress);
+    assert_eq!(status.stopped, Some(RebaseStopReason::Edit));
+    assert_eq!(status.remaining.len(), 1);
+    assert_eq!(rebase::rebase_status(&repo.path).unwrap().stopped, Some(RebaseStopReason::Edit));
+
+    let status = rebase::rebase_continue(&repo.path, Some("Edited")).unwrap();
+    assert!(!status.in_progress);
+    assert_eq!(summaries(&repo, 3), vec!["Second", "Edited", "Main"]);
+}
+
+#[test]
+fn rebase_continue_commits_a_resolved_conflict() {
+    let repo = TestRepo::new();
+    diverged(&repo);
+    repo.checkout("topic");
+
+    let plan = rebase::rebase_plan(&repo.path, "main").unwrap();
+    let status = rebase::rebase_start(&repo.path, "main", &plan).unwrap();
+    assert_eq!(status.stopped, Some(RebaseStopReason::Conflict));
+    assert_eq!(status.conflicts, vec!["a.txt"]);
+    assert!(rebase::rebase_continue(&repo.path, None).is_err());
+
+    repo.write("a.txt", "one\nboth\nthree\n");
+    conflict::mark_resolved(&repo.path, &["a.txt".to_string()]).unwrap();
+    let status = rebase::rebase_continue(&repo.path, None).unwrap();
+
+    assert!(!status.in_progress);
+    assert_eq!(summaries(&repo, 3), vec!["Theirs", "Ours", "Base"]);
+    assert_eq!(repo.read("a.txt"), "one\nboth\nthree\n");
+}
+
+#[test]
+fn rebase_skip_drops_the_conflicting_commit() {
+    let repo = TestRepo::new();
+    let (_, ours, _) = diverged(&repo);
+    repo.checkout("topic");
+
+    let plan = rebase::rebase_plan(&repo.path, "main").unwrap();
+    rebase::rebase_start(&repo.path, "main", &plan).unwrap();
+    let status = rebase::rebase_skip(&repo.path).unwrap();
+
+    assert!(!status.in_progress);
+    assert_eq!(repo.head(), ours);
+    assert_eq!(repo.head_name().as_deref(), Some("refs/heads/topic"));
+    assert!(repo::status(&repo.path).unwrap().unstaged.is_empty());
+}
+
+#[test]
+fn rebase_abort_restores_the_original_branch() {
+    let repo = TestRepo::new();
+    let (_, _, theirs) = diverged(&repo);
+    repo.checkout("topic");
+
+    let plan = rebase::rebase_plan(&repo.path, "main").unwrap();
+    rebase::rebase_start(&repo.path, "main", &plan).unwrap();
+    let status = rebase::rebase_abort(&repo.path).unwrap();
+
+    assert!(!status.in_progress);
+    assert_eq!(repo.head(), theirs);
+    assert_eq!(repo.head_name().as_deref(), Some("refs/heads/topic"));
+    assert_eq!(repo.read("a.txt"), "one\ntheirs\nthree\n");
+}
+
+#[test]
+fn rebase_start_refuses_a_dirty_working_tree() {
+    let repo = TestRepo::new();
+    stacked(&repo);
+    repo.write("b.txt", "dirty\n");
+
+    let plan = rebase::rebase_plan(&repo.path, "main").unwrap();
+    assert!(rebase::rebase_start(&repo.path, "main", &plan).is_err());
+    assert!(!rebase::rebase_status(&repo.path).unwrap().in_progress);
+}
+
+// `topic` adds the same c.txt that `main` already has, so picking it changes nothing
+fn already_upstream(repo: &TestRepo) -> String {
+    let base = repo.commit_file("a.txt", "a\n", "Base");
+    repo.branch("topic", &base);
+    repo.commit_file("c.txt", "c\n", "Main adds c");
+    repo.checkout("topic");
+    let duplicate = repo.commit_file("c.txt", "c\n", "Topic adds c");
+    repo.commit_file("b.txt", "b\n", "Topic adds b");
+    duplicate
+}
+
+#[test]
+fn rebase_stops_on_a_step_with_nothing_to_commit() {
+    let repo = TestRepo::new();
+    let duplicate = already_upstream(&repo);
+
+    let plan = rebase::rebase_plan(&repo.path, "main").unwrap();
+    let status = rebase::rebase_start(&repo.path, "main", &plan).unwrap();
+    assert!(status.in_progress);
+    assert_eq!(status.stopped, Some(RebaseStopReason::Empty));
+    assert_eq!(status.done.last().unwrap().oid.as_deref(), Some(duplicate.as_str()));
+    assert!(rebase::rebase_continue(&repo.path, None).is_err());
+
+    let status = rebase::rebase_skip(&repo.path).unwrap();
+    assert!(!status.in_progress);
+    assert_eq!(summaries(&repo, 3), vec!["Topic adds b", "Main adds c", "Base"]);
+}
+
+#[test]
+fn an_empty_edit_step_still_stops() {
+    let repo = TestRepo::new();
+    already_upstream(&repo);
+
+    let mut plan = rebase::rebase_plan(&repo.path, "main").unwrap();
+    plan[0].action = RebaseAction::Edit;
+    plan[0].message = Some("Kept".to_string());
+    let status = rebase::rebase_start(&repo.path, "main", &plan).unwrap();
+    assert_eq!(status.stopped, Some(RebaseStopReason::Empty));
+    assert_eq!(status.message.as_deref(), Some("Kept"));
+
+    // Staging something makes it a real commit, which then stops for the edit
+    repo.write("c.txt", "c, edited\n");
+    repo::stage(&repo.path, &["c.txt".to_string()]).unwrap();
+    let status = rebase::rebase_continue(&repo.path, None).unwrap();
+    assert_eq!(status.stopped, Some(RebaseStopReason::Edit));
+    assert_eq!(summaries(&repo, 1), vec!["Kept"]);
+
+    let status = rebase::rebase_continue(&repo.path, None).unwrap();
+    assert!(!status.in_progress);
+    assert_eq!(summaries(&repo, 3), vec!["Topic adds b", "Kept", "Main adds c"]);
+}
+
+#[test]
+fn the_same_commit_can_be_picked_twice_with_different_messages() {
+    let repo = TestRepo::new();
+    let base = repo.commit_file("a.txt", "a\n", "Base");
+    let change = repo.commit_file("b.txt", "b\n", "Add b");
+
+    // Pick it, revert it, pick it again: each copy gets its own message
+    let entry = |message: &str| RebaseTodoEntry {
+        action: RebaseAction::Reword,
+        oid: Some(change.clone()),
+        summary: None,
+        message: Some(message.to_string()),
+        command: None,
+    };
+    let todo = vec![
+        entry("First copy"),
+        RebaseTodoEntry {
+            action: RebaseAction::Exec,
+            oid: None,
+            summary: None,
+            message: None,
+            command: Some("git rm -q b.txt && git commit -qm 'Remove b'".to_string()),
+        },
+        entry("Second copy"),
+    ];
+    let status = rebase::rebase_start(&repo.path, &base, &todo).unwrap();
+
+    assert!(!status.in_progress, "{:?}", status.stopped);
+    assert_eq!(summaries(&repo, 4), vec!["Second copy", "Remove b", "First copy", "Base"]);
+}
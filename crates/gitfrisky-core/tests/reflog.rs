@@ -0,0 +1,82 @@
+mod common;
+
+use common::TestRepo;
+use gitfrisky_core::domain::types::ResetMode;
+use gitfrisky_core::{reflog, reset};
+
+#[test]
+fn reflog_lists_head_movements_newest_first() {
+    let repo = TestRepo::new();
+    let first = repo.commit_file("a.txt", "one\n", "First");
+    let second = repo.commit_file("a.txt", "two\n", "Second");
+    reset::reset(&repo.path, &first, ResetMode::Hard, false).unwrap();
+
+    let entries = reflog::reflog(&repo.path, None).unwrap();
+    let moves: Vec<_> = entries.iter().map(|e| (e.old_oid.as_str(), e.new_oid.as_str())).collect();
+    assert_eq!(moves[0], (second.as_str(), first.as_str()));
+    assert_eq!(moves[1], (first.as_str(), second.as_str()));
+    assert_eq!(entries[1].commit.as_ref().unwrap().summary, "Second");
+
+    let branch = reflog::reflog(&repo.path, Some("refs/heads/main")).unwrap();
+    assert_eq!(branch.len(), entries.len());
+}
+
+#[test]
+fn dangling_commits_finds_commits_lost_to_a_reset() {
+    let repo = TestRepo::new();
+    let first = repo.commit_file("a.txt", "one\n", "First");
+    repo.commit_file("a.txt", "two\n", "Second");
+    let lost = repo.commit_file("a.txt", "three\n", "Third");
+    assert!(reflog::dangling_commits(&repo.path).unwrap().is_empty());
+
+    reset::reset(&repo.path, &first, ResetMode::Hard, false).unwrap();
+
+    // Only the tip; its lost parent comes back with it
+    let dangling = reflog::dangling_commits(&repo.path).unwrap();
+    assert_eq!(dangling.len(), 1);
+    assert_eq!(dangling[0].commit.oid, lost);
+    assert_eq!(dangling[0].reflog.as_deref(), Some("HEAD"));
+}
+
+#[test]
+fn dangling_commits_leaves_out_older_stashes() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    let mut stashing = git2::Repository::open(&repo.path).unwrap();
+    let sig = git2::Signature::now("Test User", "test@example.com").unwrap();
+    for content in ["two\n", "three\n"] {
+        repo.write("a.txt", content);
+        stashing.stash_save(&sig, "WIP", None).unwrap();
+    }
+
+    assert!(reflog::dangling_commits(&repo.path).unwrap().is_empty());
+}
+
+#[test]
+fn dangling_commits_without_a_reflog_entry_have_no_label() {
+    let repo = TestRepo::new();
+    let head = repo.commit_file("a.txt", "one\n", "Initial");
+    let parent = repo.repo.find_commit(git2::Oid::from_str(&head).unwrap()).unwrap();
+    let sig = git2::Signature::now("Test User", "test@example.com").unwrap();
+    let orphan = repo.repo.commit(None, &sig, &sig, "Never on a ref", &parent.tree().unwrap(), &[&parent]).unwrap();
+
+    let dangling = reflog::dangling_commits(&repo.path).unwrap();
+    assert_eq!(dangling.len(), 1);
+    assert_eq!(dangling[0].commit.oid, orphan.to_string());
+    assert_eq!(dangling[0].reflog, None);
+}
+
+#[test]
+fn restore_commit_creates_a_branch_at_a_lost_commit() {
+    let repo = TestRepo::new();
+    let first = repo.commit_file("a.txt", "one\n", "First");
+    let lost = repo.commit_file("a.txt", "two\n", "Second");
+    reset::reset(&repo.path, &first, ResetMode::Hard, false).unwrap();
+
+    let name = reflog::restore_commit(&repo.path, &lost, "rescued").unwrap();
+    assert_eq!(name, "refs/heads/rescued");
+    assert_eq!(repo.repo.refname_to_id(&name).unwrap().to_string(), lost);
+    assert!(reflog::dangling_commits(&repo.path).unwrap().is_empty());
+
+    assert!(reflog::restore_commit(&repo.path, &lost, "rescued").is_err());
+}
@@ -0,0 +1,242 @@
+mod common;
+
+use common::{strings, TestRepo};
+use gitfrisky_core::domain::types::{FileStatus, LineType, RepoState};
+use gitfrisky_core::repo;
+
+fn paths(changes: &[gitfrisky_core::domain::types::FileChange]) -> Vec<&str> {
+    changes.iter().map(|c| c.path.as_str()).collect()
+}
+
+#[test]
+fn open_repo_lists_branches_and_head() {
+    let repo = TestRepo::new();
+    let base = repo.commit_file("a.txt", "one\n", "Initial");
+    repo.branch("topic", &base);
+
+    let summary = repo::open_repo(&repo.path).unwrap();
+    assert_eq!(summary.head.as_deref(), Some("main"));
+    assert!(!summary.is_bare);
+    assert!(!summary.is_detached);
+    assert!(matches!(summary.state, RepoState::Clean));
+
+    let mut names: Vec<_> = summary.branches.iter().map(|b| (b.name.as_str(), b.is_head)).collect();
+    names.sort();
+    assert_eq!(names, vec![("main", true), ("topic", false)]);
+}
+
+#[test]
+fn open_repo_rejects_plain_directories() {
+    let dir = tempfile::tempdir().unwrap();
+    assert!(repo::open_repo(dir.path().to_str().unwrap()).is_err());
+}
+
+#[test]
+fn status_reports_staged_unstaged_and_untracked_files() {
+    let repo = TestRepo::new();
+    repo.write("modified.txt", "one\n");
+    repo.write("deleted.txt", "gone\n");
+    repo.commit_all("Initial");
+
+    repo.write("modified.txt", "one\ntwo\n");
+    repo.remove("deleted.txt");
+    repo.write("added.txt", "new\n");
+    repo::stage(&repo.path, &strings(&["added.txt"])).unwrap();
+    repo.write("untracked.txt", "?\n");
+
+    let status = repo::status(&repo.path).unwrap();
+    assert_eq!(paths(&status.staged), vec!["added.txt"]);
+    assert!(matches!(status.staged[0].status, FileStatus::A));
+
+    let unstaged = paths(&status.unstaged);
+    assert!(unstaged.contains(&"modified.txt"));
+    assert!(unstaged.contains(&"deleted.txt"));
+    assert!(unstaged.contains(&"untracked.txt"));
+    let untracked = status.unstaged.iter().find(|c| c.path == "untracked.txt").unwrap();
+    assert!(matches!(untracked.status, FileStatus::U));
+    let modified = status.unstaged.iter().find(|c| c.path == "modified.txt").unwrap();
+    assert!(matches!(modified.status, FileStatus::M));
+}
+
+#[test]
+fn status_paths_only_reports_the_given_paths() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "a\n", "Initial");
+    repo.write("a.txt", "changed\n");
+    repo.write("dir/b.txt", "b\n");
+    repo.write("c.txt", "c\n");
+
+    let status = repo::status_paths(&repo.path, &strings(&["a.txt", "dir"])).unwrap();
+    let mut unstaged = paths(&status.unstaged);
+    unstaged.sort();
+    assert_eq!(unstaged, vec!["a.txt", "dir/b.txt"]);
+
+    let none = repo::status_paths(&repo.path, &[]).unwrap();
+    assert!(none.unstaged.is_empty() && none.staged.is_empty());
+}
+
+#[test]
+fn stage_and_unstage_move_files_between_lists() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("a.txt", "two\n");
+    repo.write("new.txt", "new\n");
+
+    repo::stage(&repo.path, &strings(&["a.txt", "new.txt"])).unwrap();
+    let status = repo::status(&repo.path).unwrap();
+    assert!(status.unstaged.is_empty());
+    assert_eq!(status.staged.len(), 2);
+
+    repo::unstage(&repo.path, &strings(&["a.txt", "new.txt"])).unwrap();
+    let status = repo::status(&repo.path).unwrap();
+    assert!(status.staged.is_empty());
+    let new = status.unstaged.iter().find(|c| c.path == "new.txt").unwrap();
+    assert!(matches!(new.status, FileStatus::U));
+}
+
+#[test]
+fn stage_records_deletions() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.remove("a.txt");
+
+    repo::stage(&repo.path, &strings(&["a.txt"])).unwrap();
+    let status = repo::status(&repo.path).unwrap();
+    assert_eq!(paths(&status.staged), vec!["a.txt"]);
+    assert!(matches!(status.staged[0].status, FileStatus::D));
+}
+
+#[test]
+fn unstage_works_before_the_first_commit() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo::stage(&repo.path, &strings(&["a.txt"])).unwrap();
+
+    repo::unstage(&repo.path, &strings(&["a.txt"])).unwrap();
+    assert!(repo::status(&repo.path).unwrap().staged.is_empty());
+}
+
+#[test]
+fn get_diff_shows_staged_and_unstaged_changes() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\ntwo\nthree\n", "Initial");
+    repo.write("a.txt", "one\n2\nthree\n");
+    repo::stage(&repo.path, &strings(&["a.txt"])).unwrap();
+    repo.write("a.txt", "one\n2\nthree\nfour\n");
+
+    let staged = repo::get_diff(&repo.path, "a.txt", true, None, None, None).unwrap();
+    assert_eq!(staged.hunks.len(), 1);
+    let changed: Vec<_> = staged.hunks[0]
+        .lines
+        .iter()
+        .filter(|l| !matches!(l.line_type, LineType::Context))
+        .map(|l| l.content.trim_end())
+        .collect();
+    assert_eq!(changed, vec!["two", "2"]);
+
+    let unstaged = repo::get_diff(&repo.path, "a.txt", false, None, None, None).unwrap();
+    let added: Vec<_> = unstaged.hunks[0]
+        .lines
+        .iter()
+        .filter(|l| matches!(l.line_type, LineType::Addition))
+        .map(|l| l.content.trim_end())
+        .collect();
+    assert_eq!(added, vec!["four"]);
+}
+
+#[test]
+fn get_diff_shows_untracked_files_as_additions() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "a\n", "Initial");
+    repo.write("new.txt", "x\ny\n");
+
+    let diff = repo::get_diff(&repo.path, "new.txt", false, None, None, None).unwrap();
+    assert!(!diff.is_binary);
+    let lines = &diff.hunks[0].lines;
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().all(|l| matches!(l.line_type, LineType::Addition)));
+}
+
+#[test]
+fn commit_creates_a_commit_on_head() {
+    let repo = TestRepo::new();
+    let parent = repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("a.txt", "two\n");
+    repo::stage(&repo.path, &strings(&["a.txt"])).unwrap();
+
+    let commit = repo::commit(&repo.path, "Second\n\nWith a body").unwrap();
+    assert_eq!(commit.summary, "Second");
+    assert_eq!(commit.parents, vec![parent]);
+    assert_eq!(repo.head(), commit.oid);
+    assert!(repo::status(&repo.path).unwrap().staged.is_empty());
+}
+
+#[test]
+fn commit_works_on_an_unborn_branch() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo::stage(&repo.path, &strings(&["a.txt"])).unwrap();
+
+    let commit = repo::commit(&repo.path, "Initial").unwrap();
+    assert!(commit.parents.is_empty());
+    assert_eq!(repo.head(), commit.oid);
+}
+
+#[test]
+fn commit_refuses_an_empty_message() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("a.txt", "two\n");
+    repo::stage(&repo.path, &strings(&["a.txt"])).unwrap();
+
+    assert!(repo::commit(&repo.path, "   ").is_err());
+}
+
+#[test]
+fn log_lists_commits_newest_first() {
+    let repo = TestRepo::new();
+    let first = repo.commit_file("a.txt", "1\n", "First");
+    let second = repo.commit_file("a.txt", "2\n", "Second");
+    repo.branch("topic", &second);
+    repo.checkout("topic");
+    let side = repo.commit_file("b.txt", "b\n", "Side");
+    repo.checkout("main");
+    let third = repo.commit_file("a.txt", "3\n", "Third");
+
+    let log = repo::log(&repo.path, None).unwrap();
+    let oids: Vec<_> = log.iter().map(|c| c.oid.clone()).collect();
+    // Every branch, by date
+    assert_eq!(oids, vec![third.clone(), side, second, first]);
+
+    let limited = repo::log(&repo.path, Some(1)).unwrap();
+    assert_eq!(limited.len(), 1);
+    assert_eq!(limited[0].oid, third);
+}
+
+#[test]
+fn stage_hunk_and_unstage_hunk_move_a_single_hunk() {
+    let repo = TestRepo::new();
+    let lines: Vec<String> = (1..=20).map(|n| n.to_string()).collect();
+    repo.commit_file("a.txt", &format!("{}\n", lines.join("\n")), "Initial");
+
+    // Two changes far enough apart to get separate hunks
+    let mut changed = lines.clone();
+    changed[0] = "one".to_string();
+    changed[19] = "twenty".to_string();
+    repo.write("a.txt", &format!("{}\n", changed.join("\n")));
+
+    let diff = repo::get_diff(&repo.path, "a.txt", false, None, None, None).unwrap();
+    assert_eq!(diff.hunks.len(), 2);
+    repo::stage_hunk(&repo.path, "a.txt", &diff.hunks[0], None).unwrap();
+
+    let staged = repo::get_diff(&repo.path, "a.txt", true, None, None, None).unwrap();
+    assert_eq!(staged.hunks.len(), 1);
+    assert!(staged.hunks[0].lines.iter().any(|l| l.content.trim_end() == "one"));
+    let unstaged = repo::get_diff(&repo.path, "a.txt", false, None, None, None).unwrap();
+    assert_eq!(unstaged.hunks.len(), 1);
+    assert!(unstaged.hunks[0].lines.iter().any(|l| l.content.trim_end() == "twenty"));
+
+    repo::unstage_hunk(&repo.path, "a.txt", &staged.hunks[0], None).unwrap();
+    assert!(repo::get_diff(&repo.path, "a.txt", true, None, None, None).unwrap().hunks.is_empty());
+    assert_eq!(repo::get_diff(&repo.path, "a.txt", false, None, None, None).unwrap().hunks.len(), 2);
+}
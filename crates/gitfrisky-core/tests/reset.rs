@@ -0,0 +1,96 @@
+mod common;
+
+use common::{strings, TestRepo};
+use gitfrisky_core::domain::types::ResetMode;
+use gitfrisky_core::{repo, reset};
+
+// Two commits of a.txt; returns (first, second)
+fn two_commits(repo: &TestRepo) -> (String, String) {
+    let first = repo.commit_file("a.txt", "one\n", "First");
+    let second = repo.commit_file("a.txt", "two\n", "Second");
+    (first, second)
+}
+
+#[test]
+fn soft_reset_keeps_changes_staged() {
+    let repo = TestRepo::new();
+    let (first, second) = two_commits(&repo);
+
+    let result = reset::reset(&repo.path, &first, ResetMode::Soft, false).unwrap();
+    assert!(result.applied);
+    assert_eq!((result.previous_head, result.new_head), (second, first.clone()));
+    assert_eq!(repo.head(), first);
+    assert_eq!(repo.head_name().as_deref(), Some("refs/heads/main"));
+    assert_eq!(repo::status(&repo.path).unwrap().staged.len(), 1);
+    assert_eq!(repo.read("a.txt"), "two\n");
+}
+
+#[test]
+fn mixed_reset_keeps_changes_in_the_working_tree() {
+    let repo = TestRepo::new();
+    let (first, _) = two_commits(&repo);
+
+    reset::reset(&repo.path, "HEAD~1", ResetMode::Mixed, false).unwrap();
+    let status = repo::status(&repo.path).unwrap();
+    assert_eq!(repo.head(), first);
+    assert!(status.staged.is_empty());
+    assert_eq!(status.unstaged.len(), 1);
+    assert_eq!(repo.read("a.txt"), "two\n");
+}
+
+#[test]
+fn hard_reset_asks_before_discarding_changes() {
+    let repo = TestRepo::new();
+    let (first, second) = two_commits(&repo);
+    repo.write("a.txt", "dirty\n");
+
+    let refused = reset::reset(&repo.path, &first, ResetMode::Hard, false).unwrap();
+    assert!(!refused.applied);
+    assert_eq!(refused.dirty_files, vec!["a.txt"]);
+    assert_eq!(repo.head(), second);
+    assert_eq!(repo.read("a.txt"), "dirty\n");
+
+    let forced = reset::reset(&repo.path, &first, ResetMode::Hard, true).unwrap();
+    assert!(forced.applied);
+    assert_eq!(repo.head(), first);
+    assert_eq!(repo.read("a.txt"), "one\n");
+}
+
+#[test]
+fn checkout_commit_detaches_head() {
+    let repo = TestRepo::new();
+    let (first, _) = two_commits(&repo);
+
+    let oid = reset::checkout_commit(&repo.path, "HEAD~1", false).unwrap();
+    assert_eq!(oid, first);
+    assert_eq!(repo.head(), first);
+    assert!(repo.repo.head_detached().unwrap());
+    assert_eq!(repo.read("a.txt"), "one\n");
+}
+
+#[test]
+fn checkout_commit_refuses_to_overwrite_local_changes() {
+    let repo = TestRepo::new();
+    let (_, second) = two_commits(&repo);
+    repo.write("a.txt", "dirty\n");
+
+    assert!(reset::checkout_commit(&repo.path, "HEAD~1", false).is_err());
+    assert_eq!(repo.head(), second);
+    assert_eq!(repo.read("a.txt"), "dirty\n");
+}
+
+#[test]
+fn checkout_paths_restores_files_from_a_commit() {
+    let repo = TestRepo::new();
+    repo.write("b.txt", "b1\n");
+    let (_, second) = two_commits(&repo);
+    repo.write("b.txt", "b2\n");
+
+    reset::checkout_paths(&repo.path, "HEAD~1", &strings(&["a.txt"])).unwrap();
+    assert_eq!(repo.read("a.txt"), "one\n");
+    assert_eq!(repo.read("b.txt"), "b2\n");
+    assert_eq!(repo.head(), second);
+    assert_eq!(repo.index_blob("a.txt"), Some(repo.blob("one\n")));
+
+    assert!(reset::checkout_paths(&repo.path, "HEAD", &strings(&["missing.txt"])).is_err());
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::TestRepo;
+use gitfrisky_core::domain::types::{HistorySearch, SearchDone, SearchMatch, SearchMode};
+use gitfrisky_core::search::SearchState;
+use std::sync::mpsc;
+use std::time::Duration;
+
+fn query(mode: SearchMode, pattern: &str) -> HistorySearch {
+    HistorySearch {
+        mode,
+        pattern: pattern.to_string(),
+        pickaxe_regex: false,
+        ignore_case: false,
+        range: None,
+        paths: vec![],
+        limit: None,
+    }
+}
+
+// Run a search to completion, collecting what the callbacks hear
+fn run(state: &SearchState, repo: &TestRepo, query: HistorySearch) -> (Vec<SearchMatch>, SearchDone) {
+    let (matches_tx, matches_rx) = mpsc::channel();
+    let (done_tx, done_rx) = mpsc::channel();
+    state
+        .start(&repo.path, query, move |m| matches_tx.send(m).unwrap(), move |d| done_tx.send(d).unwrap())
+        .unwrap();
+
+    let done = done_rx.recv_timeout(Duration::from_secs(30)).expect("search finished");
+    (matches_rx.try_iter().collect(), done)
+}
+
+// "needle" appears in the second commit and goes away in the fourth
+fn history(repo: &TestRepo) -> Vec<String> {
+    vec![
+        repo.commit_file("a.txt", "hay\n", "First"),
+        repo.commit_file("a.txt", "hay\nneedle\n", "Add needle"),
+        repo.commit_file("a.txt", "hay\nneedle\nmore hay\n", "More hay"),
+        repo.commit_file("a.txt", "hay\nmore hay\n", "Remove needle"),
+    ]
+}
+
+#[test]
+fn pickaxe_finds_commits_that_add_or_remove_a_string() {
+    let repo = TestRepo::new();
+    let oids = history(&repo);
+    let state = SearchState::new();
+
+    let (matches, done) = run(&state, &repo, query(SearchMode::Pickaxe, "needle"));
+    let found: Vec<_> = matches.iter().map(|m| m.commit.oid.clone()).collect();
+    assert_eq!(found, vec![oids[3].clone(), oids[1].clone()]);
+    assert_eq!(matches[0].files[0].path, "a.txt");
+    assert_eq!((done.scanned, done.matched, done.cancelled), (4, 2, false));
+    assert!(done.error.is_none());
+    assert!(matches.iter().all(|m| m.search_id == done.search_id));
+}
+
+#[test]
+fn regex_search_finds_commits_with_matching_changed_lines() {
+    let repo = TestRepo::new();
+    let oids = history(&repo);
+    let state = SearchState::new();
+
+    let (matches, _) = run(&state, &repo, query(SearchMode::Regex, "^more"));
+    let found: Vec<_> = matches.iter().map(|m| m.commit.oid.clone()).collect();
+    assert_eq!(found, vec![oids[2].clone()]);
+}
+
+#[test]
+fn invalid_patterns_are_rejected_up_front() {
+    let repo = TestRepo::new();
+    history(&repo);
+    let state = SearchState::new();
+
+    let result = state.start(&repo.path, query(SearchMode::Regex, "("), |_| {}, |_| {});
+    assert!(result.is_err());
+}
+
+#[test]
+fn cancel_stops_a_running_search() {
+    let repo = TestRepo::new();
+    for n in 0..200 {
+        repo.commit_file("a.txt", &format!("{}\n", n), &format!("Commit {}", n));
+    }
+    let state = SearchState::new();
+
+    // Block the first match until the search has been cancelled
+    let (cancelled_tx, cancelled_rx) = mpsc::channel::<()>();
+    let (done_tx, done_rx) = mpsc::channel();
+    let id = state
+        .start(
+            &repo.path,
+            query(SearchMode::Regex, "."),
+            move |_| {
+                let _ = cancelled_rx.recv();
+            },
+            move |d| done_tx.send(d).unwrap(),
+        )
+        .unwrap();
+    state.cancel(id).unwrap();
+    cancelled_tx.send(()).unwrap();
+
+    let done = done_rx.recv_timeout(Duration::from_secs(30)).expect("search finished");
+    assert_eq!(done.search_id, id);
+    assert!(done.cancelled);
+    assert!(done.matched < 200);
+}
+
+#[test]
+fn search_reports_a_long_history_in_order() {
+    let repo = TestRepo::new();
+    // More commits than the search hands out at a time; every tenth adds a needle
+    let oids: Vec<_> = (0..600)
+        .map(|n| {
+            let content = (0..=n / 10).map(|_| "needle\n").collect::<String>() + &format!("{}\n", n);
+            repo.commit_file("a.txt", &content, &format!("Commit {}", n))
+        })
+        .collect();
+    let state = SearchState::new();
+
+    let mut search = query(SearchMode::Pickaxe, "needle");
+    search.limit = Some(1000);
+    let (matches, done) = run(&state, &repo, search);
+    let found: Vec<_> = matches.iter().map(|m| m.commit.oid.clone()).collect();
+    let expected: Vec<_> = oids.iter().enumerate().rev().filter(|(n, _)| n % 10 == 0).map(|(_, oid)| oid.clone()).collect();
+    assert_eq!(found, expected);
+    assert_eq!((done.scanned, done.matched), (600, 60));
+}
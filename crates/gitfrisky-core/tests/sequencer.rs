@@ -0,0 +1,208 @@
+mod common;
+
+use common::{diverged, strings, TestRepo};
+use gitfrisky_core::domain::types::{GitError, PickOptions, SequenceKind};
+use gitfrisky_core::{conflict, repo, sequencer};
+
+// `topic` adds two files on top of `base`; `main` adds another. Returns
+// topic's commits with `main` checked out.
+fn picks(repo: &TestRepo) -> Vec<String> {
+    let base = repo.commit_file("a.txt", "a\n", "Base");
+    repo.branch("topic", &base);
+    repo.checkout("topic");
+    let first = repo.commit_file("b.txt", "b\n", "Add b");
+    let second = repo.commit_file("c.txt", "c\n", "Add c");
+    repo.checkout("main");
+    repo.commit_file("d.txt", "d\n", "Add d");
+    vec![first, second]
+}
+
+// A clean pick followed by one that conflicts with `main`, which is checked out
+fn picks_after_conflict(repo: &TestRepo) -> Vec<String> {
+    let base = repo.head();
+    repo.branch("topic", &base);
+    repo.checkout("topic");
+    let clean = repo.commit_file("b.txt", "b\n", "Clean");
+    let conflicting = repo.commit_file("a.txt", "one\ntheirs\nthree\n", "Conflicting");
+    repo.checkout("main");
+    repo.commit_file("a.txt", "one\nours\nthree\n", "Ours");
+    vec![clean, conflicting]
+}
+
+#[test]
+fn cherry_pick_applies_commits_in_order() {
+    let repo = TestRepo::new();
+    let oids = picks(&repo);
+
+    let created = sequencer::cherry_pick(&repo.path, &oids, None).unwrap();
+    let summaries: Vec<_> = created.iter().map(|c| c.summary.as_str()).collect();
+    assert_eq!(summaries, vec!["Add b", "Add c"]);
+    assert_eq!(repo.head(), created[1].oid);
+    assert_eq!(repo.read("b.txt"), "b\n");
+    assert!(!sequencer::sequencer_status(&repo.path).unwrap().in_progress);
+}
+
+#[test]
+fn cherry_pick_records_the_origin_and_can_leave_changes_staged() {
+    let repo = TestRepo::new();
+    let oids = picks(&repo);
+
+    let options = PickOptions { record_origin: true, ..Default::default() };
+    let created = sequencer::cherry_pick(&repo.path, &oids[..1], Some(options)).unwrap();
+    let message = repo.repo.find_commit(git2::Oid::from_str(&created[0].oid).unwrap()).unwrap();
+    assert!(message.message().unwrap().contains(&format!("(cherry picked from commit {})", oids[0])));
+
+    let head = repo.head();
+    let options = PickOptions { no_commit: true, ..Default::default() };
+    let created = sequencer::cherry_pick(&repo.path, &oids[1..], Some(options)).unwrap();
+    assert!(created.is_empty());
+    assert_eq!(repo.head(), head);
+    let staged = repo::status(&repo.path).unwrap().staged;
+    assert_eq!(staged.iter().map(|c| c.path.as_str()).collect::<Vec<_>>(), vec!["c.txt"]);
+}
+
+#[test]
+fn revert_undoes_a_commit() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    let change = repo.commit_file("a.txt", "two\n", "Change");
+
+    let created = sequencer::revert(&repo.path, &[change], None).unwrap();
+    assert_eq!(created.len(), 1);
+    assert!(created[0].summary.starts_with("Revert \"Change\""));
+    assert_eq!(repo.read("a.txt"), "one\n");
+}
+
+#[test]
+fn sequencer_continue_commits_a_resolved_conflict_and_finishes() {
+    let repo = TestRepo::new();
+    let (_, _, theirs) = diverged(&repo);
+    repo.checkout("topic");
+    let after = repo.commit_file("b.txt", "b\n", "After");
+    repo.checkout("main");
+
+    let result = sequencer::cherry_pick(&repo.path, &[theirs, after], None);
+    assert!(matches!(result, Err(GitError::MergeConflict(_))));
+
+    let status = sequencer::sequencer_status(&repo.path).unwrap();
+    assert!(status.in_progress);
+    assert_eq!(status.kind, Some(SequenceKind::CherryPick));
+    assert_eq!(status.remaining.len(), 2);
+    assert_eq!(status.conflicts, vec!["a.txt"]);
+    assert!(sequencer::sequencer_continue(&repo.path).is_err());
+
+    repo.write("a.txt", "one\nboth\nthree\n");
+    conflict::mark_resolved(&repo.path, &strings(&["a.txt"])).unwrap();
+    let created = sequencer::sequencer_continue(&repo.path).unwrap();
+
+    let summaries: Vec<_> = created.iter().map(|c| c.summary.as_str()).collect();
+    assert_eq!(summaries, vec!["Theirs", "After"]);
+    assert!(!sequencer::sequencer_status(&repo.path).unwrap().in_progress);
+    assert_eq!(repo.read("a.txt"), "one\nboth\nthree\n");
+}
+
+#[test]
+fn sequencer_skip_moves_past_the_conflicting_step() {
+    let repo = TestRepo::new();
+    let (_, ours, theirs) = diverged(&repo);
+    repo.checkout("topic");
+    let after = repo.commit_file("b.txt", "b\n", "After");
+    repo.checkout("main");
+
+    assert!(sequencer::cherry_pick(&repo.path, &[theirs, after], None).is_err());
+    let created = sequencer::sequencer_skip(&repo.path).unwrap();
+
+    assert_eq!(created.len(), 1);
+    assert_eq!(created[0].parents, vec![ours]);
+    assert_eq!(repo.read("a.txt"), "one\nours\nthree\n");
+    assert!(!sequencer::sequencer_status(&repo.path).unwrap().in_progress);
+}
+
+#[test]
+fn sequencer_abort_returns_to_where_the_sequence_started() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\ntwo\nthree\n", "Base");
+    let oids = picks_after_conflict(&repo);
+    let start = repo.head();
+
+    assert!(sequencer::cherry_pick(&repo.path, &oids, None).is_err());
+    sequencer::sequencer_abort(&repo.path).unwrap();
+
+    assert_eq!(repo.head(), start);
+    assert_eq!(repo.head_name().as_deref(), Some("refs/heads/main"));
+    assert!(!repo.file("b.txt").exists());
+    assert!(!sequencer::sequencer_status(&repo.path).unwrap().in_progress);
+}
+
+#[test]
+fn cherry_pick_refuses_a_dirty_working_tree() {
+    let repo = TestRepo::new();
+    let oids = picks(&repo);
+    repo.write("a.txt", "dirty\n");
+
+    assert!(sequencer::cherry_pick(&repo.path, &oids, None).is_err());
+    assert!(!sequencer::sequencer_status(&repo.path).unwrap().in_progress);
+}
+
+#[test]
+fn sequencer_abort_names_a_revert_in_the_reflog() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    let first = repo.commit_file("a.txt", "two\n", "First");
+    repo.commit_file("a.txt", "three\n", "Second");
+    let other = repo.commit_file("b.txt", "b\n", "Other");
+    let start = repo.head();
+
+    // `other` reverts cleanly, then `first` conflicts with `Second`
+    assert!(sequencer::revert(&repo.path, &[other, first], None).is_err());
+    assert!(!repo.file("b.txt").exists());
+    sequencer::sequencer_abort(&repo.path).unwrap();
+
+    assert_eq!(repo.head(), start);
+    let reflog = repo.repo.reflog("refs/heads/main").unwrap();
+    assert_eq!(reflog.get(0).unwrap().message(), Some("revert: abort"));
+}
+
+#[test]
+fn sequencer_abort_refuses_when_nothing_is_in_progress() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("a.txt", "edited\n");
+
+    assert!(sequencer::sequencer_abort(&repo.path).is_err());
+    assert_eq!(repo.read("a.txt"), "edited\n");
+}
+
+#[test]
+fn sequencer_skip_refuses_when_nothing_is_in_progress() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("a.txt", "edited\n");
+    repo.write("new.txt", "new\n");
+    repo::stage(&repo.path, &strings(&["new.txt"])).unwrap();
+
+    assert!(sequencer::sequencer_skip(&repo.path).is_err());
+    assert_eq!(repo.read("a.txt"), "edited\n");
+    assert_eq!(repo.index_blob("new.txt"), Some(repo.blob("new\n")));
+}
+
+#[test]
+fn origin_line_follows_a_blank_line_after_a_conventional_subject() {
+    let repo = TestRepo::new();
+    let base = repo.commit_file("a.txt", "a\n", "Base");
+    repo.branch("topic", &base);
+    repo.checkout("topic");
+    let docs = repo.commit_file("b.txt", "b\n", "docs: update readme");
+    let signed = repo.commit_file("c.txt", "c\n", "Add c\n\nSigned-off-by: Test User <test@example.com>");
+    repo.checkout("main");
+
+    let options = PickOptions { record_origin: true, ..Default::default() };
+    let created = sequencer::cherry_pick(&repo.path, &[docs.clone(), signed.clone()], Some(options)).unwrap();
+    let message = |oid: &str| repo.repo.find_commit(git2::Oid::from_str(oid).unwrap()).unwrap().message().unwrap().to_string();
+
+    assert_eq!(message(&created[0].oid), format!("docs: update readme\n\n(cherry picked from commit {})\n", docs));
+    assert_eq!(
+        message(&created[1].oid),
+        format!("Add c\n\nSigned-off-by: Test User <test@example.com>\n(cherry picked from commit {})\n", signed)
+    );
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::{strings, TestRepo};
+use gitfrisky_core::operations::{finished_operations, RepoLayout};
+use gitfrisky_core::repo;
+use gitfrisky_core::status_cache::StatusCache;
+
+fn unstaged(cache: &StatusCache) -> Vec<String> {
+    cache.payload().unstaged.into_iter().map(|c| c.path).collect()
+}
+
+fn cache(repo: &TestRepo) -> StatusCache {
+    let layout = RepoLayout::resolve(&repo.repo);
+    StatusCache::scan(&repo.repo, layout.git_dir, finished_operations()).unwrap()
+}
+
+#[test]
+fn scan_matches_a_full_status() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("a.txt", "two\n");
+    repo.write("b.txt", "b\n");
+
+    let cache = cache(&repo);
+    let status = repo::status(&repo.path).unwrap();
+    let expected: Vec<_> = status.unstaged.into_iter().map(|c| c.path).collect();
+    assert_eq!(unstaged(&cache), expected);
+}
+
+#[test]
+fn update_rechecks_only_the_reported_paths() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("a.txt", "two\n");
+    let mut cache = cache(&repo);
+
+    repo.write("a.txt", "one\n");
+    repo.write("dir/new.txt", "new\n");
+    repo.write("unreported.txt", "?\n");
+    let delta = cache.update(&repo.repo, &strings(&["a.txt", "dir"]), finished_operations()).unwrap();
+
+    assert_eq!(delta.paths, vec!["a.txt", "dir/new.txt"]);
+    assert_eq!(delta.unstaged.iter().map(|c| c.path.as_str()).collect::<Vec<_>>(), vec!["dir/new.txt"]);
+    assert_eq!(unstaged(&cache), vec!["dir/new.txt"]);
+}
+
+#[test]
+fn rescan_reports_everything_that_changed() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("a.txt", "two\n");
+    let mut cache = cache(&repo);
+
+    repo.write("a.txt", "one\n");
+    repo.write("b.txt", "b\n");
+    let delta = cache.rescan(&repo.repo, finished_operations()).unwrap();
+
+    assert_eq!(delta.paths, vec!["a.txt", "b.txt"]);
+    assert_eq!(unstaged(&cache), vec!["b.txt"]);
+}
+
+#[test]
+fn update_clears_files_under_a_directory_that_went_away() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    repo.write("dir/one.txt", "1\n");
+    repo.write("dir/two.txt", "2\n");
+    let mut cache = cache(&repo);
+    assert_eq!(unstaged(&cache), vec!["dir/one.txt", "dir/two.txt"]);
+
+    std::fs::remove_dir_all(repo.file("dir")).unwrap();
+    let delta = cache.update(&repo.repo, &strings(&["dir"]), finished_operations()).unwrap();
+
+    assert_eq!(delta.paths, vec!["dir/one.txt", "dir/two.txt"]);
+    assert!(delta.unstaged.is_empty());
+    assert!(unstaged(&cache).is_empty());
+}
+
+#[test]
+fn update_with_too_many_paths_rescans_everything() {
+    let repo = TestRepo::new();
+    repo.commit_file("a.txt", "one\n", "Initial");
+    let mut cache = cache(&repo);
+
+    // Not among the reported paths, but a full rescan finds it anyway
+    repo.write("unreported.txt", "?\n");
+    let paths: Vec<String> = (0..1001).map(|n| format!("missing-{}.txt", n)).collect();
+    let delta = cache.update(&repo.repo, &paths, finished_operations()).unwrap();
+
+    assert_eq!(delta.paths, vec!["unreported.txt"]);
+    assert_eq!(unstaged(&cache), vec!["unreported.txt"]);
+}
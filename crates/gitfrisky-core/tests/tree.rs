@@ -0,0 +1,74 @@
+mod common;
+
+use common::TestRepo;
+use gitfrisky_core::domain::types::TreeEntryKind;
+use gitfrisky_core::tree;
+
+fn project(repo: &TestRepo) {
+    repo.write("README.md", "hello\n");
+    repo.write("src/main.rs", "fn main() {}\n");
+    repo.write("src/lib/mod.rs", "\n");
+    repo.write("assets/logo.bin", "\0\x01\x02");
+    repo.commit_all("Initial");
+}
+
+#[test]
+fn list_tree_lists_directories_first_then_files() {
+    let repo = TestRepo::new();
+    project(&repo);
+
+    let root = tree::list_tree(&repo.path, None, None).unwrap();
+    let names: Vec<_> = root.iter().map(|e| (e.name.as_str(), e.kind)).collect();
+    assert_eq!(
+        names,
+        vec![("assets", TreeEntryKind::Directory), ("src", TreeEntryKind::Directory), ("README.md", TreeEntryKind::File)]
+    );
+    assert_eq!(root[2].size, Some(6));
+    assert_eq!(root[0].size, None);
+
+    let src = tree::list_tree(&repo.path, Some("HEAD"), Some("src/")).unwrap();
+    let paths: Vec<_> = src.iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(paths, vec!["src/lib", "src/main.rs"]);
+}
+
+#[test]
+fn list_tree_rejects_missing_paths_and_files() {
+    let repo = TestRepo::new();
+    project(&repo);
+
+    assert!(tree::list_tree(&repo.path, None, Some("nope")).is_err());
+    assert!(tree::list_tree(&repo.path, None, Some("README.md")).is_err());
+}
+
+#[test]
+fn read_blob_returns_file_content_at_a_revision() {
+    let repo = TestRepo::new();
+    project(&repo);
+    repo.commit_file("README.md", "hello again\n", "Update");
+
+    let blob = tree::read_blob(&repo.path, None, "README.md", None).unwrap();
+    assert_eq!(blob.content.as_deref(), Some("hello again\n"));
+    assert!(!blob.is_binary && !blob.truncated);
+
+    let old = tree::read_blob(&repo.path, Some("HEAD~1"), "README.md", None).unwrap();
+    assert_eq!(old.content.as_deref(), Some("hello\n"));
+}
+
+#[test]
+fn read_blob_flags_binary_and_truncated_files() {
+    let repo = TestRepo::new();
+    project(&repo);
+
+    let binary = tree::read_blob(&repo.path, None, "assets/logo.bin", None).unwrap();
+    assert!(binary.is_binary);
+    assert_eq!(binary.content, None);
+    assert_eq!(binary.size, 3);
+
+    let truncated = tree::read_blob(&repo.path, None, "README.md", Some(2)).unwrap();
+    assert!(truncated.truncated);
+    assert_eq!(truncated.content.as_deref(), Some("he"));
+    assert_eq!(truncated.size, 6);
+
+    assert!(tree::read_blob(&repo.path, None, "src", None).is_err());
+    assert!(tree::read_blob(&repo.path, None, "missing.txt", None).is_err());
+}
@@ -30,6 +30,14 @@ export type FileChange = {
   deletions: number;
 };
 
+// Pushed as `status-changed`: the entries of `paths` are replaced by the ones
+// listed here (none for files that are now clean)
+export type StatusDelta = {
+  paths: string[];
+  unstaged: FileChange[];
+  staged: FileChange[];
+};
+
 export type LineType = 'context' | 'addition' | 'deletion';
 
 export type DiffLine = {
@@ -37,8 +45,17 @@ export type DiffLine = {
   lineType: LineType;
   oldLineno?: number;
   newLineno?: number;
+  changes?: ChangeRange[]; // Changed spans, when intra-line highlighting was requested
+};
+
+// UTF-16 offsets, so they slice the content string directly
+export type ChangeRange = {
+  start: number;
+  end: number;
 };
 
+export type IntralineMode = 'word' | 'char';
+
 export type DiffHunk = {
   header: string;
   oldStart: number;
@@ -46,6 +63,266 @@ export type DiffHunk = {
   newStart: number;
   newLines: number;
   lines: DiffLine[];
+  truncated: boolean; // Cut off by the line limit; can't be staged until the rest is loaded
+};
+
+// libgit2 has no histogram diff; patience is used instead
+export type DiffAlgorithm = 'myers' | 'minimal' | 'patience' | 'histogram';
+
+export type RenameDetection = 'off' | 'renames' | 'copies';
+
+// Unset fields follow the repository's diff.algorithm and diff.renames config
+export type DiffViewOptions = {
+  ignoreAllSpace?: boolean;
+  ignoreSpaceAtEol?: boolean;
+  ignoreSpaceChange?: boolean;
+  ignoreBlankLines?: boolean;
+  contextLines?: number;
+  algorithm?: DiffAlgorithm;
+  renames?: RenameDetection;
+};
+
+export type FileDiff = {
+  path: string;
+  oldPath?: string; // Staged renames and copies: where the file came from
+  isBinary: boolean; // No lines; compare sizes and hashes instead
+  tooLarge: boolean; // Over the size limit, so not diffed at all
+  oldOid?: string;
+  newOid?: string;
+  oldSize?: number;
+  newSize?: number;
+  encoding: string;
+  algorithm: DiffAlgorithm; // Algorithm that produced the hunks; 'histogram' is served by 'patience'
+  hunks: DiffHunk[];
+  totalLines: number;
+  truncated: boolean; // Request again with a higher maxLines to load more
+};
+
+export type ConflictStyle = 'merge' | 'diff3' | 'zdiff3';
+
+export type ConflictSide = {
+  oid: string;
+  mode: number;
+  size: number;
+  isBinary: boolean;
+  content?: string;
+};
+
+export type ConflictRegion =
+  | { kind: 'resolved'; text: string }
+  | { kind: 'conflict'; index: number; ours: string; base?: string; theirs: string };
+
+export type ConflictFile = {
+  path: string;
+  base?: ConflictSide;
+  ours?: ConflictSide;
+  theirs?: ConflictSide;
+  style: ConflictStyle;
+  regions: ConflictRegion[];
+};
+
+export type ConflictChoice = 'ours' | 'theirs' | 'base' | 'union';
+
+export type ConflictResolution =
+  | { type: 'file'; choice: ConflictChoice }
+  | { type: 'regions'; choices: ConflictChoice[]; style?: ConflictStyle }; // style the regions were fetched in
+
+export type RebaseAction = 'pick' | 'reword' | 'edit' | 'squash' | 'fixup' | 'drop' | 'exec';
+
+export type RebaseTodoEntry = {
+  action: RebaseAction;
+  oid?: string;
+  summary?: string;
+  message?: string;
+  command?: string;
+};
+
+export type RebaseStopReason = 'conflict' | 'edit' | 'reword' | 'execFailed' | 'empty';
+
+export type RebaseStatus = {
+  inProgress: boolean;
+  headName?: string;
+  onto?: string;
+  origHead?: string;
+  done: RebaseTodoEntry[];
+  remaining: RebaseTodoEntry[];
+  stopped?: RebaseStopReason;
+  message?: string;
+  conflicts: string[];
+};
+
+export type PickOptions = {
+  noCommit?: boolean;
+  recordOrigin?: boolean;
+  mainline?: number;
+};
+
+export type SequenceKind = 'cherryPick' | 'revert';
+
+export type SequenceStatus = {
+  inProgress: boolean;
+  kind?: SequenceKind;
+  remaining: string[];
+  conflicts: string[];
+};
+
+export type ResetMode = 'soft' | 'mixed' | 'hard';
+
+export type ResetResult = {
+  applied: boolean;
+  previousHead: string;
+  newHead: string;
+  dirtyFiles: string[];
+};
+
+export type JournalEntry = {
+  id: number;
+  label: string;
+  timestamp: number;
+};
+
+export type JournalState = {
+  undo: JournalEntry[]; // Most recent last
+  redo: JournalEntry[];
+};
+
+export type ReflogEntry = {
+  oldOid: string;
+  newOid: string;
+  message?: string;
+  committer: string;
+  timestamp: number;
+  commit?: Commit; // Missing if the commit was garbage collected
+};
+
+export type DanglingCommit = {
+  commit: Commit;
+  reflog?: string; // Reflog still recording it, e.g. "HEAD"; missing if only the object database has it
+};
+
+export type BisectMark = 'good' | 'bad' | 'skip';
+
+export type BisectStatus = {
+  inProgress: boolean;
+  bad?: string;
+  good: string[];
+  skipped: string[];
+  pathspec: string[];
+  current?: string; // Commit checked out for testing
+  remaining: Commit[];
+  stepsLeft: number;
+  firstBad?: Commit;
+};
+
+// Payload of the 'bisect-step' event emitted during bisectRun
+export type BisectStep = {
+  oid: string;
+  summary: string;
+  mark: BisectMark;
+  exitCode?: number;
+};
+
+export type BlameOptions = {
+  ignoreWhitespace?: boolean;
+  ignoreRevsFile?: boolean; // Honour blame.ignoreRevsFile
+  ignoreRevs?: string[];
+};
+
+export type BlameRange = {
+  startLine: number; // 1-based
+  lineCount: number;
+  oid: string;
+  author: string;
+  email: string;
+  timestamp: number;
+  summary: string;
+  origPath: string;
+  origStartLine: number;
+  unblamable: boolean;
+};
+
+export type BlameResult = {
+  path: string;
+  oid: string;
+  lines: string[];
+  ranges: BlameRange[];
+};
+
+export type FileHistoryEntry = {
+  commit: Commit;
+  path: string; // Name of the file at this commit
+  oldPath?: string; // Previous name when this commit renamed it
+  status: FileStatus;
+  hunks: DiffHunk[];
+};
+
+export type SearchMode = 'pickaxe' | 'regex';
+
+export type HistorySearch = {
+  mode: SearchMode;
+  pattern: string;
+  pickaxeRegex?: boolean;
+  ignoreCase?: boolean;
+  range?: string; // "a..b" or a single revision; defaults to HEAD
+  paths?: string[];
+  limit?: number;
+};
+
+export type SearchFileMatch = {
+  path: string;
+  hunks: DiffHunk[];
+};
+
+// Payload of 'history-search-match' events
+export type SearchMatch = {
+  searchId: number;
+  commit: Commit;
+  files: SearchFileMatch[];
+};
+
+// Payload of the 'history-search-done' event
+export type SearchDone = {
+  searchId: number;
+  scanned: number;
+  matched: number;
+  cancelled: boolean;
+  error?: string;
+};
+
+export type TreeEntryKind = 'file' | 'executable' | 'symlink' | 'directory' | 'submodule';
+
+export type TreeEntry = {
+  name: string;
+  path: string;
+  oid: string;
+  mode: number;
+  kind: TreeEntryKind;
+  size?: number; // Blobs only
+};
+
+export type BlobContent = {
+  path: string;
+  oid: string;
+  size: number;
+  isBinary: boolean;
+  encoding: string;
+  content?: string; // Missing for binary files
+  truncated: boolean;
+};
+
+export type WatchBackend = 'native' | 'poll';
+
+export type WatchOptions = {
+  backend?: WatchBackend; // Native unless the filesystem or OS limits rule it out
+  pollIntervalMs?: number;
+};
+
+// Also sent as the `watcher-status` event whenever watching starts or degrades
+export type WatcherStatus = {
+  backend: WatchBackend;
+  pollIntervalMs?: number;
+  degraded: boolean; // Polling when native events were wanted
+  reason?: string;
 };
 
 export type RepoState = 'clean' | 'merge' | 'rebase' | 'rebaseInteractive' | 'rebaseMerge' | 'revert' | 'cherryPick' | 'bisect';
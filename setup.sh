@@ -11,8 +11,9 @@ echo "▶ git-frisky structure — mode: $MODE"
 
 dirs=(
   "apps/desktop/src-tauri/src/api"
-  "apps/desktop/src-tauri/src/domain"
-  "apps/desktop/src-tauri/src/util"
+  "crates/gitfrisky-core/src/domain"
+  "crates/gitfrisky-core/src/util"
+  "crates/gitfrisky-core/tests"
   "apps/ui/src/components"
   "apps/ui/src/stores"
   "apps/ui/src/lib"